@@ -0,0 +1,86 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Agent;
+
+/// AgentRow mirrors a row of the agents table
+struct AgentRow {
+    id: Uuid,
+    name: String,
+    location: String,
+    created_at: NaiveDateTime,
+    last_seen_at: Option<NaiveDateTime>,
+}
+
+impl From<AgentRow> for Agent {
+    fn from(row: AgentRow) -> Self {
+        Agent {
+            id: row.id,
+            name: row.name,
+            location: row.location,
+            created_at: row.created_at.and_utc(),
+            last_seen_at: row.last_seen_at.map(|l| l.and_utc()),
+        }
+    }
+}
+
+/// Create an agent identified by the hash of its token
+pub async fn create(pool: &PgPool, name: &str, location: &str, token_hash: &str) -> Result<Agent, sqlx::Error> {
+    let row = sqlx::query_as!(
+        AgentRow,
+        r#"
+        INSERT INTO agents (name, location, token_hash)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, location, created_at, last_seen_at
+        "#,
+        name,
+        location,
+        token_hash
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Find the agent owning a token hash, marking it as seen
+pub async fn touch(pool: &PgPool, token_hash: &str) -> Result<Option<Agent>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        AgentRow,
+        r#"
+        UPDATE agents SET last_seen_at = NOW()
+        WHERE token_hash = $1
+        RETURNING id, name, location, created_at, last_seen_at
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Agent::from))
+}
+
+pub async fn list(pool: &PgPool) -> Result<Vec<Agent>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        AgentRow,
+        r#"
+        SELECT id, name, location, created_at, last_seen_at
+        FROM agents
+        ORDER BY location, name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Agent::from).collect())
+}
+
+/// Delete an agent, revoking its token. Returns whether the agent existed.
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query!("DELETE FROM agents WHERE id = $1", id)
+        .execute(pool)
+        .await?;
+
+    Ok(deleted.rows_affected() > 0)
+}
@@ -0,0 +1,30 @@
+pub mod db;
+pub mod models;
+pub mod runner;
+
+pub use models::*;
+pub use runner::Runner;
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// Register an agent at a location. Returns the agent and its token, which is not stored.
+pub async fn register(pool: &PgPool, name: &str, location: &str) -> Result<(Agent, String), anyhow::Error> {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+
+    let agent = db::create(pool, name, location, &hash_token(&token)).await?;
+    Ok((agent, token))
+}
+
+/// The agent a token belongs to, if any
+pub async fn authenticate(pool: &PgPool, token: &str) -> Result<Option<Agent>, anyhow::Error> {
+    Ok(db::touch(pool, &hash_token(token)).await?)
+}
+
+/// Tokens are random, so a plain hash is enough to keep them out of the database
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rstat_core::{HealthCheckResult, Kind, Schedule, Service, ServiceOptions, ServiceState};
+
+/// Agent is a remote process checking services from its own location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agent {
+    pub id: Uuid,
+    pub name: String,
+    /// Where the agent runs, services are assigned to agents by location.
+    pub location: String,
+    pub created_at: DateTime<Utc>,
+    /// When the agent last called the server.
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+/// Reports the server accepts in a single push
+pub const MAX_REPORTS_PER_PUSH: usize = 100;
+
+/// Report is the outcome of a check performed by an agent, pushed to the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub service_id: Uuid,
+    /// The check result, or why the check could not be performed.
+    pub result: Result<HealthCheckResult, String>,
+}
+
+/// AssignedService is what an agent is told about a service it checks: how and when to
+/// check it, without the service's notification channels, objectives or state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignedService {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: Kind,
+    pub interval: Duration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+}
+
+impl From<Service> for AssignedService {
+    fn from(service: Service) -> Self {
+        Self {
+            id: service.id,
+            name: service.name,
+            kind: service.kind,
+            interval: service.interval,
+            schedule: service.options.schedule,
+        }
+    }
+}
+
+/// The agent keeps its schedule in services, due as soon as they are assigned
+impl From<AssignedService> for Service {
+    fn from(service: AssignedService) -> Self {
+        Service {
+            id: service.id,
+            name: service.name,
+            kind: service.kind,
+            interval: service.interval,
+            next_run: DateTime::<Utc>::MIN_UTC,
+            options: ServiceOptions { schedule: service.schedule, ..ServiceOptions::default() },
+            state: ServiceState::default(),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use reqwest::StatusCode;
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use rstat_core::{HealthCheckRequest, Service};
+
+use crate::models::{AssignedService, Report, MAX_REPORTS_PER_PUSH};
+
+/// How often the agent asks the server for the services it should check
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reports kept while the server is unreachable, the oldest are dropped past this
+const MAX_PENDING: usize = 10_000;
+
+/// Runner checks the services assigned to an agent and pushes the results to the server.
+///
+/// The agent has no database: it keeps its own schedule in memory, and reports that
+/// could not be delivered are retried on the next tick.
+pub struct Runner {
+    client: reqwest::Client,
+    server: String,
+    token: String,
+    services: HashMap<Uuid, Service>,
+    pending: VecDeque<Report>,
+}
+
+impl Runner {
+    pub fn new(server: &str, token: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            server: server.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            services: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub async fn run(mut self) {
+        info!("Starting agent, reporting to {}", self.server);
+        let mut refreshed_at: Option<Instant> = None;
+
+        loop {
+            if refreshed_at.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL) {
+                match self.fetch_services().await {
+                    Ok(services) => {
+                        schedule(&mut self.services, services, Utc::now());
+                        refreshed_at = Some(Instant::now());
+                    }
+                    Err(err) => error!("Failed to fetch services from {}: {}", self.server, err),
+                }
+            }
+
+            self.run_due_checks().await;
+            self.push_reports().await;
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    async fn fetch_services(&self) -> Result<Vec<Service>, anyhow::Error> {
+        let response = self
+            .client
+            .get(format!("{}/agent/services", self.server))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(anyhow::anyhow!("The server rejected the agent token"));
+        }
+
+        let services: Vec<AssignedService> = response.error_for_status()?.json().await?;
+        debug!("Agent is assigned {} services", services.len());
+        Ok(services.into_iter().map(Service::from).collect())
+    }
+
+    async fn run_due_checks(&mut self) {
+        let now = Utc::now();
+        let due: Vec<Service> = self
+            .services
+            .values_mut()
+            .filter(|service| service.next_run <= now)
+            .map(|service| {
+                // Services with an interval out of range are not scheduled
+                service.next_run = next_run(service, now).unwrap_or(DateTime::<Utc>::MAX_UTC);
+                service.clone()
+            })
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+
+        info!("Running {} due healthchecks", due.len());
+        let reports = join_all(due.into_iter().map(|service| async move {
+            let result = rstat_healthcheck::perform_check(HealthCheckRequest::new(service.clone())).await;
+            if let Err(err) = &result {
+                warn!("Healthcheck failed for service {} with error: {}", service.name, err);
+            }
+            Report {
+                service_id: service.id,
+                result: result.map_err(|err| err.to_string()),
+            }
+        }))
+        .await;
+
+        self.pending.extend(reports);
+        if self.pending.len() > MAX_PENDING {
+            let dropped = self.pending.len() - MAX_PENDING;
+            warn!("Dropping {} undelivered reports", dropped);
+            self.pending.drain(..dropped);
+        }
+    }
+
+    /// Push pending reports, oldest first, until the server stops accepting them
+    async fn push_reports(&mut self) {
+        while !self.pending.is_empty() {
+            let count = self.pending.len().min(MAX_REPORTS_PER_PUSH);
+            let batch: Vec<&Report> = self.pending.range(..count).collect();
+            let response = self
+                .client
+                .post(format!("{}/agent/results", self.server))
+                .bearer_auth(&self.token)
+                .json(&batch)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match response {
+                Ok(_) => {
+                    debug!("Pushed {} reports", count);
+                    self.pending.drain(..count);
+                }
+                Err(err) => {
+                    warn!("Failed to push {} reports, retrying later: {}", self.pending.len(), err);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Replace the scheduled services with the ones assigned by the server.
+///
+/// Services already scheduled keep their next run, new ones are due right away or once
+/// their schedule allows.
+fn schedule(scheduled: &mut HashMap<Uuid, Service>, services: Vec<Service>, now: DateTime<Utc>) {
+    let mut next = HashMap::with_capacity(services.len());
+    for mut service in services {
+        if next_run(&service, now).is_none() {
+            warn!("Skipping service {}, its interval of {:?} is out of range", service.name, service.interval);
+            continue;
+        }
+        let first_run = service.options.schedule.as_ref().and_then(|schedule| schedule.first_run(now)).unwrap_or(now);
+        service.next_run = scheduled.get(&service.id).map_or(first_run, |s| s.next_run);
+        next.insert(service.id, service);
+    }
+    *scheduled = next;
+}
+
+/// When a service checked at `now` is due again, None when its interval is out of range
+fn next_run(service: &Service, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let interval = now.checked_add_signed(chrono::Duration::from_std(service.interval).ok()?)?;
+    // The server rejects schedules that never match, the interval is only a fallback
+    Some(match &service.options.schedule {
+        Some(schedule) => schedule.next_run(now, service.interval).unwrap_or(interval),
+        None => interval,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstat_core::{Kind, ServiceOptions, ServiceState, TcpChecker};
+
+    fn service(name: &str) -> Service {
+        Service {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            kind: Kind::TCP(TcpChecker::default()),
+            interval: Duration::from_secs(30),
+            next_run: DateTime::<Utc>::MIN_UTC,
+            options: ServiceOptions {
+                locations: vec!["eu-west".to_string()],
+                ..ServiceOptions::default()
+            },
+            state: ServiceState::default(),
+        }
+    }
+
+    #[test]
+    fn test_schedule_keeps_next_run_of_known_services() {
+        let now = Utc::now();
+        let (kept, removed, added) = (service("kept"), service("removed"), service("added"));
+
+        let mut scheduled = HashMap::new();
+        schedule(&mut scheduled, vec![kept.clone(), removed.clone()], now);
+        scheduled.get_mut(&kept.id).unwrap().next_run = now + chrono::Duration::seconds(30);
+
+        schedule(&mut scheduled, vec![kept.clone(), added.clone()], now);
+
+        assert_eq!(scheduled.len(), 2);
+        assert_eq!(scheduled[&kept.id].next_run, now + chrono::Duration::seconds(30));
+        assert_eq!(scheduled[&added.id].next_run, now);
+        assert!(!scheduled.contains_key(&removed.id));
+    }
+
+    #[test]
+    fn test_schedule_skips_intervals_out_of_range() {
+        let mut endless = service("endless");
+        endless.interval = Duration::MAX;
+
+        let mut scheduled = HashMap::new();
+        schedule(&mut scheduled, vec![endless], Utc::now());
+
+        assert!(scheduled.is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 use std::time::Duration;
 
-use axum::{extract::State, routing::{get}, Json, Router, extract::Path, extract::Query};
+use axum::{extract::State, routing::{get, post}, Json, Router, extract::Path, extract::Query};
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
+use sha2::Sha256;
 use http::StatusCode;
 use sqlx::PgPool;
 use serde::Deserialize;
@@ -10,23 +13,42 @@ use tower_http::{
     trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer},
     cors::{CorsLayer, Any},
 };
-use tracing::{error, Level};
+use tracing::{error, info, warn, Level};
 
-use rstat_core::Service;
-use rstat_service;
-use rstat_healthcheck;
-use rstat_metrics::{ServiceMetric, ServiceMetricsSummary, MetricsCalculator};
+use rstat_agent::{Agent, AssignedService, Report, MAX_REPORTS_PER_PUSH};
+use rstat_core::{LocationLatency, Service, ServiceStatus};
+use rstat_incidents::{Incident, IncidentDetails, IncidentManager, IncidentStatus};
+use rstat_maintenance::{MaintenanceSettings, MaintenanceWindow};
+use rstat_slo::{SloEvaluator, SloReport};
+use rstat_metrics::{MetricRollup, Resolution, ServiceMetric, ServiceMetricsSummary, MetricsCalculator};
+use rstat_scheduler::Scheduler;
 
 pub mod types;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
+    /// Secret used to verify PagerDuty webhook signatures. PagerDuty webhooks are rejected when unset.
+    pub pagerduty_webhook_secret: Option<String>,
+    /// Processes the results reported by agents like the ones of local checks.
+    pub scheduler: Scheduler,
 }
 
 #[derive(Deserialize)]
 pub struct MetricsQuery {
     days: Option<u32>,
+    resolution: Option<Resolution>,
+}
+
+#[derive(Deserialize)]
+pub struct IncidentsQuery {
+    status: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct LocationsQuery {
+    hours: Option<i64>,
 }
 
 #[derive(Serialize)]
@@ -35,6 +57,14 @@ pub struct ServiceWithMetricsSummary {
     pub metrics_summary: ServiceMetricsSummary,
 }
 
+/// Metrics of a service: daily metrics by default, rollups when a resolution is requested
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ServiceMetrics {
+    Daily(Vec<ServiceMetric>),
+    Rollups(Vec<MetricRollup>),
+}
+
 pub async fn create_server(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -48,6 +78,26 @@ pub async fn create_server(state: AppState) -> Router {
                 .delete(delete_http_check)
         )
         .route("/http/checks/{id}", get(get_checks_for_service))
+        .route("/services/{id}", get(get_service))
+        .route("/services/{id}/pause", post(pause_service))
+        .route("/services/{id}/resume", post(resume_service))
+        .route("/services/{id}/incidents", get(list_service_incidents))
+        .route("/services/{id}/slo", get(get_service_slo))
+        .route("/services/{id}/locations", get(get_service_locations))
+        .route("/agents", get(list_agents))
+        .route("/agent/services", get(list_agent_services))
+        .route("/agent/results", post(ingest_agent_results))
+        .route("/maintenance", get(list_maintenance_windows).post(create_maintenance_window))
+        .route(
+            "/maintenance/{id}",
+            get(get_maintenance_window)
+                .put(update_maintenance_window)
+                .delete(delete_maintenance_window),
+        )
+        .route("/incidents", get(list_incidents))
+        .route("/incidents/{id}", get(get_incident))
+        .route("/incidents/{id}/acknowledge", post(acknowledge_incident))
+        .route("/integrations/pagerduty", post(pagerduty_webhook))
         .route("/metrics", get(get_all_metrics))
         .route("/metrics/{service_id}", get(get_service_metrics))
         .route("/metrics/{service_id}/summary", get(get_service_metrics_summary))
@@ -75,6 +125,7 @@ async fn create_http_check(
         &payload.name,
         payload.kind,
         Duration::from_secs(payload.interval),
+        &payload.options,
     ).await;
     
     match svc {
@@ -108,7 +159,7 @@ async fn list_http_checks(
 ) -> (StatusCode, Json<Vec<Service>>) {
     let checks = rstat_service::all(&state.pool).await;
     match checks {
-        Ok(checks) => (StatusCode::OK, Json(checks)),
+        Ok(checks) => (StatusCode::OK, Json(checks.into_iter().map(Service::redacted).collect())),
         Err(err) => {
             error!("Failed to list services: {}", err);
             (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
@@ -116,6 +167,438 @@ async fn list_http_checks(
     }
 }
 
+async fn get_service(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<Service>>) {
+    match rstat_service::get(&state.pool, service_id).await {
+        Ok(service) => (StatusCode::OK, Json(Some(service.redacted()))),
+        Err(err) => {
+            error!("Failed to get service {}: {}", service_id, err);
+            (StatusCode::NOT_FOUND, Json(None))
+        }
+    }
+}
+
+/// Stop checking a service, keeping its history
+async fn pause_service(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<Service>>) {
+    match rstat_service::pause(&state.pool, service_id).await {
+        Ok(Some(_)) => {
+            info!("Paused service {}", service_id);
+            get_service(State(state), Path(service_id)).await
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            error!("Failed to pause service {}: {}", service_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+async fn resume_service(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<Service>>) {
+    match rstat_service::resume(&state.pool, service_id).await {
+        Ok(Some(_)) => {
+            info!("Resumed service {}", service_id);
+            get_service(State(state), Path(service_id)).await
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            error!("Failed to resume service {}: {}", service_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+async fn get_service_slo(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<SloReport>>) {
+    let service = match rstat_service::get(&state.pool, service_id).await {
+        Ok(service) => service,
+        Err(err) => {
+            error!("Failed to get service {}: {}", service_id, err);
+            return (StatusCode::NOT_FOUND, Json(None));
+        }
+    };
+    // Services without SLOs have nothing to report
+    let Some(config) = service.options.slo else {
+        return (StatusCode::NOT_FOUND, Json(None));
+    };
+
+    let evaluator = SloEvaluator::new(state.pool.clone());
+    match evaluator.evaluate(service_id, &config).await {
+        Ok(report) => (StatusCode::OK, Json(Some(report))),
+        Err(err) => {
+            error!("Failed to evaluate SLO of service {}: {}", service_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// Compare the response times of a service from each location it is checked from
+async fn get_service_locations(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+    Query(query): Query<LocationsQuery>,
+) -> (StatusCode, Json<Vec<LocationLatency>>) {
+    let since = chrono::Utc::now() - chrono::Duration::hours(query.hours.unwrap_or(24));
+    match rstat_healthcheck::db::latency_by_location(&state.pool, service_id, since).await {
+        Ok(locations) => (StatusCode::OK, Json(locations)),
+        Err(err) => {
+            error!("Failed to get latency by location of service {}: {}", service_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+async fn list_agents(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<Agent>>) {
+    match rstat_agent::db::list(&state.pool).await {
+        Ok(agents) => (StatusCode::OK, Json(agents)),
+        Err(err) => {
+            error!("Failed to list agents: {}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+/// The agent calling the API, identified by the bearer token it was registered with
+async fn authenticate_agent(state: &AppState, headers: &HeaderMap) -> Result<Agent, StatusCode> {
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    match rstat_agent::authenticate(&state.pool, token.trim()).await {
+        Ok(Some(agent)) => Ok(agent),
+        Ok(None) => {
+            warn!("Rejected an agent request with an unknown token");
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        Err(err) => {
+            error!("Failed to authenticate agent: {}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Services the calling agent should check, the ones assigned to its location
+async fn list_agent_services(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> (StatusCode, Json<Vec<AssignedService>>) {
+    let agent = match authenticate_agent(&state, &headers).await {
+        Ok(agent) => agent,
+        Err(status) => return (status, Json(vec![])),
+    };
+
+    match rstat_service::for_location(&state.pool, &agent.location).await {
+        Ok(services) => (StatusCode::OK, Json(services.into_iter().map(AssignedService::from).collect())),
+        Err(err) => {
+            error!("Failed to list services for location {}: {}", agent.location, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+/// Ingest the check results of the calling agent, tagged with its location
+async fn ingest_agent_results(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(reports): Json<Vec<Report>>,
+) -> StatusCode {
+    let agent = match authenticate_agent(&state, &headers).await {
+        Ok(agent) => agent,
+        Err(status) => return status,
+    };
+    if reports.len() > MAX_REPORTS_PER_PUSH {
+        warn!("Agent {} pushed {} reports, at most {} are accepted at once", agent.name, reports.len(), MAX_REPORTS_PER_PUSH);
+        return StatusCode::PAYLOAD_TOO_LARGE;
+    }
+
+    for report in reports {
+        // Reloaded for every report, as each one advances the service state
+        let service = match rstat_service::get(&state.pool, report.service_id).await {
+            Ok(service) => service,
+            Err(err) => {
+                warn!("Agent {} reported on unknown service {}: {}", agent.name, report.service_id, err);
+                continue;
+            }
+        };
+        if let Err(reason) = accept_report(&agent, &service) {
+            warn!("Ignoring a result from agent {}: {}", agent.name, reason);
+            continue;
+        }
+
+        let result = match report.result {
+            Ok(mut result) => match report_time(result.created_at, chrono::Utc::now()) {
+                Ok(created_at) => {
+                    result.created_at = created_at;
+                    result.location = Some(agent.location.clone());
+                    Ok(result)
+                }
+                Err(reason) => {
+                    warn!("Ignoring a result from agent {} on service {}: {}", agent.name, service.name, reason);
+                    continue;
+                }
+            },
+            Err(err) => Err(err),
+        };
+        state.scheduler.process_from(&service, &agent.location, result).await;
+    }
+
+    StatusCode::OK
+}
+
+/// How far ahead of the server the clock of an agent may be
+const MAX_AGENT_CLOCK_SKEW: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Oldest result accepted from an agent, about as long as it keeps results the server
+/// could not receive
+const MAX_AGENT_RESULT_AGE: chrono::Duration = chrono::Duration::hours(1);
+
+/// When a result reported by an agent was taken. Results slightly ahead of the server are
+/// taken now, the ones further ahead or too old are refused as they would land in
+/// metrics and maintenance windows they do not belong to.
+fn report_time(
+    created_at: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if created_at > now + MAX_AGENT_CLOCK_SKEW {
+        return Err(format!("it was taken at {}, ahead of the server", created_at));
+    }
+    if created_at < now - MAX_AGENT_RESULT_AGE {
+        return Err(format!("it was taken at {}, too long ago", created_at));
+    }
+    Ok(created_at.min(now))
+}
+
+/// Whether an agent may report on a service: the service must be checked from the agent's
+/// location and not be paused, which the agent learns about on its next refresh
+fn accept_report(agent: &Agent, service: &Service) -> Result<(), String> {
+    if !service.options.locations.contains(&agent.location) {
+        return Err(format!("service {} is not checked from {}", service.name, agent.location));
+    }
+    if service.state.status == ServiceStatus::Paused {
+        return Err(format!("service {} is paused", service.name));
+    }
+    Ok(())
+}
+
+async fn list_maintenance_windows(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<Vec<MaintenanceWindow>>) {
+    match rstat_maintenance::db::list(&state.pool).await {
+        Ok(windows) => (StatusCode::OK, Json(windows)),
+        Err(err) => {
+            error!("Failed to list maintenance windows: {}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+async fn create_maintenance_window(
+    State(state): State<AppState>,
+    Json(payload): Json<MaintenanceSettings>,
+) -> (StatusCode, Json<String>) {
+    match rstat_maintenance::create(&state.pool, &payload).await {
+        Ok(window) => {
+            info!("Created maintenance window {} ({})", window.settings.name, window.id);
+            (StatusCode::CREATED, Json(window.id.to_string()))
+        }
+        Err(err) => (StatusCode::BAD_REQUEST, Json(err.to_string())),
+    }
+}
+
+async fn get_maintenance_window(
+    State(state): State<AppState>,
+    Path(window_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<MaintenanceWindow>>) {
+    match rstat_maintenance::db::get(&state.pool, window_id).await {
+        Ok(Some(window)) => (StatusCode::OK, Json(Some(window))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            error!("Failed to get maintenance window {}: {}", window_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+async fn update_maintenance_window(
+    State(state): State<AppState>,
+    Path(window_id): Path<uuid::Uuid>,
+    Json(payload): Json<MaintenanceSettings>,
+) -> (StatusCode, Json<String>) {
+    match rstat_maintenance::update(&state.pool, window_id, &payload).await {
+        Ok(Some(window)) => (StatusCode::OK, Json(window.id.to_string())),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(format!("No maintenance window with ID {}", window_id))),
+        Err(err) => (StatusCode::BAD_REQUEST, Json(err.to_string())),
+    }
+}
+
+async fn delete_maintenance_window(
+    State(state): State<AppState>,
+    Path(window_id): Path<uuid::Uuid>,
+) -> StatusCode {
+    match rstat_maintenance::db::delete(&state.pool, window_id).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(err) => {
+            error!("Failed to delete maintenance window {}: {}", window_id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn list_incidents(
+    State(state): State<AppState>,
+    Query(query): Query<IncidentsQuery>,
+) -> (StatusCode, Json<Vec<Incident>>) {
+    let status = query.status.map(|s| IncidentStatus::from(s.to_uppercase()));
+    let limit = query.limit.unwrap_or(100);
+    match rstat_incidents::db::list(&state.pool, status, limit).await {
+        Ok(incidents) => (StatusCode::OK, Json(incidents)),
+        Err(err) => {
+            error!("Failed to list incidents: {}", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
+async fn get_incident(
+    State(state): State<AppState>,
+    Path(incident_id): Path<uuid::Uuid>,
+) -> (StatusCode, Json<Option<IncidentDetails>>) {
+    let manager = IncidentManager::new(state.pool.clone());
+    match manager.get_details(incident_id).await {
+        Ok(Some(details)) => (StatusCode::OK, Json(Some(details))),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+        Err(err) => {
+            error!("Failed to get incident {}: {}", incident_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+async fn acknowledge_incident(
+    State(state): State<AppState>,
+    Path(incident_id): Path<uuid::Uuid>,
+    payload: Option<Json<types::AcknowledgeIncidentRequest>>,
+) -> (StatusCode, Json<Option<Incident>>) {
+    let by = payload.and_then(|Json(payload)| payload.by);
+    let manager = IncidentManager::new(state.pool.clone());
+    match manager.acknowledge(incident_id, by).await {
+        Ok(Some(incident)) => (StatusCode::OK, Json(Some(incident))),
+        // Unknown, or already acknowledged or resolved
+        Ok(None) => match rstat_incidents::db::get(&state.pool, incident_id).await {
+            Ok(Some(_)) => (StatusCode::CONFLICT, Json(None)),
+            Ok(None) => (StatusCode::NOT_FOUND, Json(None)),
+            Err(err) => {
+                error!("Failed to get incident {}: {}", incident_id, err);
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+            }
+        },
+        Err(err) => {
+            error!("Failed to acknowledge incident {}: {}", incident_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(None))
+        }
+    }
+}
+
+/// Reflect acknowledgements made in PagerDuty on the matching rstat incident
+async fn pagerduty_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> StatusCode {
+    // Without a secret anyone could acknowledge incidents
+    let Some(secret) = &state.pagerduty_webhook_secret else {
+        warn!("Rejected PagerDuty webhook, PAGERDUTY_WEBHOOK_SECRET is not set");
+        return StatusCode::FORBIDDEN;
+    };
+    let signatures = headers
+        .get("X-PagerDuty-Signature")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !verify_pagerduty_signature(secret, &body, signatures) {
+        warn!("Rejected PagerDuty webhook with an invalid signature");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let webhook: types::PagerDutyWebhook = match serde_json::from_str(&body) {
+        Ok(webhook) => webhook,
+        Err(err) => {
+            warn!("Invalid PagerDuty webhook: {}", err);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    if webhook.event.event_type != "incident.acknowledged" {
+        return StatusCode::OK;
+    }
+
+    // Incidents triggered by other tools are none of our business
+    let Some(service_id) = webhook
+        .event
+        .data
+        .incident_key
+        .as_deref()
+        .and_then(rstat_incidents::service_from_dedup_key)
+    else {
+        return StatusCode::OK;
+    };
+
+    let by = webhook.event.agent.and_then(|agent| agent.summary);
+    let manager = IncidentManager::new(state.pool.clone());
+    match manager.acknowledge_service(service_id, by).await {
+        Ok(Some(incident)) => {
+            info!("Incident {} acknowledged in PagerDuty", incident.id);
+            StatusCode::OK
+        }
+        Ok(None) => StatusCode::OK,
+        Err(err) => {
+            error!("Failed to acknowledge incident of service {}: {}", service_id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Check a `v1=<hex>` signature list against the HMAC-SHA256 of the body
+fn verify_pagerduty_signature(secret: &str, body: &str, signatures: &str) -> bool {
+    signatures
+        .split(',')
+        .filter_map(|signature| signature.trim().strip_prefix("v1="))
+        .filter_map(|signature| hex::decode(signature).ok())
+        .any(|signature| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+            mac.update(body.as_bytes());
+            mac.verify_slice(&signature).is_ok()
+        })
+}
+
+async fn list_service_incidents(
+    State(state): State<AppState>,
+    Path(service_id): Path<uuid::Uuid>,
+    Query(query): Query<IncidentsQuery>,
+) -> (StatusCode, Json<Vec<Incident>>) {
+    let limit = query.limit.unwrap_or(100);
+    match rstat_incidents::db::list_for_service(&state.pool, service_id, limit).await {
+        Ok(incidents) => (StatusCode::OK, Json(incidents)),
+        Err(err) => {
+            error!("Failed to list incidents for service {}: {}", service_id, err);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+        }
+    }
+}
+
 async fn get_checks_for_service(
     State(state): State<AppState>,
     Path(service_id): Path<uuid::Uuid>,
@@ -155,7 +638,19 @@ async fn get_service_metrics(
     State(state): State<AppState>,
     Path(service_id): Path<uuid::Uuid>,
     Query(query): Query<MetricsQuery>,
-) -> (StatusCode, Json<Vec<ServiceMetric>>) {
+) -> (StatusCode, Json<ServiceMetrics>) {
+    if let Some(resolution) = query.resolution {
+        let days = query.days.unwrap_or(resolution.default_days());
+        let calculator = MetricsCalculator::new(state.pool.clone());
+        return match calculator.get_rollups(service_id, resolution, days).await {
+            Ok(rollups) => (StatusCode::OK, Json(ServiceMetrics::Rollups(rollups))),
+            Err(err) => {
+                error!("Failed to get {:?} metrics for service {}: {}", resolution, service_id, err);
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ServiceMetrics::Rollups(vec![])))
+            }
+        };
+    }
+
     let days = query.days.unwrap_or(30);
     let metrics = rstat_metrics::db::get_metrics_for_service_last_days(
         &state.pool,
@@ -164,10 +659,10 @@ async fn get_service_metrics(
     ).await;
     
     match metrics {
-        Ok(metrics) => (StatusCode::OK, Json(metrics)),
+        Ok(metrics) => (StatusCode::OK, Json(ServiceMetrics::Daily(metrics))),
         Err(err) => {
             error!("Failed to get metrics for service {}: {}", service_id, err);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(ServiceMetrics::Daily(vec![])))
         }
     }
 }
@@ -185,13 +680,7 @@ async fn get_service_metrics_summary(
         Ok(summary) => (StatusCode::OK, Json(summary)),
         Err(err) => {
             error!("Failed to get metrics summary for service {}: {}", service_id, err);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(ServiceMetricsSummary {
-                service_id,
-                current_uptime: 0.0,
-                current_latency_ms: 0,
-                average_latency_ms: 0,
-                uptime_data: vec![],
-            }))
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(ServiceMetricsSummary::from_metrics(service_id, vec![])))
         }
     }
 }
@@ -212,14 +701,130 @@ async fn list_services_with_metrics(
     let mut result = Vec::with_capacity(services.len());
     for service in services {
         let summary = calculator.get_metrics_summary(service.id, Some(days)).await
-            .unwrap_or(ServiceMetricsSummary {
-                service_id: service.id,
-                current_uptime: 0.0,
-                current_latency_ms: 0,
-                average_latency_ms: 0,
-                uptime_data: vec![],
-            });
-        result.push(ServiceWithMetricsSummary { service, metrics_summary: summary });
+            .unwrap_or_else(|_| ServiceMetricsSummary::from_metrics(service.id, vec![]));
+        result.push(ServiceWithMetricsSummary { service: service.redacted(), metrics_summary: summary });
     }
     (StatusCode::OK, Json(result))
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use rstat_metrics::aggregator::Aggregator;
+    use tokio::sync::mpsc;
+
+    /// State whose database is never reached, for requests rejected before any query
+    fn offline_state(pagerduty_webhook_secret: Option<&str>) -> AppState {
+        let pool = PgPool::connect_lazy("postgres://localhost/rstat").unwrap();
+        let (event_tx, _) = mpsc::channel(1);
+        AppState {
+            pool: pool.clone(),
+            pagerduty_webhook_secret: pagerduty_webhook_secret.map(str::to_string),
+            scheduler: Scheduler::new(pool.clone(), event_tx, Arc::new(Aggregator::new(pool))),
+        }
+    }
+
+    #[test]
+    fn test_verify_pagerduty_signature() {
+        let body = r#"{"event":{"event_type":"incident.acknowledged"}}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        assert!(verify_pagerduty_signature("s3cret", body, &format!("v1=deadbeef,v1={}", signature)));
+        assert!(!verify_pagerduty_signature("other", body, &format!("v1={}", signature)));
+        assert!(!verify_pagerduty_signature("s3cret", body, ""));
+    }
+
+    #[tokio::test]
+    async fn test_pagerduty_webhook_requires_a_secret() {
+        let body = r#"{"event":{"event_type":"incident.acknowledged"}}"#.to_string();
+
+        let status = pagerduty_webhook(State(offline_state(None)), HeaderMap::new(), body.clone()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = pagerduty_webhook(State(offline_state(Some("s3cret"))), HeaderMap::new(), body).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    fn agent(location: &str) -> Agent {
+        Agent {
+            id: uuid::Uuid::new_v4(),
+            name: "probe-1".to_string(),
+            location: location.to_string(),
+            created_at: chrono::Utc::now(),
+            last_seen_at: None,
+        }
+    }
+
+    fn service(locations: &[&str]) -> Service {
+        Service {
+            id: uuid::Uuid::new_v4(),
+            name: "API".to_string(),
+            kind: rstat_core::Kind::TCP(rstat_core::TcpChecker::default()),
+            interval: Duration::from_secs(30),
+            next_run: chrono::Utc::now(),
+            options: rstat_core::ServiceOptions {
+                locations: locations.iter().map(|location| location.to_string()).collect(),
+                notifications: rstat_core::NotificationSettings {
+                    pagerduty: vec![serde_json::from_str(r#"{"routing_key": "R0UT1NG"}"#).unwrap()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            state: rstat_core::ServiceState::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_endpoints_require_a_token() {
+        let mut basic = HeaderMap::new();
+        basic.insert(http::header::AUTHORIZATION, "Basic cHJvYmU6c2VjcmV0".parse().unwrap());
+
+        for headers in [HeaderMap::new(), basic] {
+            let (status, Json(services)) = list_agent_services(State(offline_state(None)), headers.clone()).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert!(services.is_empty());
+
+            let status = ingest_agent_results(State(offline_state(None)), headers, Json(vec![])).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[test]
+    fn test_accept_report() {
+        let agent = agent("eu-west");
+        assert!(accept_report(&agent, &service(&["eu-west", "us-east"])).is_ok());
+
+        // Services checked elsewhere or by the scheduler are not the agent's to report on
+        assert!(accept_report(&agent, &service(&["us-east"])).is_err());
+        assert!(accept_report(&agent, &service(&[])).is_err());
+
+        let mut paused = service(&["eu-west"]);
+        paused.state.status = ServiceStatus::Paused;
+        assert!(accept_report(&agent, &paused).is_err());
+    }
+
+    #[test]
+    fn test_report_time() {
+        let now = chrono::Utc::now();
+        assert_eq!(report_time(now - chrono::Duration::minutes(5), now), Ok(now - chrono::Duration::minutes(5)));
+        // A clock slightly ahead is taken as now
+        assert_eq!(report_time(now + chrono::Duration::seconds(30), now), Ok(now));
+
+        assert!(report_time(now + chrono::Duration::minutes(5), now).is_err());
+        assert!(report_time(now - chrono::Duration::hours(2), now).is_err());
+    }
+
+    #[test]
+    fn test_assigned_service_leaves_out_settings() {
+        let json = serde_json::to_string(&AssignedService::from(service(&["eu-west"]))).unwrap();
+        assert!(json.contains("\"kind\""));
+        for field in ["R0UT1NG", "notifications", "state", "locations"] {
+            assert!(!json.contains(field), "{} sent to the agent", field);
+        }
+    }
+}
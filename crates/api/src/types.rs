@@ -1,11 +1,13 @@
 use serde::Deserialize;
-use rstat_core::Kind;
+use rstat_core::{Kind, ServiceOptions};
 
 #[derive(Deserialize)]
 pub struct CreateServiceRequest {
     pub name: String,
     pub kind: Kind,
     pub interval: u64,
+    #[serde(flatten)]
+    pub options: ServiceOptions,
 }
 
 #[derive(Deserialize)]
@@ -16,4 +18,33 @@ pub struct DeleteServiceRequest {
 #[derive(Deserialize)]
 pub struct GetServiceRequest {
     pub id: String,
-} 
\ No newline at end of file
+} 
+#[derive(Deserialize, Default)]
+pub struct AcknowledgeIncidentRequest {
+    /// Who acknowledged the incident.
+    pub by: Option<String>,
+}
+
+/// A PagerDuty V3 webhook delivery
+#[derive(Deserialize)]
+pub struct PagerDutyWebhook {
+    pub event: PagerDutyWebhookEvent,
+}
+
+#[derive(Deserialize)]
+pub struct PagerDutyWebhookEvent {
+    pub event_type: String,
+    pub agent: Option<PagerDutyReference>,
+    pub data: PagerDutyIncident,
+}
+
+#[derive(Deserialize)]
+pub struct PagerDutyReference {
+    pub summary: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PagerDutyIncident {
+    /// The dedup key the incident was triggered with.
+    pub incident_key: Option<String>,
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -20,6 +21,28 @@ pub enum Commands {
         #[command(subcommand)]
         command: MetricsCommands,
     },
+    /// Run as an agent, checking the services of its location for a central server
+    Agent {
+        /// URL of the central rstat server
+        #[arg(short, long, env = "RSTAT_SERVER_URL")]
+        server: String,
+
+        /// Token printed when the agent was registered
+        #[arg(short, long, env = "RSTAT_AGENT_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+    Agents {
+        #[command(subcommand)]
+        command: AgentCommands,
+    },
+    Maintenance {
+        #[command(subcommand)]
+        command: MaintenanceCommands,
+    },
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -44,6 +67,86 @@ pub enum ConfigCommands {
     LoadDefault,
 }
 
+#[derive(Subcommand)]
+pub enum AgentCommands {
+    /// Register an agent and print its token
+    Register {
+        /// Name of the agent
+        #[arg(short, long)]
+        name: String,
+
+        /// Location of the agent, services listing it are checked by the agent
+        #[arg(short, long)]
+        location: String,
+    },
+    /// List registered agents
+    List,
+    /// Remove an agent, revoking its token
+    Remove {
+        /// Agent ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Stop checking a service, keeping its history
+    Pause {
+        /// Service ID
+        id: String,
+    },
+    /// Check a paused service again
+    Resume {
+        /// Service ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MaintenanceCommands {
+    /// Schedule a maintenance window
+    Add {
+        /// Name of the window, e.g. what is being deployed
+        #[arg(short, long)]
+        name: String,
+
+        /// Service under maintenance
+        #[arg(short, long, conflicts_with = "tag")]
+        service: Option<String>,
+
+        /// Tag of the services under maintenance, all services when neither this nor --service is set
+        #[arg(short, long)]
+        tag: Option<String>,
+
+        /// When the window starts, or starts recurring (RFC 3339), now by default
+        #[arg(long)]
+        starts_at: Option<DateTime<Utc>>,
+
+        /// When the window ends, or stops recurring (RFC 3339)
+        #[arg(long)]
+        ends_at: Option<DateTime<Utc>>,
+
+        /// How long the window lasts, each time for recurring windows
+        #[arg(short, long)]
+        duration_minutes: Option<u32>,
+
+        /// Cron expression a recurring window opens on
+        #[arg(long, requires = "duration_minutes")]
+        cron: Option<String>,
+
+        /// Time zone of the cron expression, UTC by default
+        #[arg(long, requires = "cron")]
+        timezone: Option<String>,
+    },
+    /// List maintenance windows
+    List,
+    /// Remove a maintenance window
+    Remove {
+        /// Maintenance window ID
+        id: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum MetricsCommands {
     /// Calculate metrics for all services
@@ -57,7 +160,7 @@ pub enum MetricsCommands {
     CalculateYesterday,
     /// Clean up old metrics
     Cleanup {
-        /// Delete metrics older than this many days
+        /// Delete metrics older than this many days, rollups are kept no longer than their retention
         #[arg(default_value = "90")]
         days: u32,
     },
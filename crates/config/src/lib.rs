@@ -6,16 +6,69 @@ use sqlx::PgPool;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
-use rstat_core::{Kind, HttpChecker, TcpChecker};
+use rstat_core::{
+    Kind, ServiceOptions, Assertion, HttpChecker, TcpChecker, DnsChecker, DnsRecordType, TlsChecker,
+    NotificationSettings, Schedule, SloConfig,
+};
 
 pub mod loader;
 
+/// Top-level YAML configuration.
+///
+/// A file may also be a plain list of services, in which case no global notifications are set.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RstatConfig {
+    /// Channels notified about every service.
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub services: Vec<ServiceConfig>,
+}
+
+impl RstatConfig {
+    /// Parse a YAML configuration file
+    pub fn parse(content: &str) -> Result<Self, anyhow::Error> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        if value.is_sequence() {
+            let services: Vec<ServiceConfig> = serde_yaml::from_value(value)?;
+            return Ok(Self { services, ..Self::default() });
+        }
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_yaml::from_value(value)?)
+    }
+
+    /// Read and parse a YAML configuration file
+    pub fn from_file(file_path: &Path) -> Result<Self, anyhow::Error> {
+        let content = fs::read_to_string(file_path)?;
+        Self::parse(&content)
+    }
+}
+
 /// YAML configuration structure for services
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ServiceConfig {
     pub name: String,
     pub kind: ServiceKind,
     pub interval: u64,
+    /// Consecutive failures before the service is marked DOWN.
+    pub failure_threshold: Option<u32>,
+    /// Consecutive successes before a DOWN service is marked UP again.
+    pub success_threshold: Option<u32>,
+    /// Channels notified about this service in addition to the global ones.
+    pub notifications: Option<NotificationSettings>,
+    /// Service level objectives of the service.
+    pub slo: Option<SloConfig>,
+    /// Locations of the agents checking the service, the scheduler checks it when unset.
+    pub locations: Option<Vec<String>>,
+    /// Labels grouping the service with others, e.g. for maintenance windows.
+    pub tags: Option<Vec<String>>,
+    /// Cron schedule and active windows of the service.
+    pub schedule: Option<Schedule>,
+    /// Whether the service is checked, services that are not are created paused.
+    pub enabled: Option<bool>,
 }
 
 /// Service kind configuration for YAML
@@ -30,6 +83,8 @@ pub enum ServiceKind {
         body: Option<String>,
         timeout: Option<u8>,
         max_retries: Option<u8>,
+        assertions: Option<Vec<Assertion>>,
+        allow_insecure_redirects: Option<bool>,
     },
     #[serde(rename = "tcp")]
     TCP {
@@ -38,6 +93,25 @@ pub enum ServiceKind {
         timeout: Option<u8>,
         max_retries: Option<u8>,
     },
+    #[serde(rename = "dns")]
+    DNS {
+        domain: String,
+        record_type: Option<DnsRecordType>,
+        nameserver: Option<String>,
+        expected: Option<Vec<String>>,
+        timeout: Option<u8>,
+        max_retries: Option<u8>,
+    },
+    #[serde(rename = "tls")]
+    TLS {
+        host: String,
+        port: Option<u16>,
+        expiry_warning_days: Option<u32>,
+        fail_on_expiry_warning: Option<bool>,
+        verify_chain: Option<bool>,
+        timeout: Option<u8>,
+        max_retries: Option<u8>,
+    },
 }
 
 /// Configuration loader for services from YAML files
@@ -54,8 +128,7 @@ impl ConfigLoader {
     pub async fn load_from_file(&self, file_path: &Path) -> Result<Vec<Uuid>, anyhow::Error> {
         info!("Loading services from YAML file: {}", file_path.display());
         
-        let content = fs::read_to_string(file_path)?;
-        let services: Vec<ServiceConfig> = serde_yaml::from_str(&content)?;
+        let services = RstatConfig::from_file(file_path)?.services;
         
         info!("Found {} services in configuration file", services.len());
         
@@ -80,8 +153,15 @@ impl ConfigLoader {
 
     /// Create a service from configuration
     async fn create_service_from_config(&self, config: ServiceConfig) -> Result<Uuid, anyhow::Error> {
+        if let Some(slo) = &config.slo {
+            slo.validate().map_err(|e| anyhow::anyhow!("{}: {}", config.name, e))?;
+        }
+        if let Some(schedule) = &config.schedule {
+            schedule.validate().map_err(|e| anyhow::anyhow!("{}: {}", config.name, e))?;
+        }
+
         let kind = match config.kind {
-            ServiceKind::HTTP { url, method, headers, body, timeout, max_retries } => {
+            ServiceKind::HTTP { url, method, headers, body, timeout, max_retries, assertions, allow_insecure_redirects } => {
                 Kind::HTTP(HttpChecker {
                     url,
                     method: method.unwrap_or_else(|| "GET".to_string()),
@@ -89,6 +169,8 @@ impl ConfigLoader {
                     body,
                     timeout: timeout.unwrap_or(5),
                     max_retries: max_retries.unwrap_or(3),
+                    assertions: assertions.unwrap_or_default(),
+                    allow_insecure_redirects: allow_insecure_redirects.unwrap_or(false),
                 })
             }
             ServiceKind::TCP { host, port, timeout, max_retries } => {
@@ -99,11 +181,47 @@ impl ConfigLoader {
                     max_retries: max_retries.unwrap_or(3),
                 })
             }
+            ServiceKind::DNS { domain, record_type, nameserver, expected, timeout, max_retries } => {
+                Kind::DNS(DnsChecker {
+                    domain,
+                    record_type: record_type.unwrap_or_default(),
+                    nameserver,
+                    expected: expected.unwrap_or_default(),
+                    timeout: timeout.unwrap_or(5),
+                    max_retries: max_retries.unwrap_or(3),
+                })
+            }
+            ServiceKind::TLS { host, port, expiry_warning_days, fail_on_expiry_warning, verify_chain, timeout, max_retries } => {
+                let defaults = TlsChecker::default();
+                Kind::TLS(TlsChecker {
+                    host,
+                    port: port.unwrap_or(defaults.port),
+                    expiry_warning_days: expiry_warning_days.unwrap_or(defaults.expiry_warning_days),
+                    fail_on_expiry_warning: fail_on_expiry_warning.unwrap_or(defaults.fail_on_expiry_warning),
+                    verify_chain: verify_chain.unwrap_or(defaults.verify_chain),
+                    timeout: timeout.unwrap_or(5),
+                    max_retries: max_retries.unwrap_or(3),
+                })
+            }
         };
 
         let interval = Duration::from_secs(config.interval);
+        let defaults = ServiceOptions::default();
+        let options = ServiceOptions {
+            failure_threshold: config.failure_threshold.unwrap_or(defaults.failure_threshold),
+            success_threshold: config.success_threshold.unwrap_or(defaults.success_threshold),
+            notifications: config.notifications.unwrap_or_default(),
+            slo: config.slo,
+            locations: config.locations.unwrap_or_default(),
+            tags: config.tags.unwrap_or_default(),
+            schedule: config.schedule,
+        };
         
-        rstat_service::create(&self.pool, &config.name, kind, interval).await
+        if config.enabled == Some(false) {
+            rstat_service::create_paused(&self.pool, &config.name, kind, interval, &options).await
+        } else {
+            rstat_service::create(&self.pool, &config.name, kind, interval, &options).await
+        }
     }
 
     /// Load services from a directory containing YAML files
@@ -120,7 +238,7 @@ impl ConfigLoader {
             let entry = entry?;
             let path = entry.path();
             
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("yaml") {
+            if is_yaml(&path) {
                 match self.load_from_file(&path).await {
                     Ok(ids) => all_created_ids.extend(ids),
                     Err(e) => {
@@ -144,8 +262,7 @@ impl ConfigLoader {
     pub async fn load_from_file_with_check(&self, file_path: &Path) -> Result<Vec<Uuid>, anyhow::Error> {
         info!("Loading services from YAML file with duplicate checking: {}", file_path.display());
         
-        let content = fs::read_to_string(file_path)?;
-        let services: Vec<ServiceConfig> = serde_yaml::from_str(&content)?;
+        let services = RstatConfig::from_file(file_path)?.services;
         
         info!("Found {} services in configuration file", services.len());
         
@@ -173,4 +290,81 @@ impl ConfigLoader {
         info!("Successfully created {} new services from configuration", created_ids.len());
         Ok(created_ids)
     }
-} 
\ No newline at end of file
+} 
+/// Whether a path is a YAML file, with either a `.yaml` or a `.yml` extension
+pub(crate) fn is_yaml(path: &Path) -> bool {
+    path.is_file() && matches!(path.extension().and_then(|s| s.to_str()), Some("yaml" | "yml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accepts_service_list_and_full_config() {
+        let list = r#"
+- name: "API"
+  kind:
+    type: tcp
+    host: "localhost"
+    port: 80
+  interval: 30
+  enabled: false
+  schedule:
+    cron: "*/5 * * * *"
+    timezone: "Europe/Berlin"
+    windows:
+      - days: [Mon, Tue, Wed, Thu, Fri]
+        start: "09:00"
+        end: "18:00"
+"#;
+        let config = RstatConfig::parse(list).unwrap();
+        assert_eq!(config.services.len(), 1);
+        assert!(config.notifications.is_empty());
+        assert_eq!(config.services[0].enabled, Some(false));
+        let schedule = config.services[0].schedule.as_ref().unwrap();
+        assert_eq!(schedule.windows[0].days.len(), 5);
+        assert!(schedule.validate().is_ok());
+
+        let full = r#"
+notifications:
+  webhooks:
+    - url: "https://hooks.example.com/rstat"
+      secret: "s3cret"
+      events: [service_down, service_recovered]
+services:
+  - name: "API"
+    kind:
+      type: tcp
+      host: "localhost"
+      port: 80
+    interval: 30
+    notifications:
+      webhooks:
+        - url: "https://hooks.example.com/api-team"
+"#;
+        let config = RstatConfig::parse(full).unwrap();
+        assert_eq!(config.notifications.webhooks.len(), 1);
+        assert_eq!(config.notifications.webhooks[0].max_retries, 3);
+        assert_eq!(config.notifications.webhooks[0].events, vec!["service_down", "service_recovered"]);
+
+        let service_hooks = config.services[0].notifications.as_ref().unwrap();
+        assert_eq!(service_hooks.webhooks[0].url, "https://hooks.example.com/api-team");
+    }
+
+    #[test]
+    fn test_is_yaml() {
+        let dir = std::env::temp_dir().join(format!("rstat-config-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["services.yaml", "services.yml", "services.json"] {
+            fs::write(dir.join(name), "[]").unwrap();
+        }
+
+        assert!(is_yaml(&dir.join("services.yaml")));
+        assert!(is_yaml(&dir.join("services.yml")));
+        assert!(!is_yaml(&dir.join("services.json")));
+        assert!(!is_yaml(&dir));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
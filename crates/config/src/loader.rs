@@ -1,19 +1,24 @@
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 use anyhow::Result;
 
-use super::ConfigLoader;
+use rstat_core::NotificationSettings;
+
+use super::{is_yaml, ConfigLoader, RstatConfig};
+
+const DEFAULT_CONFIG_FILES: [&str; 4] = [
+    "config/services.yaml",
+    "config/services.yml",
+    "services.yaml",
+    "services.yml",
+];
 
 impl ConfigLoader {
     /// Load services from a default configuration file path
     pub async fn load_from_default(&self) -> Result<Vec<uuid::Uuid>, anyhow::Error> {
-        let default_paths = [
-            "config/services.yaml",
-            "config/services.yml", 
-            "services.yaml",
-            "services.yml",
-        ];
-        
+        let default_paths = DEFAULT_CONFIG_FILES;
+
         for path in &default_paths {
             let path = Path::new(path);
             if path.exists() {
@@ -64,4 +69,32 @@ impl ConfigLoader {
         
         Ok(Vec::new())
     }
-} 
\ No newline at end of file
+
+    /// Load the global notification settings from the environment-specified or default configuration
+    pub fn load_notifications(&self) -> Result<NotificationSettings, anyhow::Error> {
+        let path = match std::env::var("RSTAT_CONFIG_PATH") {
+            Ok(config_path) if Path::new(&config_path).exists() => PathBuf::from(config_path),
+            _ => match DEFAULT_CONFIG_FILES.iter().map(Path::new).find(|p| p.exists()) {
+                Some(path) => path.to_path_buf(),
+                None => return Ok(NotificationSettings::default()),
+            },
+        };
+
+        if path.is_file() {
+            return Ok(RstatConfig::from_file(&path)?.notifications);
+        }
+
+        let mut notifications = NotificationSettings::default();
+        for entry in fs::read_dir(&path)? {
+            let path = entry?.path();
+            if is_yaml(&path) {
+                match RstatConfig::from_file(&path) {
+                    Ok(config) => notifications.extend(config.notifications),
+                    Err(e) => warn!("Failed to read notifications from {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        Ok(notifications)
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::healthcheck::{CertificateInfo, HealthCheckResult};
+use crate::service::{Service, ServiceStatus};
+
+/// ServiceRef identifies the service an event is about.
+///
+/// Only the public parts of a service are included so that events can be sent
+/// to external channels without leaking checker configuration such as headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceRef {
+    pub id: Uuid,
+    pub name: String,
+    /// The kind of healthcheck, e.g. HTTP or TLS.
+    pub kind: String,
+}
+
+impl From<&Service> for ServiceRef {
+    fn from(service: &Service) -> Self {
+        Self {
+            id: service.id,
+            name: service.name.clone(),
+            kind: service.kind.to_string(),
+        }
+    }
+}
+
+/// Event is something that happened to a service and that notifiers may act on.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A healthcheck finished and its result was stored.
+    CheckCompleted {
+        service: ServiceRef,
+        result: HealthCheckResult,
+    },
+    /// The service went DOWN.
+    ServiceDown {
+        service: ServiceRef,
+        previous: ServiceStatus,
+        message: String,
+        /// Latency of the check that caused the change, if it got a response.
+        latency_ms: Option<u64>,
+        occurred_at: DateTime<Utc>,
+    },
+    /// The service went back UP after being DOWN or DEGRADED.
+    ServiceRecovered {
+        service: ServiceRef,
+        previous: ServiceStatus,
+        latency_ms: Option<u64>,
+        occurred_at: DateTime<Utc>,
+    },
+    /// The service is reachable but needs attention.
+    ServiceDegraded {
+        service: ServiceRef,
+        previous: ServiceStatus,
+        message: String,
+        latency_ms: Option<u64>,
+        occurred_at: DateTime<Utc>,
+    },
+    /// A TLS certificate entered its expiry warning window.
+    CertificateExpiring {
+        service: ServiceRef,
+        certificate: CertificateInfo,
+        occurred_at: DateTime<Utc>,
+    },
+    /// An incident was opened for the service.
+    IncidentOpened {
+        service: ServiceRef,
+        incident_id: Uuid,
+        started_at: DateTime<Utc>,
+    },
+    /// The open incident of the service was resolved.
+    IncidentResolved {
+        service: ServiceRef,
+        incident_id: Uuid,
+        started_at: DateTime<Utc>,
+        resolved_at: DateTime<Utc>,
+        duration_seconds: i64,
+    },
+    /// The error budget of an SLO is being spent faster than its alerting policy allows.
+    ErrorBudgetBurn {
+        service: ServiceRef,
+        /// The objective burning its budget, `availability` or `latency`.
+        objective: String,
+        /// `page` or `ticket`
+        severity: String,
+        /// The long and short windows the burn rate was measured over, e.g. `1h/5m`.
+        window: String,
+        burn_rate: f64,
+        threshold: f64,
+        /// Percentage of the error budget left in the SLO window.
+        budget_remaining: f64,
+        occurred_at: DateTime<Utc>,
+    },
+}
+
+impl Event {
+    /// The service this event is about.
+    pub fn service(&self) -> &ServiceRef {
+        match self {
+            Event::CheckCompleted { service, .. }
+            | Event::ServiceDown { service, .. }
+            | Event::ServiceRecovered { service, .. }
+            | Event::ServiceDegraded { service, .. }
+            | Event::CertificateExpiring { service, .. }
+            | Event::IncidentOpened { service, .. }
+            | Event::IncidentResolved { service, .. }
+            | Event::ErrorBudgetBurn { service, .. } => service,
+        }
+    }
+
+    /// The name of the event, matching its serialized `type`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::CheckCompleted { .. } => "check_completed",
+            Event::ServiceDown { .. } => "service_down",
+            Event::ServiceRecovered { .. } => "service_recovered",
+            Event::ServiceDegraded { .. } => "service_degraded",
+            Event::CertificateExpiring { .. } => "certificate_expiring",
+            Event::IncidentOpened { .. } => "incident_opened",
+            Event::IncidentResolved { .. } => "incident_resolved",
+            Event::ErrorBudgetBurn { .. } => "error_budget_burn",
+        }
+    }
+
+    /// Latency of the check behind the event, in milliseconds.
+    pub fn latency_ms(&self) -> Option<u64> {
+        match self {
+            Event::CheckCompleted { result, .. } => Some((result.response_time / 1000) as u64),
+            Event::ServiceDown { latency_ms, .. }
+            | Event::ServiceRecovered { latency_ms, .. }
+            | Event::ServiceDegraded { latency_ms, .. } => *latency_ms,
+            _ => None,
+        }
+    }
+
+    /// The error or warning reported by the check behind the event.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Event::CheckCompleted { result, .. } if !result.success => Some(&result.message),
+            Event::ServiceDown { message, .. } | Event::ServiceDegraded { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// The status the service has after the event.
+    pub fn status(&self) -> ServiceStatus {
+        match self {
+            Event::CheckCompleted { result, .. } if result.success => ServiceStatus::Up,
+            Event::CheckCompleted { .. } | Event::ServiceDown { .. } | Event::IncidentOpened { .. } => {
+                ServiceStatus::Down
+            }
+            Event::ServiceRecovered { .. } | Event::IncidentResolved { .. } => ServiceStatus::Up,
+            Event::ServiceDegraded { .. } | Event::CertificateExpiring { .. } | Event::ErrorBudgetBurn { .. } => {
+                ServiceStatus::Degraded
+            }
+        }
+    }
+
+    /// When the event happened.
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        match self {
+            Event::CheckCompleted { result, .. } => result.created_at,
+            Event::ServiceDown { occurred_at, .. }
+            | Event::ServiceRecovered { occurred_at, .. }
+            | Event::ServiceDegraded { occurred_at, .. }
+            | Event::CertificateExpiring { occurred_at, .. }
+            | Event::ErrorBudgetBurn { occurred_at, .. } => *occurred_at,
+            Event::IncidentOpened { started_at, .. } => *started_at,
+            Event::IncidentResolved { resolved_at, .. } => *resolved_at,
+        }
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::CheckCompleted { service, result } => {
+                let outcome = if result.success { "succeeded" } else { "failed" };
+                write!(f, "Healthcheck for {} {} in {}ms", service.name, outcome, result.response_time / 1000)
+            }
+            Event::ServiceDown { service, message, .. } => {
+                write!(f, "{} is DOWN: {}", service.name, message)
+            }
+            Event::ServiceRecovered { service, previous, .. } => {
+                write!(f, "{} is UP again (was {})", service.name, previous)
+            }
+            Event::ServiceDegraded { service, message, .. } => {
+                write!(f, "{} is DEGRADED: {}", service.name, message)
+            }
+            Event::CertificateExpiring { service, certificate, .. } => write!(
+                f,
+                "Certificate of {} expires in {} days on {}",
+                service.name,
+                certificate.days_until_expiry,
+                certificate.not_after.format("%Y-%m-%d")
+            ),
+            Event::IncidentOpened { service, incident_id, .. } => {
+                write!(f, "Incident {} opened for {}", incident_id, service.name)
+            }
+            Event::IncidentResolved { service, incident_id, duration_seconds, .. } => write!(
+                f,
+                "Incident {} for {} resolved after {}s",
+                incident_id, service.name, duration_seconds
+            ),
+            Event::ErrorBudgetBurn { service, objective, window, burn_rate, budget_remaining, .. } => write!(
+                f,
+                "{} is burning its {} error budget {:.1}x too fast over {} ({:.1}% left)",
+                service.name, objective, burn_rate, window, budget_remaining
+            ),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::service::Service;
 
 const DEFAULT_MAX_RETRIES: u8 = 3;
 const DEFAULT_TIMEOUT: u8 = 5;
+const DEFAULT_EXPIRY_WARNING_DAYS: u32 = 14;
 
 /// Trait for all entities that can be healthchecked.
 #[async_trait]
@@ -29,22 +30,138 @@ impl PartialEq for HealthCheckRequest {
 impl HealthCheckRequest {
     pub fn new(service: Service) -> Self {
         HealthCheckRequest {
-            service: service
+            service
         }
     }
 }
 
 /// HealthCheckResult represents the result of a healthcheck.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthCheckResult {
     pub id: Uuid,
     pub success: bool,
+    /// The check passed but something needs attention soon (e.g. a certificate close to expiry).
+    pub degraded: bool,
     pub code: u64,
     pub response_time: u128,
     pub message: String,
+    /// Why the check failed, unset when it succeeded.
+    pub error: Option<ErrorKind>,
+    /// Peer certificate details, recorded by TLS checks.
+    pub certificate: Option<CertificateInfo>,
+    /// Time spent in each phase of the request, recorded by HTTP checks.
+    pub timings: Option<HttpTimings>,
+    /// Location of the agent that performed the check, unset when the scheduler did.
+    pub location: Option<String>,
+    /// The check ran during a maintenance window of the service.
+    #[serde(default)]
+    pub maintenance: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// ErrorKind classifies why a check failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The host name did not resolve.
+    DnsFailure,
+    /// Nothing listens on the port.
+    ConnectionRefused,
+    /// The check did not complete within its timeout.
+    Timeout,
+    /// The TLS handshake failed or the certificate was rejected.
+    Tls,
+    /// The response status was not the expected one.
+    UnexpectedStatus,
+    /// The response did not satisfy an assertion.
+    AssertionFailed,
+    /// The response body exceeded the size limit.
+    BodyTooLarge,
+    /// Any other failure, such as a reset connection.
+    Other,
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ErrorKind::DnsFailure => "dns_failure",
+            ErrorKind::ConnectionRefused => "connection_refused",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Tls => "tls",
+            ErrorKind::UnexpectedStatus => "unexpected_status",
+            ErrorKind::AssertionFailed => "assertion_failed",
+            ErrorKind::BodyTooLarge => "body_too_large",
+            ErrorKind::Other => "other",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+impl From<String> for ErrorKind {
+    fn from(kind: String) -> Self {
+        match kind.as_str() {
+            "dns_failure" => ErrorKind::DnsFailure,
+            "connection_refused" => ErrorKind::ConnectionRefused,
+            "timeout" => ErrorKind::Timeout,
+            "tls" => ErrorKind::Tls,
+            "unexpected_status" => ErrorKind::UnexpectedStatus,
+            "assertion_failed" => ErrorKind::AssertionFailed,
+            "body_too_large" => ErrorKind::BodyTooLarge,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// HttpTimings breaks the response time of an HTTP check down into phases, in microseconds.
+///
+/// When the check follows redirects, each phase adds up the time of every request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpTimings {
+    pub dns_lookup_us: u64,
+    pub tcp_connect_us: u64,
+    /// Zero for plain HTTP.
+    pub tls_handshake_us: u64,
+    /// From sending the request to receiving the response headers.
+    pub time_to_first_byte_us: u64,
+    pub download_us: u64,
+}
+
+impl HttpTimings {
+    pub fn add(&mut self, other: &HttpTimings) {
+        self.dns_lookup_us += other.dns_lookup_us;
+        self.tcp_connect_us += other.tcp_connect_us;
+        self.tls_handshake_us += other.tls_handshake_us;
+        self.time_to_first_byte_us += other.time_to_first_byte_us;
+        self.download_us += other.download_us;
+    }
+}
+
+/// CertificateInfo describes the certificate presented by a TLS endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// Subject alternative names (DNS names and IP addresses).
+    pub sans: Vec<String>,
+    pub not_before: chrono::DateTime<chrono::Utc>,
+    pub not_after: chrono::DateTime<chrono::Utc>,
+    pub days_until_expiry: i64,
+    /// Whether the chain verified against the system trust store and matched the host.
+    pub chain_valid: bool,
+    pub verification_error: Option<String>,
+}
+
+/// LocationLatency compares the checks of a service performed from one location.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationLatency {
+    /// Location of the agent, unset for checks performed by the scheduler.
+    pub location: Option<String>,
+    pub total_checks: i64,
+    pub successful_checks: i64,
+    pub avg_response_time_ms: f64,
+    pub p95_response_time_ms: f64,
+}
+
 /// Kind represents the type of healthcheck to perform.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Kind {
@@ -52,6 +169,10 @@ pub enum Kind {
     HTTP(HttpChecker),
     /// A TCP healthcheck attempts to connect to a specified host and port.
     TCP(TcpChecker),
+    /// A DNS healthcheck resolves a record against a nameserver.
+    DNS(DnsChecker),
+    /// A TLS healthcheck inspects the certificate presented by a host and port.
+    TLS(TlsChecker),
 }
 
 impl Display for Kind {
@@ -59,16 +180,28 @@ impl Display for Kind {
         match self {
             Kind::HTTP(_) => write!(f, "HTTP"),
             Kind::TCP(_) => write!(f, "TCP"),
+            Kind::DNS(_) => write!(f, "DNS"),
+            Kind::TLS(_) => write!(f, "TLS"),
         }
     }
 }
 
-impl Into<String> for Kind {
-    fn into(self) -> String {
-        match self {
-            Kind::HTTP(_) => "HTTP".to_string(),
-            Kind::TCP(_) => "TCP".to_string(),
-        }
+impl Kind {
+    /// Host the check sends traffic to, unset for DNS checks using the system resolver
+    pub fn host(&self) -> Option<String> {
+        let host = match self {
+            Kind::HTTP(http) => url::Url::parse(&http.url).ok()?.host_str()?.to_string(),
+            Kind::TCP(tcp) => tcp.host.clone(),
+            Kind::DNS(dns) => dns.nameserver.clone()?,
+            Kind::TLS(tls) => tls.host.clone(),
+        };
+        Some(host.to_lowercase())
+    }
+}
+
+impl From<Kind> for String {
+    fn from(kind: Kind) -> Self {
+        kind.to_string()
     }
 }
 
@@ -77,6 +210,8 @@ impl From<String> for Kind {
         match kind.as_str() {
             "HTTP" => Kind::HTTP(HttpChecker::default()),
             "TCP" => Kind::TCP(TcpChecker::default()),
+            "DNS" => Kind::DNS(DnsChecker::default()),
+            "TLS" => Kind::TLS(TlsChecker::default()),
             _ => panic!("Invalid healthcheck kind"),
         }
     }
@@ -91,6 +226,72 @@ pub struct HttpChecker {
     pub body: Option<String>,
     pub timeout: u8,
     pub max_retries: u8,
+    /// Conditions the response must satisfy. Without a status assertion any 2xx passes.
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+    /// Follow redirects from HTTPS to plain HTTP, which are refused by default
+    #[serde(default)]
+    pub allow_insecure_redirects: bool,
+}
+
+/// Assertion represents a condition checked against an HTTP response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Assertion {
+    /// The status code must match one of the given codes or ranges.
+    Status { codes: Vec<StatusCodeMatcher> },
+    /// The body must contain the given substring.
+    BodyContains { value: String },
+    /// The body must not contain the given substring.
+    BodyNotContains { value: String },
+    /// The body must match the given regular expression.
+    BodyMatches { pattern: String },
+    /// The body must not match the given regular expression.
+    BodyNotMatches { pattern: String },
+    /// The JSONPath query against the body must yield the given value.
+    JsonPath { path: String, equals: serde_json::Value },
+    /// The response must carry the header, optionally with an exact value.
+    Header { name: String, value: Option<String> },
+    /// The response must arrive within the given number of milliseconds.
+    MaxResponseTime { ms: u64 },
+}
+
+/// StatusCodeMatcher matches an exact code (`204`) or a range (`"2xx"`, `"200-299"`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StatusCodeMatcher {
+    Code(u16),
+    Range(String),
+}
+
+impl StatusCodeMatcher {
+    pub fn matches(&self, code: u16) -> bool {
+        match self {
+            StatusCodeMatcher::Code(expected) => *expected == code,
+            StatusCodeMatcher::Range(range) => {
+                let range = range.trim().to_lowercase();
+                if let Some(class) = range.strip_suffix("xx") {
+                    return class.parse::<u16>().map(|c| code / 100 == c).unwrap_or(false);
+                }
+                match range.split_once('-') {
+                    Some((start, end)) => match (start.trim().parse::<u16>(), end.trim().parse::<u16>()) {
+                        (Ok(start), Ok(end)) => (start..=end).contains(&code),
+                        _ => false,
+                    },
+                    None => range.parse::<u16>().map(|c| c == code).unwrap_or(false),
+                }
+            }
+        }
+    }
+}
+
+impl Display for StatusCodeMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusCodeMatcher::Code(code) => write!(f, "{}", code),
+            StatusCodeMatcher::Range(range) => write!(f, "{}", range),
+        }
+    }
 }
 
 impl Default for HttpChecker {
@@ -102,6 +303,8 @@ impl Default for HttpChecker {
             body: None,
             timeout: DEFAULT_TIMEOUT,
             max_retries: DEFAULT_MAX_RETRIES,
+            assertions: Vec::new(),
+            allow_insecure_redirects: false,
         }
     }
 }
@@ -124,4 +327,86 @@ impl Default for TcpChecker {
             max_retries: DEFAULT_MAX_RETRIES,
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// DNS record types supported by the DNS checker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DnsRecordType {
+    #[default]
+    A,
+    AAAA,
+    CNAME,
+    MX,
+    TXT,
+    SRV,
+}
+
+impl Display for DnsRecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DnsRecordType::A => "A",
+            DnsRecordType::AAAA => "AAAA",
+            DnsRecordType::CNAME => "CNAME",
+            DnsRecordType::MX => "MX",
+            DnsRecordType::TXT => "TXT",
+            DnsRecordType::SRV => "SRV",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// DNS checker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsChecker {
+    /// Domain name to resolve.
+    pub domain: String,
+    pub record_type: DnsRecordType,
+    /// Nameserver to query, as `ip` or `ip:port`. Uses the system resolver when empty.
+    pub nameserver: Option<String>,
+    /// Values the answer set must match exactly. Any non-empty answer passes when empty.
+    pub expected: Vec<String>,
+    pub timeout: u8,
+    pub max_retries: u8,
+}
+
+impl Default for DnsChecker {
+    fn default() -> Self {
+        Self {
+            domain: String::new(),
+            record_type: DnsRecordType::A,
+            nameserver: None,
+            expected: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+/// TLS checker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsChecker {
+    pub host: String,
+    pub port: u16,
+    /// Certificates expiring within this many days are reported.
+    pub expiry_warning_days: u32,
+    /// Fail the check inside the warning window instead of marking it degraded.
+    pub fail_on_expiry_warning: bool,
+    /// Fail the check when the chain does not verify or does not match the host.
+    pub verify_chain: bool,
+    pub timeout: u8,
+    pub max_retries: u8,
+}
+
+impl Default for TlsChecker {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 443,
+            expiry_warning_days: DEFAULT_EXPIRY_WARNING_DAYS,
+            fail_on_expiry_warning: false,
+            verify_chain: true,
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
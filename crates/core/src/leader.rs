@@ -0,0 +1,77 @@
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+
+/// Prefix of rstat's advisory lock keys, "rstat" in ASCII, so they don't collide with other applications
+const LOCK_NAMESPACE: i64 = 0x0072_7374_6174 << 16;
+
+/// Lock held by the instance evaluating SLOs.
+pub const SLO_MONITOR_LOCK: i64 = LOCK_NAMESPACE | 1;
+/// Lock held by the instance sending daily digests.
+pub const DIGEST_LOCK: i64 = LOCK_NAMESPACE | 2;
+/// Lock held by the instance deleting expired rollups.
+pub const RETENTION_LOCK: i64 = LOCK_NAMESPACE | 3;
+
+/// Leader elects one of several rstat instances sharing a database to run a singleton task.
+///
+/// Leadership is a session-level Postgres advisory lock held on a dedicated connection.
+/// If the leader dies its connection closes, the lock is released, and another instance
+/// takes over the next time it asks.
+pub struct Leader {
+    pool: PgPool,
+    key: i64,
+    connection: Option<PoolConnection<Postgres>>,
+}
+
+impl Leader {
+    pub fn new(pool: PgPool, key: i64) -> Self {
+        Self { pool, key, connection: None }
+    }
+
+    /// Whether this instance is the leader, trying to become it when it is not.
+    pub async fn is_leader(&mut self) -> bool {
+        if let Some(connection) = self.connection.as_mut() {
+            // The lock lives as long as the connection
+            if sqlx::query("SELECT 1").execute(&mut **connection).await.is_ok() {
+                return true;
+            }
+            self.connection = None;
+        }
+
+        let Ok(mut connection) = self.pool.acquire().await else {
+            return false;
+        };
+        let locked = sqlx::query_scalar!(r#"SELECT pg_try_advisory_lock($1) AS "locked!""#, self.key)
+            .fetch_one(&mut *connection)
+            .await
+            .unwrap_or(false);
+
+        if locked {
+            self.connection = Some(connection);
+        }
+        locked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    #[sqlx::test(migrations = false)]
+    async fn test_one_leader_at_a_time(options: PgPoolOptions, connect: PgConnectOptions) -> sqlx::Result<()> {
+        let first = options.clone().connect_with(connect.clone()).await?;
+        let second = options.connect_with(connect).await?;
+        let mut leader = Leader::new(first.clone(), SLO_MONITOR_LOCK);
+        let mut follower = Leader::new(second, SLO_MONITOR_LOCK);
+
+        assert!(leader.is_leader().await);
+        assert!(!follower.is_leader().await);
+        assert!(leader.is_leader().await);
+
+        // The lock goes with the connection of an instance that stops
+        drop(leader);
+        first.close().await;
+        assert!(follower.is_leader().await);
+        Ok(())
+    }
+}
@@ -1,5 +1,15 @@
+pub mod event;
 pub mod healthcheck;
+pub mod leader;
+pub mod notification;
+pub mod schedule;
 pub mod service;
+pub mod slo;
 
+pub use event::*;
 pub use healthcheck::*;
-pub use service::*; 
\ No newline at end of file
+pub use leader::*;
+pub use notification::*;
+pub use schedule::*;
+pub use service::*;
+pub use slo::*; 
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+
+/// How many windows are tried when looking for the next run, a bit over a year of daily windows.
+/// A schedule that does not match within them is taken to never match.
+const MAX_WINDOW_STEPS: usize = 400;
+
+/// Schedule decides when a service is checked when a fixed interval is not enough.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    /// Cron expression (minute, hour, day of month, month, day of week) the service is
+    /// checked on, instead of its interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// Windows the service is checked in. The service is not checked outside of them,
+    /// it is checked all the time when there are none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<ActiveWindow>,
+    /// IANA time zone of the cron expression and windows, UTC when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// ActiveWindow is a daily stretch of time, e.g. business hours
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveWindow {
+    /// Days the window opens on, every day when empty.
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// A window ending before it starts runs past midnight, into the next day.
+    pub end: NaiveTime,
+}
+
+impl ActiveWindow {
+    fn opens_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains<T: TimeZone>(&self, at: &DateTime<T>) -> bool {
+        let time = at.time();
+        if self.start <= self.end {
+            self.opens_on(at.weekday()) && self.start <= time && time < self.end
+        } else {
+            (self.opens_on(at.weekday()) && time >= self.start)
+                || (self.opens_on(at.weekday().pred()) && time < self.end)
+        }
+    }
+}
+
+impl Schedule {
+    /// Check the cron expression and time zone, that windows are not empty and that the
+    /// cron expression matches within the windows
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        self.timezone()?;
+        let cron = self.cron()?;
+        if self.windows.iter().any(|window| window.start == window.end) {
+            return Err(anyhow::anyhow!("An active window must not start and end at the same time"));
+        }
+        if self.fit(Utc::now(), cron.as_ref(), true).is_none() {
+            return Err(anyhow::anyhow!("The schedule never matches, the cron expression falls outside the active windows"));
+        }
+        Ok(())
+    }
+
+    /// When to check the service next after a check at `at`: the next cron occurrence,
+    /// or an interval later, moved into the next window. None when the schedule never matches.
+    pub fn next_run(&self, at: DateTime<Utc>, interval: Duration) -> Option<DateTime<Utc>> {
+        match self.cron() {
+            Ok(Some(cron)) => self.fit(at, Some(&cron), false),
+            _ => self.fit(at + chrono::Duration::from_std(interval).unwrap_or_default(), None, true),
+        }
+    }
+
+    /// When to check a service created at `at` first, None when the schedule never matches
+    pub fn first_run(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let cron = self.cron().ok().flatten();
+        self.fit(at, cron.as_ref(), true)
+    }
+
+    /// Whether the service is checked at `at`
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.in_window(&at.with_timezone(&self.timezone().unwrap_or(Tz::UTC)))
+    }
+
+    fn in_window(&self, at: &DateTime<Tz>) -> bool {
+        self.windows.is_empty() || self.windows.iter().any(|window| window.contains(at))
+    }
+
+    /// The first time from `at` that matches the cron expression, if any, and falls in a window
+    fn fit(&self, at: DateTime<Utc>, cron: Option<&Cron>, inclusive: bool) -> Option<DateTime<Utc>> {
+        let tz = self.timezone().unwrap_or(Tz::UTC);
+        let mut at = at.with_timezone(&tz);
+        let mut inclusive = inclusive;
+
+        for _ in 0..MAX_WINDOW_STEPS {
+            if let Some(cron) = cron {
+                at = cron.find_next_occurrence(&at, inclusive).ok()?;
+            }
+            if self.in_window(&at) {
+                return Some(at.with_timezone(&Utc));
+            }
+            at = self.next_opening(&at)?;
+            inclusive = true;
+        }
+
+        None
+    }
+
+    /// When the next window opens after `at`
+    fn next_opening(&self, at: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        (0..=7)
+            .filter_map(|days| at.date_naive().checked_add_days(chrono::Days::new(days)))
+            .flat_map(|date| {
+                self.windows
+                    .iter()
+                    .filter(move |window| window.opens_on(date.weekday()))
+                    .filter_map(move |window| at.timezone().from_local_datetime(&date.and_time(window.start)).earliest())
+            })
+            .filter(|opening| opening > at)
+            .min()
+    }
+
+    fn timezone(&self) -> Result<Tz, anyhow::Error> {
+        match &self.timezone {
+            Some(timezone) => timezone
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Unknown time zone {}", timezone)),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    fn cron(&self) -> Result<Option<Cron>, anyhow::Error> {
+        self.cron
+            .as_ref()
+            .map(|cron| {
+                Cron::new(cron)
+                    .parse()
+                    .map_err(|err| anyhow::anyhow!("Invalid cron expression {}: {}", cron, err))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_schedule_next_run() {
+        let business_hours: Schedule = serde_json::from_str(
+            r#"{"cron": "*/5 * * * *", "timezone": "Europe/Berlin", "windows": [{"days": ["Mon", "Tue", "Wed", "Thu", "Fri"], "start": "09:00", "end": "18:00"}]}"#,
+        )
+        .unwrap();
+        // Friday 17:50 in Berlin, summer time
+        assert_eq!(business_hours.next_run(at("2025-07-11T15:50:00Z"), Duration::from_secs(60)), Some(at("2025-07-11T15:55:00Z")));
+        // The window closes at 18:00, the next run is on Monday morning
+        assert_eq!(business_hours.next_run(at("2025-07-11T15:55:00Z"), Duration::from_secs(60)), Some(at("2025-07-14T07:00:00Z")));
+        assert!(!business_hours.is_active(at("2025-07-12T10:00:00Z")));
+
+        // Intervals are moved into the window too, which may cross midnight
+        let nightly = Schedule {
+            windows: vec![ActiveWindow { days: vec![], start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(), end: NaiveTime::from_hms_opt(2, 0, 0).unwrap() }],
+            ..Schedule::default()
+        };
+        assert_eq!(nightly.next_run(at("2025-07-11T23:59:00Z"), Duration::from_secs(120)), Some(at("2025-07-12T00:01:00Z")));
+        assert_eq!(nightly.next_run(at("2025-07-12T01:59:00Z"), Duration::from_secs(120)), Some(at("2025-07-12T22:00:00Z")));
+        assert_eq!(nightly.first_run(at("2025-07-12T22:30:00Z")), Some(at("2025-07-12T22:30:00Z")));
+
+        let daily = Schedule { cron: Some("0 2 * * *".to_string()), ..Schedule::default() };
+        assert_eq!(daily.first_run(at("2025-07-12T02:00:00Z")), Some(at("2025-07-12T02:00:00Z")));
+        assert_eq!(daily.next_run(at("2025-07-12T02:00:00.250Z"), Duration::from_secs(60)), Some(at("2025-07-13T02:00:00Z")));
+
+        assert!(Schedule { cron: Some("every day".to_string()), ..Schedule::default() }.validate().is_err());
+        assert!(Schedule { timezone: Some("Mars/Olympus".to_string()), ..Schedule::default() }.validate().is_err());
+        assert!(business_hours.validate().is_ok());
+
+        // Empty windows and cron expressions outside the windows never match
+        let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let empty = Schedule { windows: vec![ActiveWindow { days: vec![], start: noon, end: noon }], ..Schedule::default() };
+        assert!(empty.validate().is_err());
+        let night_checks = Schedule { cron: Some("0 3 * * *".to_string()), ..business_hours.clone() };
+        assert!(night_checks.validate().is_err());
+        assert_eq!(night_checks.first_run(at("2025-07-11T15:50:00Z")), None);
+        assert_eq!(night_checks.next_run(at("2025-07-11T15:50:00Z"), Duration::from_secs(60)), None);
+    }
+}
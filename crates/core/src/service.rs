@@ -1,9 +1,16 @@
+use std::fmt::Display;
 use std::time::Duration;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-use crate::healthcheck::Kind;
+use crate::healthcheck::{HealthCheckResult, Kind};
+use crate::notification::NotificationSettings;
+use crate::schedule::Schedule;
+use crate::slo::SloConfig;
+
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+pub const DEFAULT_SUCCESS_THRESHOLD: u32 = 1;
 
 /// Service represents an entity that can be checked for health.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -19,10 +26,343 @@ pub struct Service {
     pub interval: Duration,
     /// The next time this service should be checked.
     pub next_run: DateTime<Utc>,
+    /// Optional settings supplied when the service was created.
+    #[serde(flatten)]
+    pub options: ServiceOptions,
+    /// The current status of the service, derived from consecutive check outcomes.
+    pub state: ServiceState,
+}
+
+impl Service {
+    /// The service with the credentials of its notification channels masked, safe to
+    /// return from the API.
+    pub fn redacted(mut self) -> Self {
+        self.options.notifications = self.options.notifications.redacted();
+        self
+    }
 }
 
 impl PartialEq for Service {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
     }
-} 
\ No newline at end of file
+}
+
+/// ServiceOptions holds the optional per-service settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceOptions {
+    /// Consecutive failed checks needed before the service is marked DOWN.
+    pub failure_threshold: u32,
+    /// Consecutive successful checks needed before a DOWN service is marked UP again.
+    pub success_threshold: u32,
+    /// Channels notified about this service in addition to the global ones.
+    pub notifications: NotificationSettings,
+    /// Service level objectives, evaluated against the service's metrics.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slo: Option<SloConfig>,
+    /// Locations of the agents checking the service. Services without locations are
+    /// checked by the scheduler.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<String>,
+    /// Labels grouping the service with others, e.g. for maintenance windows.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// When the service is checked, instead of or on top of its interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+}
+
+impl Default for ServiceOptions {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            success_threshold: DEFAULT_SUCCESS_THRESHOLD,
+            notifications: NotificationSettings::default(),
+            slo: None,
+            locations: Vec::new(),
+            tags: Vec::new(),
+            schedule: None,
+        }
+    }
+}
+
+/// ServiceStatus is the authoritative status of a service.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ServiceStatus {
+    Up,
+    Down,
+    Degraded,
+    #[default]
+    Unknown,
+    Paused,
+}
+
+impl Display for ServiceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServiceStatus::Up => write!(f, "UP"),
+            ServiceStatus::Down => write!(f, "DOWN"),
+            ServiceStatus::Degraded => write!(f, "DEGRADED"),
+            ServiceStatus::Unknown => write!(f, "UNKNOWN"),
+            ServiceStatus::Paused => write!(f, "PAUSED"),
+        }
+    }
+}
+
+impl From<String> for ServiceStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "UP" => ServiceStatus::Up,
+            "DOWN" => ServiceStatus::Down,
+            "DEGRADED" => ServiceStatus::Degraded,
+            "PAUSED" => ServiceStatus::Paused,
+            _ => ServiceStatus::Unknown,
+        }
+    }
+}
+
+impl ServiceStatus {
+    /// How bad the status is, when the statuses of several locations are combined
+    fn severity(&self) -> u8 {
+        match self {
+            ServiceStatus::Unknown | ServiceStatus::Paused => 0,
+            ServiceStatus::Up => 1,
+            ServiceStatus::Degraded => 2,
+            ServiceStatus::Down => 3,
+        }
+    }
+}
+
+/// CheckOutcome classifies a single healthcheck result for the state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Healthy,
+    Degraded,
+    Failed,
+}
+
+impl From<&HealthCheckResult> for CheckOutcome {
+    fn from(result: &HealthCheckResult) -> Self {
+        match (result.success, result.degraded) {
+            (false, _) => CheckOutcome::Failed,
+            (true, true) => CheckOutcome::Degraded,
+            (true, false) => CheckOutcome::Healthy,
+        }
+    }
+}
+
+/// StatusTransition records a change of a service's status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatusTransition {
+    pub from: ServiceStatus,
+    pub to: ServiceStatus,
+}
+
+/// ServiceState is the persisted state machine of a service.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceState {
+    pub status: ServiceStatus,
+    pub consecutive_failures: u32,
+    pub consecutive_successes: u32,
+    /// When the status last changed.
+    pub changed_at: DateTime<Utc>,
+}
+
+impl Default for ServiceState {
+    fn default() -> Self {
+        Self {
+            status: ServiceStatus::Unknown,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+            changed_at: Utc::now(),
+        }
+    }
+}
+
+impl ServiceState {
+    /// Record a check outcome and return the transition it caused, if any.
+    ///
+    /// A service, UNKNOWN ones included, only goes DOWN after `failure_threshold`
+    /// consecutive failures and only leaves DOWN after `success_threshold` consecutive
+    /// successes. UP and DEGRADED follow the latest successful result. PAUSED services
+    /// are left untouched.
+    pub fn apply(&mut self, outcome: CheckOutcome, options: &ServiceOptions) -> Option<StatusTransition> {
+        if self.status == ServiceStatus::Paused {
+            return None;
+        }
+
+        let target = match outcome {
+            CheckOutcome::Failed => {
+                self.consecutive_failures += 1;
+                self.consecutive_successes = 0;
+
+                if self.consecutive_failures >= options.failure_threshold.max(1) {
+                    ServiceStatus::Down
+                } else {
+                    self.status
+                }
+            }
+            CheckOutcome::Healthy | CheckOutcome::Degraded => {
+                self.consecutive_successes += 1;
+                self.consecutive_failures = 0;
+
+                let reached = if outcome == CheckOutcome::Healthy {
+                    ServiceStatus::Up
+                } else {
+                    ServiceStatus::Degraded
+                };
+                let threshold_reached = self.consecutive_successes >= options.success_threshold.max(1);
+                if self.status != ServiceStatus::Down || threshold_reached {
+                    reached
+                } else {
+                    self.status
+                }
+            }
+        };
+
+        if target == self.status {
+            return None;
+        }
+
+        let transition = StatusTransition { from: self.status, to: target };
+        self.status = target;
+        self.changed_at = Utc::now();
+        Some(transition)
+    }
+
+    /// Follow the states of the locations a service is checked from, after one of them
+    /// applied an outcome to its own state.
+    ///
+    /// Every location keeps its own streaks, so results from a healthy location don't
+    /// reset those of a failing one. The service takes the worst known status of the
+    /// locations and the streaks of the reporting one. PAUSED services are left untouched.
+    pub fn follow(&mut self, reporting: &ServiceState, others: &[ServiceStatus]) -> Option<StatusTransition> {
+        if self.status == ServiceStatus::Paused {
+            return None;
+        }
+
+        self.consecutive_failures = reporting.consecutive_failures;
+        self.consecutive_successes = reporting.consecutive_successes;
+        // Locations that don't know yet say nothing about the service
+        let target = others
+            .iter()
+            .chain([&reporting.status])
+            .filter(|status| status.severity() > 0)
+            .max_by_key(|status| status.severity())
+            .copied()
+            .unwrap_or(self.status);
+
+        if target == self.status {
+            return None;
+        }
+
+        let transition = StatusTransition { from: self.status, to: target };
+        self.status = target;
+        self.changed_at = Utc::now();
+        Some(transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(failure_threshold: u32, success_threshold: u32) -> ServiceOptions {
+        ServiceOptions { failure_threshold, success_threshold, ..ServiceOptions::default() }
+    }
+
+    #[test]
+    fn test_unknown_down_after_consecutive_failures() {
+        let opts = options(3, 2);
+        let mut state = ServiceState::default();
+
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        assert_eq!(state.status, ServiceStatus::Unknown);
+        assert_eq!(
+            state.apply(CheckOutcome::Failed, &opts),
+            Some(StatusTransition { from: ServiceStatus::Unknown, to: ServiceStatus::Down })
+        );
+    }
+
+    #[test]
+    fn test_unknown_up_on_first_success() {
+        let mut state = ServiceState::default();
+        let transition = state.apply(CheckOutcome::Healthy, &options(3, 2));
+        assert_eq!(transition, Some(StatusTransition { from: ServiceStatus::Unknown, to: ServiceStatus::Up }));
+    }
+
+    #[test]
+    fn test_down_after_consecutive_failures() {
+        let opts = options(3, 2);
+        let mut state = ServiceState { status: ServiceStatus::Up, ..ServiceState::default() };
+
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        // A success in between resets the streak
+        assert_eq!(state.apply(CheckOutcome::Healthy, &opts), None);
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        assert_eq!(state.apply(CheckOutcome::Failed, &opts), None);
+        assert_eq!(
+            state.apply(CheckOutcome::Failed, &opts),
+            Some(StatusTransition { from: ServiceStatus::Up, to: ServiceStatus::Down })
+        );
+        assert_eq!(state.consecutive_failures, 3);
+    }
+
+    #[test]
+    fn test_recovery_after_consecutive_successes() {
+        let opts = options(1, 2);
+        let mut state = ServiceState { status: ServiceStatus::Down, ..ServiceState::default() };
+
+        assert_eq!(state.apply(CheckOutcome::Degraded, &opts), None);
+        assert_eq!(
+            state.apply(CheckOutcome::Degraded, &opts),
+            Some(StatusTransition { from: ServiceStatus::Down, to: ServiceStatus::Degraded })
+        );
+        assert_eq!(
+            state.apply(CheckOutcome::Healthy, &opts),
+            Some(StatusTransition { from: ServiceStatus::Degraded, to: ServiceStatus::Up })
+        );
+    }
+
+    #[test]
+    fn test_follow_takes_the_worst_location() {
+        let opts = options(2, 1);
+        let mut service = ServiceState { status: ServiceStatus::Up, ..ServiceState::default() };
+        let mut failing = ServiceState { status: ServiceStatus::Up, ..ServiceState::default() };
+
+        // Successes elsewhere don't reset the failing location's streak
+        failing.apply(CheckOutcome::Failed, &opts);
+        assert_eq!(service.follow(&failing, &[ServiceStatus::Up]), None);
+        assert_eq!(service.consecutive_failures, 1);
+        failing.apply(CheckOutcome::Failed, &opts);
+        assert_eq!(
+            service.follow(&failing, &[ServiceStatus::Up]),
+            Some(StatusTransition { from: ServiceStatus::Up, to: ServiceStatus::Down })
+        );
+
+        // The service stays down while any location is down
+        let healthy = ServiceState { status: ServiceStatus::Up, consecutive_successes: 5, ..ServiceState::default() };
+        assert_eq!(service.follow(&healthy, &[ServiceStatus::Down]), None);
+        assert_eq!(service.status, ServiceStatus::Down);
+
+        // A location that does not know yet changes nothing
+        let new = ServiceState { consecutive_failures: 1, ..ServiceState::default() };
+        let mut up = ServiceState { status: ServiceStatus::Up, ..ServiceState::default() };
+        assert_eq!(up.follow(&new, &[]), None);
+        assert_eq!(up.status, ServiceStatus::Up);
+
+        let mut paused = ServiceState { status: ServiceStatus::Paused, ..ServiceState::default() };
+        assert_eq!(paused.follow(&failing, &[]), None);
+    }
+
+    #[test]
+    fn test_paused_ignores_results() {
+        let mut state = ServiceState { status: ServiceStatus::Paused, ..ServiceState::default() };
+        assert_eq!(state.apply(CheckOutcome::Failed, &options(1, 1)), None);
+        assert_eq!(state.status, ServiceStatus::Paused);
+    }
+}
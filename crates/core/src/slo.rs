@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// SloConfig declares the service level objectives of a service.
+///
+/// Objectives are measured over a rolling window of `window_days` days.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SloConfig {
+    /// Percentage of checks that must succeed, e.g. 99.9
+    pub availability: f64,
+    #[serde(default = "default_window_days")]
+    pub window_days: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyObjective>,
+}
+
+/// LatencyObjective requires a percentage of successful checks to be faster than a threshold,
+/// e.g. 95% of checks under 300ms.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LatencyObjective {
+    pub target: f64,
+    pub threshold_ms: u32,
+}
+
+fn default_window_days() -> u32 {
+    30
+}
+
+impl SloConfig {
+    /// Check that the window is not empty and the targets are percentages that can be both met and missed
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.window_days == 0 {
+            return Err(anyhow::anyhow!("An SLO window must last at least a day"));
+        }
+        validate_target("availability", self.availability)?;
+        if let Some(latency) = &self.latency {
+            validate_target("latency target", latency.target)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_target(name: &str, target: f64) -> Result<(), anyhow::Error> {
+    if !(target > 0.0 && target < 100.0) {
+        return Err(anyhow::anyhow!("The SLO {} must be between 0 and 100 percent, got {}", name, target));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let config = SloConfig {
+            availability: 99.9,
+            window_days: 30,
+            latency: Some(LatencyObjective { target: 95.0, threshold_ms: 300 }),
+        };
+        assert!(config.validate().is_ok());
+
+        assert!(SloConfig { window_days: 0, ..config.clone() }.validate().is_err());
+        for availability in [0.0, 100.0, 120.0, f64::NAN] {
+            assert!(SloConfig { availability, ..config.clone() }.validate().is_err());
+        }
+        let latency = Some(LatencyObjective { target: 100.0, threshold_ms: 300 });
+        assert!(SloConfig { latency, ..config }.validate().is_err());
+    }
+}
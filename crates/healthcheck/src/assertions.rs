@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use http::HeaderMap;
+use regex::Regex;
+use rstat_core::{Assertion, ErrorKind};
+use serde_json_path::JsonPath;
+
+/// Response holds the parts of an HTTP response that assertions are evaluated against.
+pub struct Response<'a> {
+    pub status: u16,
+    pub headers: &'a HeaderMap,
+    pub body: &'a str,
+    pub elapsed: Duration,
+}
+
+/// Evaluate assertions in order, returning the description of the first one that fails.
+pub fn evaluate(assertions: &[Assertion], response: &Response) -> Result<(), String> {
+    for assertion in assertions {
+        check(assertion, response).map_err(|reason| format!("Assertion failed: {}", reason))?;
+    }
+    Ok(())
+}
+
+/// Whether the assertions define their own status expectation.
+pub fn has_status_assertion(assertions: &[Assertion]) -> bool {
+    assertions.iter().any(|a| matches!(a, Assertion::Status { .. }))
+}
+
+/// Classify failed assertions: a wrong status outranks any other failed assertion.
+pub fn failure_kind(assertions: &[Assertion], status: u16) -> ErrorKind {
+    let status_failed = assertions
+        .iter()
+        .any(|a| matches!(a, Assertion::Status { codes } if !codes.iter().any(|c| c.matches(status))));
+    if status_failed {
+        ErrorKind::UnexpectedStatus
+    } else {
+        ErrorKind::AssertionFailed
+    }
+}
+
+fn check(assertion: &Assertion, response: &Response) -> Result<(), String> {
+    match assertion {
+        Assertion::Status { codes } => {
+            if codes.iter().any(|c| c.matches(response.status)) {
+                return Ok(());
+            }
+            let expected: Vec<String> = codes.iter().map(|c| c.to_string()).collect();
+            Err(format!(
+                "expected status in [{}], got {}",
+                expected.join(", "),
+                response.status
+            ))
+        }
+        Assertion::BodyContains { value } => {
+            if response.body.contains(value.as_str()) {
+                Ok(())
+            } else {
+                Err(format!("body does not contain \"{}\"", value))
+            }
+        }
+        Assertion::BodyNotContains { value } => {
+            if response.body.contains(value.as_str()) {
+                Err(format!("body contains \"{}\"", value))
+            } else {
+                Ok(())
+            }
+        }
+        Assertion::BodyMatches { pattern } => {
+            let regex = compile(pattern)?;
+            if regex.is_match(response.body) {
+                Ok(())
+            } else {
+                Err(format!("body does not match /{}/", pattern))
+            }
+        }
+        Assertion::BodyNotMatches { pattern } => {
+            let regex = compile(pattern)?;
+            if regex.is_match(response.body) {
+                Err(format!("body matches /{}/", pattern))
+            } else {
+                Ok(())
+            }
+        }
+        Assertion::JsonPath { path, equals } => {
+            let query = JsonPath::parse(path).map_err(|e| format!("invalid JSONPath {}: {}", path, e))?;
+            let body: serde_json::Value = serde_json::from_str(response.body)
+                .map_err(|e| format!("body is not valid JSON: {}", e))?;
+
+            let nodes = query.query(&body).all();
+            let actual = match nodes.as_slice() {
+                [] => return Err(format!("{} not found in body", path)),
+                [node] => (*node).clone(),
+                nodes => serde_json::Value::Array(nodes.iter().map(|n| (*n).clone()).collect()),
+            };
+
+            if &actual == equals {
+                Ok(())
+            } else {
+                Err(format!("expected {} to equal {}, got {}", path, equals, actual))
+            }
+        }
+        Assertion::Header { name, value } => {
+            let header = response
+                .headers
+                .get(name.as_str())
+                .ok_or_else(|| format!("missing header {}", name))?;
+
+            match value {
+                Some(expected) => {
+                    let actual = header.to_str().unwrap_or_default();
+                    if actual == expected {
+                        Ok(())
+                    } else {
+                        Err(format!("expected header {} to equal \"{}\", got \"{}\"", name, expected, actual))
+                    }
+                }
+                None => Ok(()),
+            }
+        }
+        Assertion::MaxResponseTime { ms } => {
+            let elapsed = response.elapsed.as_millis();
+            if elapsed <= *ms as u128 {
+                Ok(())
+            } else {
+                Err(format!("response took {}ms, limit is {}ms", elapsed, ms))
+            }
+        }
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("invalid regex /{}/: {}", pattern, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstat_core::StatusCodeMatcher;
+
+    fn response<'a>(status: u16, headers: &'a HeaderMap, body: &'a str) -> Response<'a> {
+        Response {
+            status,
+            headers,
+            body,
+            elapsed: Duration::from_millis(120),
+        }
+    }
+
+    #[test]
+    fn test_status_ranges() {
+        let headers = HeaderMap::new();
+        let assertions = vec![Assertion::Status {
+            codes: vec![
+                StatusCodeMatcher::Code(204),
+                StatusCodeMatcher::Range("3xx".to_string()),
+                StatusCodeMatcher::Range("500-502".to_string()),
+            ],
+        }];
+
+        assert!(evaluate(&assertions, &response(204, &headers, "")).is_ok());
+        assert!(evaluate(&assertions, &response(301, &headers, "")).is_ok());
+        assert!(evaluate(&assertions, &response(501, &headers, "")).is_ok());
+        assert!(evaluate(&assertions, &response(200, &headers, "")).is_err());
+    }
+
+    #[test]
+    fn test_json_path_reports_degraded_body() {
+        let headers = HeaderMap::new();
+        let assertions = vec![Assertion::JsonPath {
+            path: "$.status".to_string(),
+            equals: serde_json::json!("ok"),
+        }];
+
+        assert!(evaluate(&assertions, &response(200, &headers, r#"{"status":"ok"}"#)).is_ok());
+
+        let err = evaluate(&assertions, &response(200, &headers, r#"{"status":"degraded"}"#)).unwrap_err();
+        assert_eq!(err, r#"Assertion failed: expected $.status to equal "ok", got "degraded""#);
+    }
+
+    #[test]
+    fn test_first_failing_assertion_is_reported() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let assertions = vec![
+            Assertion::Header { name: "Content-Type".to_string(), value: Some("application/json".to_string()) },
+            Assertion::BodyMatches { pattern: r#""version":\s*"\d+""#.to_string() },
+            Assertion::BodyNotContains { value: "error".to_string() },
+            Assertion::MaxResponseTime { ms: 100 },
+        ];
+
+        let err = evaluate(&assertions, &response(200, &headers, r#"{"version": "3"}"#)).unwrap_err();
+        assert_eq!(err, "Assertion failed: response took 120ms, limit is 100ms");
+    }
+}
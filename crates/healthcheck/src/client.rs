@@ -0,0 +1,358 @@
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use http::header::{AUTHORIZATION, COOKIE, HOST, LOCATION, PROXY_AUTHORIZATION};
+use http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::Bytes;
+use hyper_util::rt::TokioIo;
+use openssl::ssl::{SslConnector, SslMethod};
+use rstat_core::{ErrorKind, HttpTimings};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_openssl::SslStream;
+use tracing::debug;
+use url::{Host, Position, Url};
+
+/// Redirects followed before giving up, as many as reqwest follows by default
+const MAX_REDIRECTS: usize = 10;
+
+/// Largest response body read, checks with bigger bodies fail
+pub const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// RequestError is a failed request, classified by what went wrong
+#[derive(Debug)]
+pub struct RequestError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl RequestError {
+    pub fn new(kind: ErrorKind, error: impl Display) -> Self {
+        Self { kind, message: error.to_string() }
+    }
+
+    /// Classify a failed connection attempt
+    pub fn io(error: std::io::Error) -> Self {
+        let kind = match error.kind() {
+            std::io::ErrorKind::ConnectionRefused => ErrorKind::ConnectionRefused,
+            std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            _ => ErrorKind::Other,
+        };
+        Self::new(kind, error)
+    }
+
+    fn other(error: impl Display) -> Self {
+        Self::new(ErrorKind::Other, error)
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// TimedResponse is an HTTP response along with how long each phase of getting it took
+#[derive(Debug)]
+pub struct TimedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+    pub timings: HttpTimings,
+}
+
+/// Send a request and follow its redirects, timing each phase.
+///
+/// Every request goes over a fresh HTTP/1.1 connection, so DNS lookup, TCP connect and
+/// TLS handshake are measured on each check instead of being hidden by a connection pool.
+/// The flip side is that checks connect directly: `HTTP_PROXY` and `HTTPS_PROXY` are not
+/// honored, HTTP/2 is never negotiated and compressed bodies are not decoded, as no
+/// `Accept-Encoding` is sent. Redirects from HTTPS to plain HTTP fail unless
+/// `allow_insecure_redirects` is set.
+pub async fn send(
+    method: Method,
+    url: &str,
+    headers: &HeaderMap,
+    body: &str,
+    allow_insecure_redirects: bool,
+) -> Result<TimedResponse, RequestError> {
+    let mut url = Url::parse(url).map_err(RequestError::other)?;
+    let mut headers = headers.clone();
+    let mut method = method;
+    let mut body = body.to_string();
+    let mut timings = HttpTimings::default();
+
+    for _ in 0..=MAX_REDIRECTS {
+        let mut response = send_once(method.clone(), &url, &headers, &body).await?;
+        timings.add(&response.timings);
+
+        let location = response.headers.get(LOCATION).and_then(|location| location.to_str().ok());
+        let location = match location {
+            Some(location) if response.status.is_redirection() => url.join(location).map_err(RequestError::other)?,
+            _ => {
+                response.timings = timings;
+                return Ok(response);
+            }
+        };
+
+        debug!("Following redirect from {} to {}", url, location);
+        redirect_headers(&url, &location, &mut headers, allow_insecure_redirects)?;
+        // 301, 302 and 303 turn into a GET without body, 307 and 308 repeat the request
+        if matches!(response.status.as_u16(), 301..=303) && method != Method::HEAD {
+            method = Method::GET;
+            body.clear();
+        }
+        url = location;
+    }
+
+    Err(RequestError::other("Too many redirects"))
+}
+
+/// Check that a redirect can be followed and drop the credentials another origin must not get
+fn redirect_headers(
+    from: &Url,
+    to: &Url,
+    headers: &mut HeaderMap,
+    allow_insecure_redirects: bool,
+) -> Result<(), RequestError> {
+    if from.scheme() == "https" && to.scheme() == "http" && !allow_insecure_redirects {
+        return Err(RequestError::other(format!("Refusing to follow a redirect from {} to plain HTTP {}", from, to)));
+    }
+    if from.origin() != to.origin() {
+        for header in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+            headers.remove(header);
+        }
+    }
+    Ok(())
+}
+
+/// Send a single request over a new connection
+async fn send_once(method: Method, url: &Url, headers: &HeaderMap, body: &str) -> Result<TimedResponse, RequestError> {
+    let mut timings = HttpTimings::default();
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| RequestError::other(format!("URL {} has no port", url)))?;
+
+    let start = Instant::now();
+    let (host, addrs) = match url.host() {
+        Some(Host::Domain(domain)) => {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| RequestError::new(ErrorKind::DnsFailure, e))?
+                .collect();
+            if addrs.is_empty() {
+                return Err(RequestError::new(ErrorKind::DnsFailure, format!("{} did not resolve to any address", domain)));
+            }
+            (domain.to_string(), addrs)
+        }
+        Some(Host::Ipv4(ip)) => (ip.to_string(), vec![SocketAddr::from((ip, port))]),
+        Some(Host::Ipv6(ip)) => (ip.to_string(), vec![SocketAddr::from((ip, port))]),
+        None => return Err(RequestError::other(format!("URL {} has no host", url))),
+    };
+    timings.dns_lookup_us = start.elapsed().as_micros() as u64;
+
+    // Addresses are tried in turn, so a host whose IPv6 address is unreachable is still
+    // reached over IPv4 and the other way around
+    let start = Instant::now();
+    let tcp = connect(&addrs).await?;
+    timings.tcp_connect_us = start.elapsed().as_micros() as u64;
+
+    let mut request = Request::builder()
+        .method(method)
+        .uri(&url[Position::BeforePath..Position::AfterQuery])
+        .body(Full::new(Bytes::from(body.to_string())))
+        .map_err(RequestError::other)?;
+    *request.headers_mut() = headers.clone();
+    let host_header = HeaderValue::from_str(&url[Position::BeforeHost..Position::AfterPort]).map_err(RequestError::other)?;
+    request.headers_mut().insert(HOST, host_header);
+
+    match url.scheme() {
+        "http" => exchange(tcp, request, timings).await,
+        "https" => {
+            let start = Instant::now();
+            let stream = tls_connect(&host, tcp).await.map_err(|e| RequestError::new(ErrorKind::Tls, e))?;
+            timings.tls_handshake_us = start.elapsed().as_micros() as u64;
+
+            exchange(stream, request, timings).await
+        }
+        scheme => Err(RequestError::other(format!("Unsupported URL scheme {}", scheme))),
+    }
+}
+
+/// Connect to the first of the addresses that accepts, failing with the last error
+pub(crate) async fn connect(addrs: &[SocketAddr]) -> Result<TcpStream, RequestError> {
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect(addr).await {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => {
+                debug!("Failed to connect to {}: {}", addr, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.map_or_else(|| RequestError::other("No address to connect to"), RequestError::io))
+}
+
+/// Complete a TLS handshake, verifying the certificate chain and host name
+async fn tls_connect(host: &str, tcp: TcpStream) -> Result<SslStream<TcpStream>, anyhow::Error> {
+    let ssl = SslConnector::builder(SslMethod::tls())?.build().configure()?.into_ssl(host)?;
+    let mut stream = SslStream::new(ssl, tcp)?;
+    Pin::new(&mut stream).connect().await?;
+    Ok(stream)
+}
+
+/// Send a request over an established connection and read the whole response
+async fn exchange<S>(
+    stream: S,
+    request: Request<Full<Bytes>>,
+    mut timings: HttpTimings,
+) -> Result<TimedResponse, RequestError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(RequestError::other)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            debug!("HTTP connection closed with error: {}", e);
+        }
+    });
+
+    let start = Instant::now();
+    let response = sender.send_request(request).await.map_err(RequestError::other)?;
+    timings.time_to_first_byte_us = start.elapsed().as_micros() as u64;
+
+    let start = Instant::now();
+    let (parts, body) = response.into_parts();
+    let body = Limited::new(body, MAX_BODY_BYTES)
+        .collect()
+        .await
+        .map_err(|e| match e.downcast_ref::<LengthLimitError>() {
+            Some(_) => RequestError::new(ErrorKind::BodyTooLarge, format!("Response body exceeds {} bytes", MAX_BODY_BYTES)),
+            None => RequestError::other(e),
+        })?
+        .to_bytes();
+    timings.download_us = start.elapsed().as_micros() as u64;
+
+    Ok(TimedResponse {
+        status: parts.status,
+        headers: parts.headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+        timings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve canned responses, one per connection
+    async fn start_server(responses: Vec<String>) -> u16 {
+        start_recording_server(responses).await.0
+    }
+
+    /// Serve canned responses, one per connection, and keep the requests received
+    async fn start_recording_server(responses: Vec<String>) -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let read = socket.read(&mut buf).await.unwrap();
+                received.lock().unwrap().push(String::from_utf8_lossy(&buf[..read]).into_owned());
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (port, requests)
+    }
+
+    #[tokio::test]
+    async fn test_send_follows_redirects_and_times_phases() {
+        let port = start_server(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /ok\r\nContent-Length: 0\r\n\r\n".to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello".to_string(),
+        ])
+        .await;
+
+        let url = format!("http://127.0.0.1:{}/start", port);
+        let response = send(Method::POST, &url, &HeaderMap::new(), "ping", false).await.unwrap();
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, "hello");
+        assert_eq!(response.timings.tls_handshake_us, 0);
+        assert!(response.timings.time_to_first_byte_us > 0);
+
+        // Nothing listens on a port once its listener is dropped
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let refused = send(Method::GET, &format!("http://127.0.0.1:{}/", port), &HeaderMap::new(), "", false).await;
+        assert_eq!(refused.unwrap_err().kind, ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_connect_tries_every_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let refusing = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+
+        let tcp = connect(&[refusing, listener.local_addr().unwrap()]).await.unwrap();
+        assert_eq!(tcp.peer_addr().unwrap(), listener.local_addr().unwrap());
+        assert_eq!(connect(&[refusing]).await.unwrap_err().kind, ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_to_another_origin_drops_credentials() {
+        let (target, received) = start_recording_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+        ])
+        .await;
+        // localhost is another origin than 127.0.0.1, and may resolve to an unreachable ::1 first
+        let (redirecting, redirected) = start_recording_server(vec![format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/ok\r\nContent-Length: 0\r\n\r\n",
+            target
+        )])
+        .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+        let url = format!("http://127.0.0.1:{}/", redirecting);
+        let response = send(Method::GET, &url, &headers, "", false).await.unwrap();
+
+        assert_eq!(response.body, "ok");
+        assert!(redirected.lock().unwrap()[0].to_lowercase().contains("authorization: bearer abc"));
+        assert!(!received.lock().unwrap()[0].to_lowercase().contains("authorization"));
+    }
+
+    #[test]
+    fn test_redirect_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+        headers.insert(COOKIE, HeaderValue::from_static("session=1"));
+        headers.insert("X-Request", HeaderValue::from_static("check"));
+        let from = Url::parse("https://example.com/health").unwrap();
+
+        // Credentials stay on the same origin
+        redirect_headers(&from, &from.join("/ok").unwrap(), &mut headers, false).unwrap();
+        assert!(headers.contains_key(AUTHORIZATION));
+
+        redirect_headers(&from, &Url::parse("https://example.org/").unwrap(), &mut headers, false).unwrap();
+        assert!(!headers.contains_key(AUTHORIZATION));
+        assert!(!headers.contains_key(COOKIE));
+        assert!(headers.contains_key("X-Request"));
+
+        let downgrade = Url::parse("http://example.com/").unwrap();
+        assert!(redirect_headers(&from, &downgrade, &mut headers, false).is_err());
+        assert!(redirect_headers(&from, &downgrade, &mut headers, true).is_ok());
+    }
+}
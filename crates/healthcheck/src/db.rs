@@ -1,4 +1,4 @@
-use rstat_core::HealthCheckResult;
+use rstat_core::{ErrorKind, HealthCheckResult, LocationLatency};
 use sqlx::PgPool;
 
 pub async fn create(
@@ -7,35 +7,86 @@ pub async fn create(
     service_id: uuid::Uuid,
 ) -> Result<uuid::Uuid, sqlx::Error> {
     let id = uuid::Uuid::new_v4();
+    let certificate = result.certificate.map(serde_json::to_value).transpose().map_err(encode_error)?;
+    let timings = result.timings.map(serde_json::to_value).transpose().map_err(encode_error)?;
     let created = sqlx::query!(
-        "INSERT INTO healthcheck_results (id, success, code, response_time, service_id, message, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+        "INSERT INTO healthcheck_results (id, success, degraded, code, response_time, service_id, message, error_kind, certificate, timings, location, maintenance, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
         id,
         result.success,
+        result.degraded,
         result.code as i64,
         result.response_time as i64,
         service_id,
         result.message,
+        result.error.map(|kind| kind.to_string()),
+        certificate,
+        timings,
+        result.location,
+        result.maintenance,
         result.created_at.naive_utc()
     ).fetch_one(pool).await?;
 
     Ok(created.id)
 }
 
+fn encode_error(err: serde_json::Error) -> sqlx::Error {
+    sqlx::Error::Encode(Box::new(err))
+}
+
 pub async fn get_by_service_id(
     pool: &PgPool,
     service_id: uuid::Uuid,
 ) -> Result<Vec<HealthCheckResult>, sqlx::Error> {
     let results = sqlx::query!(
-        "SELECT id, success, code, response_time, service_id, message, created_at FROM healthcheck_results WHERE service_id = $1",
+        "SELECT id, success, degraded, code, response_time, service_id, message, error_kind, certificate, timings, location, maintenance, created_at FROM healthcheck_results WHERE service_id = $1",
         service_id
     ).fetch_all(pool).await?;
 
     Ok(results.into_iter().map(|r| HealthCheckResult {
         id: r.id,
         success: r.success,
+        degraded: r.degraded,
         code: r.code.unwrap_or_default().parse::<u64>().unwrap_or_default(),
         response_time: r.response_time.unwrap_or_default().try_into().unwrap_or_default(),
         message: r.message.unwrap_or_default(),
+        error: r.error_kind.map(ErrorKind::from),
+        certificate: r.certificate.and_then(|c| serde_json::from_value(c).ok()),
+        timings: r.timings.and_then(|t| serde_json::from_value(t).ok()),
+        location: r.location,
+        maintenance: r.maintenance,
         created_at: r.created_at.and_utc(),
     }).collect())
-} 
\ No newline at end of file
+} 
+
+/// Response times of a service's checks since a point in time, grouped by the location they ran from
+pub async fn latency_by_location(
+    pool: &PgPool,
+    service_id: uuid::Uuid,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<LocationLatency>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT location,
+               COUNT(*) AS "total_checks!",
+               COUNT(*) FILTER (WHERE success) AS "successful_checks!",
+               COALESCE(AVG(response_time)::FLOAT8, 0) / 1000.0 AS "avg_response_time_ms!",
+               COALESCE(PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY response_time), 0) / 1000.0 AS "p95_response_time_ms!"
+        FROM healthcheck_results
+        WHERE service_id = $1 AND created_at >= $2
+        GROUP BY location
+        ORDER BY location NULLS FIRST
+        "#,
+        service_id,
+        since.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| LocationLatency {
+        location: r.location,
+        total_checks: r.total_checks,
+        successful_checks: r.successful_checks,
+        avg_response_time_ms: r.avg_response_time_ms,
+        p95_response_time_ms: r.p95_response_time_ms,
+    }).collect())
+}
@@ -0,0 +1,230 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::TokioAsyncResolver;
+use rstat_core::{DnsChecker, DnsRecordType, ErrorKind, HealthCheckResult, HealthChecker};
+use tokio::time::Instant;
+
+const DEFAULT_DNS_PORT: u16 = 53;
+
+/// DNS health checker implementation
+pub struct DnsHealthChecker {
+    config: DnsChecker,
+}
+
+impl DnsHealthChecker {
+    pub fn new(config: DnsChecker) -> Self {
+        Self { config }
+    }
+
+    fn build_resolver(&self) -> Result<TokioAsyncResolver, anyhow::Error> {
+        let mut opts = ResolverOpts::default();
+        opts.timeout = Duration::from_secs(self.config.timeout as u64);
+        opts.attempts = 1;
+        // Every check should reach the nameserver instead of a cached answer
+        opts.cache_size = 0;
+
+        match &self.config.nameserver {
+            Some(nameserver) => {
+                let addr = parse_nameserver(nameserver)?;
+                let group = NameServerConfigGroup::from_ips_clear(&[addr.ip()], addr.port(), true);
+                let config = ResolverConfig::from_parts(None, vec![], group);
+                Ok(TokioAsyncResolver::tokio(config, opts))
+            }
+            None => {
+                let (config, _) = hickory_resolver::system_conf::read_system_conf()?;
+                Ok(TokioAsyncResolver::tokio(config, opts))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl HealthChecker for DnsHealthChecker {
+    async fn check(&self) -> Result<HealthCheckResult, anyhow::Error> {
+        let resolver = self.build_resolver()?;
+        let record_type = to_record_type(self.config.record_type);
+
+        let max_retries = self.config.max_retries;
+        let mut attempts: u8 = 0;
+        let mut last_error: Option<String> = None;
+
+        while attempts <= max_retries {
+            let start_time = Instant::now();
+            let result = resolver.lookup(self.config.domain.as_str(), record_type).await;
+            let elapsed = start_time.elapsed();
+
+            match result {
+                Ok(lookup) => {
+                    let answers: Vec<String> = lookup
+                        .record_iter()
+                        .filter(|record| record.record_type() == record_type)
+                        .filter_map(|record| record.data().and_then(format_rdata))
+                        .collect();
+
+                    let (success, message) = evaluate_answers(&self.config, &answers);
+                    return Ok(HealthCheckResult {
+                        id: uuid::Uuid::new_v4(),
+                        success,
+                        degraded: false,
+                        response_time: elapsed.as_micros(),
+                        code: if success { 200 } else { 0 },
+                        message,
+                        error: (!success).then_some(ErrorKind::AssertionFailed),
+                        certificate: None,
+                        timings: None,
+                        location: None,
+                        maintenance: false,
+                        created_at: chrono::Utc::now(),
+                    });
+                }
+                Err(err) => {
+                    attempts += 1;
+                    last_error = Some(err.to_string());
+
+                    if attempts <= max_retries {
+                        tracing::info!("Retrying DNS lookup... attempt {}/{}", attempts, max_retries);
+                    } else {
+                        tracing::info!("Max retries reached for DNS lookup. Aborting...");
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(HealthCheckResult {
+            id: uuid::Uuid::new_v4(),
+            success: false,
+            degraded: false,
+            response_time: 0,
+            code: 0,
+            message: last_error.unwrap_or_else(|| "DNS lookup failed".to_string()),
+            error: Some(ErrorKind::DnsFailure),
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
+            created_at: chrono::Utc::now(),
+        })
+    }
+}
+
+/// Parse a nameserver given as `ip` or `ip:port`
+fn parse_nameserver(nameserver: &str) -> Result<SocketAddr, anyhow::Error> {
+    if let Ok(addr) = nameserver.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    let ip = nameserver
+        .parse::<IpAddr>()
+        .map_err(|_| anyhow::anyhow!("Invalid nameserver address: {}", nameserver))?;
+    Ok(SocketAddr::new(ip, DEFAULT_DNS_PORT))
+}
+
+fn to_record_type(record_type: DnsRecordType) -> RecordType {
+    match record_type {
+        DnsRecordType::A => RecordType::A,
+        DnsRecordType::AAAA => RecordType::AAAA,
+        DnsRecordType::CNAME => RecordType::CNAME,
+        DnsRecordType::MX => RecordType::MX,
+        DnsRecordType::TXT => RecordType::TXT,
+        DnsRecordType::SRV => RecordType::SRV,
+    }
+}
+
+/// Render a record in the same textual form users write in `expected`
+fn format_rdata(rdata: &RData) -> Option<String> {
+    let value = match rdata {
+        RData::A(a) => a.to_string(),
+        RData::AAAA(aaaa) => aaaa.to_string(),
+        RData::CNAME(cname) => cname.to_string(),
+        RData::MX(mx) => format!("{} {}", mx.preference(), mx.exchange()),
+        RData::TXT(txt) => txt
+            .txt_data()
+            .iter()
+            .map(|part| String::from_utf8_lossy(part).into_owned())
+            .collect::<Vec<_>>()
+            .join(""),
+        RData::SRV(srv) => format!("{} {} {} {}", srv.priority(), srv.weight(), srv.port(), srv.target()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Normalize a record value so that `example.com.` and `Example.com` compare equal.
+/// TXT values are compared verbatim.
+fn normalize(record_type: DnsRecordType, value: &str) -> String {
+    let value = value.trim();
+    match record_type {
+        DnsRecordType::TXT => value.to_string(),
+        _ => value.trim_end_matches('.').to_lowercase(),
+    }
+}
+
+fn evaluate_answers(config: &DnsChecker, answers: &[String]) -> (bool, String) {
+    let record_type = config.record_type;
+
+    if answers.is_empty() {
+        return (false, format!("No {} records found for {}", record_type, config.domain));
+    }
+
+    let mut actual: Vec<String> = answers.iter().map(|a| normalize(record_type, a)).collect();
+    actual.sort();
+    actual.dedup();
+
+    if !config.expected.is_empty() {
+        let mut expected: Vec<String> = config.expected.iter().map(|e| normalize(record_type, e)).collect();
+        expected.sort();
+        expected.dedup();
+
+        if actual != expected {
+            return (
+                false,
+                format!(
+                    "{} records for {} do not match: expected [{}], got [{}]",
+                    record_type,
+                    config.domain,
+                    expected.join(", "),
+                    actual.join(", ")
+                ),
+            );
+        }
+    }
+
+    (true, format!("Resolved {} {}: {}", record_type, config.domain, actual.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_answers_matches_expected_set() {
+        let config = DnsChecker {
+            domain: "example.com".to_string(),
+            record_type: DnsRecordType::CNAME,
+            expected: vec!["Target.Example.NET".to_string()],
+            ..DnsChecker::default()
+        };
+
+        let (success, _) = evaluate_answers(&config, &["target.example.net.".to_string()]);
+        assert!(success);
+
+        let (success, message) = evaluate_answers(&config, &["other.example.net.".to_string()]);
+        assert!(!success);
+        assert!(message.contains("do not match"));
+    }
+
+    #[test]
+    fn test_evaluate_answers_fails_on_empty_answer() {
+        let config = DnsChecker {
+            domain: "example.com".to_string(),
+            ..DnsChecker::default()
+        };
+
+        let (success, _) = evaluate_answers(&config, &[]);
+        assert!(!success);
+    }
+}
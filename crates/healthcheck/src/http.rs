@@ -1,21 +1,19 @@
-use rstat_core::{HealthChecker, HealthCheckResult, HttpChecker};
+use rstat_core::{ErrorKind, HealthChecker, HealthCheckResult, HttpChecker};
 use async_trait::async_trait;
 use http::{HeaderMap, Method, HeaderName};
 use tokio::time::Instant;
 use tracing::info;
 
+use crate::{assertions, client};
+
 /// HTTP health checker implementation
 pub struct HttpHealthChecker {
     config: HttpChecker,
-    client: reqwest::Client,
 }
 
 impl HttpHealthChecker {
     pub fn new(config: HttpChecker) -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            config,
-        }
+        Self { config }
     }
 
     fn validate_url(url: &str) -> Result<(), anyhow::Error> {
@@ -43,7 +41,7 @@ impl HealthChecker for HttpHealthChecker {
 
         let max_retries = self.config.max_retries;
         let mut attempts: u8 = 0;
-        let mut last_error: Option<String> = None;
+        let mut last_error: Option<client::RequestError> = None;
 
         while attempts <= max_retries {
             let method = Method::from_bytes(self.config.method.as_bytes())?;
@@ -51,32 +49,55 @@ impl HealthChecker for HttpHealthChecker {
             let body = self.config.body.clone().unwrap_or_default();
             let timeout = std::time::Duration::from_secs(self.config.timeout as u64);
 
-            let req = self.client
-                .request(method, &self.config.url)
-                .headers(headers)
-                .body(body)
-                .timeout(timeout)
-                .build()?;
-            
             let start_time = Instant::now();
-            let result = self.client.execute(req).await;
+            let request = client::send(method, &self.config.url, &headers, &body, self.config.allow_insecure_redirects);
+            let result = tokio::time::timeout(timeout, request)
+                .await
+                .unwrap_or_else(|_| {
+                    let message = format!("Request timed out after {}s", timeout.as_secs());
+                    Err(client::RequestError::new(ErrorKind::Timeout, message))
+                });
             let elapsed = start_time.elapsed();
 
             match result {
                 Ok(r) => {
-                    let success = r.status().is_success();
+                    let status = r.status;
+                    let text = r.body;
+
+                    let response = assertions::Response {
+                        status: status.as_u16(),
+                        headers: &r.headers,
+                        body: &text,
+                        elapsed,
+                    };
+                    let assertions = &self.config.assertions;
+                    let (error, message) = if !assertions::has_status_assertion(assertions) && !status.is_success() {
+                        (Some(ErrorKind::UnexpectedStatus), text.clone())
+                    } else {
+                        match assertions::evaluate(assertions, &response) {
+                            Ok(()) => (None, text.clone()),
+                            Err(failure) => (Some(assertions::failure_kind(assertions, response.status)), failure),
+                        }
+                    };
+
                     return Ok(HealthCheckResult {
                         id: uuid::Uuid::new_v4(),
-                        success,
+                        success: error.is_none(),
+                        degraded: false,
                         response_time: elapsed.as_micros(),
-                        code: r.status().as_u16() as u64,
-                        message: r.text().await.unwrap_or_default(),
+                        code: status.as_u16() as u64,
+                        message,
+                        error,
+                        certificate: None,
+                        timings: Some(r.timings),
+                        location: None,
+                        maintenance: false,
                         created_at: chrono::Utc::now(),
                     });
                 }
                 Err(err) => {
                     attempts += 1;
-                    last_error = Some(err.to_string());
+                    last_error = Some(err);
 
                     if attempts <= max_retries {
                         info!("Retrying... attempt {}/{}", attempts, max_retries);
@@ -91,9 +112,15 @@ impl HealthChecker for HttpHealthChecker {
         Ok(HealthCheckResult {
             id: uuid::Uuid::new_v4(),
             success: false,
+            degraded: false,
             response_time: 0,
             code: 0,
-            message: last_error.unwrap_or_default(),
+            message: last_error.as_ref().map(|e| e.message.clone()).unwrap_or_default(),
+            error: Some(last_error.map_or(ErrorKind::Other, |e| e.kind)),
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
             created_at: chrono::Utc::now(),
         })
     }
@@ -1,5 +1,9 @@
+pub mod assertions;
+pub mod client;
 pub mod http;
 pub mod tcp;
+pub mod dns;
+pub mod tls;
 pub mod db;
 
 use rstat_core::{HealthChecker, HealthCheckResult, HealthCheckRequest, Kind};
@@ -15,5 +19,13 @@ pub async fn perform_check(request: HealthCheckRequest) -> Result<HealthCheckRes
             let checker = tcp::TcpHealthChecker::new(tcp_checker.clone());
             checker.check().await
         }
+        Kind::DNS(dns_checker) => {
+            let checker = dns::DnsHealthChecker::new(dns_checker.clone());
+            checker.check().await
+        }
+        Kind::TLS(tls_checker) => {
+            let checker = tls::TlsHealthChecker::new(tls_checker.clone());
+            checker.check().await
+        }
     }
 } 
\ No newline at end of file
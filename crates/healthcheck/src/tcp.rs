@@ -1,8 +1,12 @@
-use rstat_core::{HealthChecker, HealthCheckResult, TcpChecker};
+use std::net::SocketAddr;
+
+use rstat_core::{ErrorKind, HealthChecker, HealthCheckResult, TcpChecker};
 use async_trait::async_trait;
 use tokio::time::Instant;
 use tokio::net::TcpStream;
 
+use crate::client::{self, RequestError};
+
 /// TCP health checker implementation
 pub struct TcpHealthChecker {
     config: TcpChecker,
@@ -12,6 +16,19 @@ impl TcpHealthChecker {
     pub fn new(config: TcpChecker) -> Self {
         Self { config }
     }
+
+    /// Connect to the first address of the host that accepts, as HTTP checks do
+    async fn connect(&self) -> Result<TcpStream, RequestError> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((self.config.host.as_str(), self.config.port))
+            .await
+            .map_err(|e| RequestError::new(ErrorKind::DnsFailure, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(RequestError::new(ErrorKind::DnsFailure, format!("{} did not resolve to any address", self.config.host)));
+        }
+
+        client::connect(&addrs).await
+    }
 }
 
 #[async_trait]
@@ -19,11 +36,15 @@ impl HealthChecker for TcpHealthChecker {
     async fn check(&self) -> Result<HealthCheckResult, anyhow::Error> {
         let max_retries = self.config.max_retries;
         let mut attempts: u8 = 0;
-        let mut last_error: Option<String> = None;
+        let timeout = std::time::Duration::from_secs(self.config.timeout as u64);
+        let mut last_error: Option<RequestError> = None;
 
         while attempts <= max_retries {
             let start_time = Instant::now();
-            let result = TcpStream::connect((self.config.host.as_str(), self.config.port)).await;
+            let result = tokio::time::timeout(timeout, self.connect()).await.unwrap_or_else(|_| {
+                let message = format!("TCP connection timed out after {}s", timeout.as_secs());
+                Err(RequestError::new(ErrorKind::Timeout, message))
+            });
             let elapsed = start_time.elapsed();
 
             match result {
@@ -31,15 +52,21 @@ impl HealthChecker for TcpHealthChecker {
                     return Ok(HealthCheckResult {
                         id: uuid::Uuid::new_v4(),
                         success: true,
+                        degraded: false,
                         response_time: elapsed.as_micros(),
                         code: 200,
                         message: "TCP connection successful".to_string(),
+                        error: None,
+                        certificate: None,
+                        timings: None,
+                        location: None,
+                        maintenance: false,
                         created_at: chrono::Utc::now(),
                     });
                 }
                 Err(err) => {
                     attempts += 1;
-                    last_error = Some(err.to_string());
+                    last_error = Some(err);
 
                     if attempts <= max_retries {
                         tracing::info!("Retrying TCP connection... attempt {}/{}", attempts, max_retries);
@@ -54,9 +81,15 @@ impl HealthChecker for TcpHealthChecker {
         Ok(HealthCheckResult {
             id: uuid::Uuid::new_v4(),
             success: false,
+            degraded: false,
             response_time: 0,
             code: 0,
-            message: last_error.unwrap_or_else(|| "TCP connection failed".to_string()),
+            message: last_error.as_ref().map_or_else(|| "TCP connection failed".to_string(), |e| e.message.clone()),
+            error: Some(last_error.map_or(ErrorKind::Other, |e| e.kind)),
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
             created_at: chrono::Utc::now(),
         })
     }
@@ -0,0 +1,316 @@
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::{X509NameRef, X509Ref, X509VerifyResult};
+use rstat_core::{CertificateInfo, ErrorKind, HealthCheckResult, HealthChecker, TlsChecker};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+use tokio_openssl::SslStream;
+
+/// TLS health checker implementation
+pub struct TlsHealthChecker {
+    config: TlsChecker,
+}
+
+impl TlsHealthChecker {
+    pub fn new(config: TlsChecker) -> Self {
+        Self { config }
+    }
+
+    /// Connect and complete a handshake, returning the peer certificate details.
+    async fn handshake(&self) -> Result<CertificateInfo, anyhow::Error> {
+        let tcp = TcpStream::connect((self.config.host.as_str(), self.config.port)).await?;
+
+        // Verification runs regardless; we only keep OpenSSL from aborting the
+        // handshake so the certificate can still be inspected when it is invalid.
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        builder.set_verify(SslVerifyMode::NONE);
+        let ssl = builder.build().configure()?.into_ssl(&self.config.host)?;
+
+        let mut stream = SslStream::new(ssl, tcp)?;
+        Pin::new(&mut stream).connect().await?;
+
+        let ssl = stream.ssl();
+        let cert = ssl
+            .peer_certificate()
+            .ok_or_else(|| anyhow::anyhow!("Peer did not present a certificate"))?;
+        let verify_result = ssl.verify_result();
+
+        certificate_info(&cert, verify_result)
+    }
+
+    fn evaluate(&self, certificate: &CertificateInfo) -> (bool, bool, String) {
+        if self.config.verify_chain && !certificate.chain_valid {
+            let reason = certificate.verification_error.clone().unwrap_or_default();
+            return (false, false, format!("Certificate verification failed: {}", reason));
+        }
+
+        if certificate.not_after <= Utc::now() {
+            return (
+                false,
+                false,
+                format!("Certificate expired on {}", certificate.not_after.format("%Y-%m-%d")),
+            );
+        }
+
+        if certificate.days_until_expiry < self.config.expiry_warning_days as i64 {
+            let message = format!(
+                "Certificate expires in {} days on {}",
+                certificate.days_until_expiry,
+                certificate.not_after.format("%Y-%m-%d")
+            );
+            if self.config.fail_on_expiry_warning {
+                return (false, false, message);
+            }
+            return (true, true, message);
+        }
+
+        (
+            true,
+            false,
+            format!("Certificate valid for {} days", certificate.days_until_expiry),
+        )
+    }
+}
+
+#[async_trait]
+impl HealthChecker for TlsHealthChecker {
+    async fn check(&self) -> Result<HealthCheckResult, anyhow::Error> {
+        let max_retries = self.config.max_retries;
+        let timeout = Duration::from_secs(self.config.timeout as u64);
+        let mut attempts: u8 = 0;
+        let mut last_error: Option<(ErrorKind, String)> = None;
+
+        while attempts <= max_retries {
+            let start_time = Instant::now();
+            let result = tokio::time::timeout(timeout, self.handshake()).await;
+            let elapsed = start_time.elapsed();
+
+            let result = match result {
+                Ok(result) => result.map_err(|e| (ErrorKind::Tls, e.to_string())),
+                Err(_) => Err((ErrorKind::Timeout, format!("TLS handshake timed out after {}s", timeout.as_secs()))),
+            };
+
+            match result {
+                Ok(certificate) => {
+                    let (success, degraded, message) = self.evaluate(&certificate);
+                    return Ok(HealthCheckResult {
+                        id: uuid::Uuid::new_v4(),
+                        success,
+                        degraded,
+                        response_time: elapsed.as_micros(),
+                        code: if success { 200 } else { 0 },
+                        message,
+                        error: (!success).then_some(ErrorKind::Tls),
+                        certificate: Some(certificate),
+                        timings: None,
+                        location: None,
+                        maintenance: false,
+                        created_at: Utc::now(),
+                    });
+                }
+                Err(err) => {
+                    attempts += 1;
+                    last_error = Some(err);
+
+                    if attempts <= max_retries {
+                        tracing::info!("Retrying TLS handshake... attempt {}/{}", attempts, max_retries);
+                    } else {
+                        tracing::info!("Max retries reached for TLS handshake. Aborting...");
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(HealthCheckResult {
+            id: uuid::Uuid::new_v4(),
+            success: false,
+            degraded: false,
+            response_time: 0,
+            code: 0,
+            error: Some(last_error.as_ref().map_or(ErrorKind::Tls, |(kind, _)| *kind)),
+            message: last_error.map_or_else(|| "TLS handshake failed".to_string(), |(_, message)| message),
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+fn certificate_info(cert: &X509Ref, verify_result: X509VerifyResult) -> Result<CertificateInfo, anyhow::Error> {
+    let not_before = asn1_to_datetime(cert.not_before())?;
+    let not_after = asn1_to_datetime(cert.not_after())?;
+
+    let sans = cert
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| {
+                    if let Some(dns) = name.dnsname() {
+                        return Some(dns.to_string());
+                    }
+                    name.ipaddress().and_then(format_ip)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let chain_valid = verify_result == X509VerifyResult::OK;
+
+    Ok(CertificateInfo {
+        subject: format_name(cert.subject_name()),
+        issuer: format_name(cert.issuer_name()),
+        sans,
+        not_before,
+        not_after,
+        days_until_expiry: (not_after - Utc::now()).num_days(),
+        chain_valid,
+        verification_error: (!chain_valid).then(|| verify_result.error_string().to_string()),
+    })
+}
+
+fn asn1_to_datetime(time: &Asn1TimeRef) -> Result<DateTime<Utc>, anyhow::Error> {
+    let epoch = Asn1Time::from_unix(0)?;
+    let diff = epoch.diff(time)?;
+    let seconds = diff.days as i64 * 86_400 + diff.secs as i64;
+    DateTime::from_timestamp(seconds, 0).ok_or_else(|| anyhow::anyhow!("Invalid certificate timestamp"))
+}
+
+/// Format a distinguished name as `CN=example.com, O=Example`
+fn format_name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            format!("{}={}", key, String::from_utf8_lossy(entry.data().as_slice()))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_ip(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => {
+            let octets: [u8; 4] = bytes.try_into().ok()?;
+            Some(std::net::Ipv4Addr::from(octets).to_string())
+        }
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::BigNum;
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::rsa::Rsa;
+    use openssl::ssl::{SslAcceptor, SslMethod};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509NameBuilder, X509};
+    use tokio::net::TcpListener;
+
+    fn self_signed(days: u32) -> (X509, PKey<Private>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&BigNum::from_u32(1).unwrap().to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(days).unwrap()).unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        (builder.build(), key)
+    }
+
+    /// Start a TLS server presenting a self-signed certificate valid for `days`
+    async fn start_server(days: u32) -> u16 {
+        let (cert, key) = self_signed(days);
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                let ssl = openssl::ssl::Ssl::new(acceptor.context()).unwrap();
+                let mut stream = SslStream::new(ssl, tcp).unwrap();
+                tokio::spawn(async move {
+                    let _ = Pin::new(&mut stream).accept().await;
+                });
+            }
+        });
+
+        port
+    }
+
+    #[tokio::test]
+    async fn test_self_signed_certificate_within_warning_window_is_degraded() {
+        let port = start_server(10).await;
+        let checker = TlsHealthChecker::new(TlsChecker {
+            host: "localhost".to_string(),
+            port,
+            expiry_warning_days: 30,
+            verify_chain: false,
+            max_retries: 0,
+            ..TlsChecker::default()
+        });
+
+        let result = checker.check().await.unwrap();
+        assert!(result.success);
+        assert!(result.degraded);
+
+        let certificate = result.certificate.unwrap();
+        assert!(!certificate.chain_valid);
+        assert_eq!(certificate.issuer, "CN=localhost");
+        assert!(certificate.sans.contains(&"localhost".to_string()));
+        assert!(certificate.sans.contains(&"127.0.0.1".to_string()));
+        assert!((9..=10).contains(&certificate.days_until_expiry));
+    }
+
+    #[tokio::test]
+    async fn test_self_signed_certificate_fails_chain_verification() {
+        let port = start_server(90).await;
+        let checker = TlsHealthChecker::new(TlsChecker {
+            host: "localhost".to_string(),
+            port,
+            max_retries: 0,
+            ..TlsChecker::default()
+        });
+
+        let result = checker.check().await.unwrap();
+        assert!(!result.success);
+        assert!(result.message.starts_with("Certificate verification failed"));
+        assert!(result.certificate.is_some());
+    }
+}
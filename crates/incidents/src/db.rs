@@ -0,0 +1,287 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::{Incident, IncidentEvent, IncidentEventKind, IncidentStatus};
+
+/// IncidentRow mirrors a row of the incidents table
+struct IncidentRow {
+    id: Uuid,
+    service_id: Uuid,
+    status: String,
+    started_at: NaiveDateTime,
+    resolved_at: Option<NaiveDateTime>,
+    duration_seconds: Option<i64>,
+    acknowledged_at: Option<NaiveDateTime>,
+    acknowledged_by: Option<String>,
+}
+
+impl From<IncidentRow> for Incident {
+    fn from(row: IncidentRow) -> Self {
+        Incident {
+            id: row.id,
+            service_id: row.service_id,
+            status: row.status.into(),
+            started_at: row.started_at.and_utc(),
+            resolved_at: row.resolved_at.map(|r| r.and_utc()),
+            duration_seconds: row.duration_seconds,
+            acknowledged_at: row.acknowledged_at.map(|a| a.and_utc()),
+            acknowledged_by: row.acknowledged_by,
+        }
+    }
+}
+
+/// Create a new open incident for a service
+pub async fn create(
+    conn: &mut PgConnection,
+    service_id: Uuid,
+    started_at: DateTime<Utc>,
+) -> Result<Incident, sqlx::Error> {
+    let row = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        INSERT INTO incidents (service_id, status, started_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        "#,
+        service_id,
+        IncidentStatus::Open.to_string(),
+        started_at.naive_utc()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Resolve an incident, computing its duration
+pub async fn resolve(
+    conn: &mut PgConnection,
+    incident_id: Uuid,
+    resolved_at: DateTime<Utc>,
+) -> Result<Incident, sqlx::Error> {
+    let row = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        UPDATE incidents
+        SET status = $1,
+            resolved_at = $2,
+            duration_seconds = GREATEST(EXTRACT(EPOCH FROM ($2 - started_at))::BIGINT, 0)
+        WHERE id = $3
+        RETURNING id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        "#,
+        IncidentStatus::Resolved.to_string(),
+        resolved_at.naive_utc(),
+        incident_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Mark an unresolved incident as acknowledged.
+///
+/// Returns None when the incident is resolved or was already acknowledged.
+pub async fn acknowledge(
+    conn: &mut PgConnection,
+    incident_id: Uuid,
+    acknowledged_by: Option<String>,
+    acknowledged_at: DateTime<Utc>,
+) -> Result<Option<Incident>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        UPDATE incidents
+        SET status = $1, acknowledged_at = $2, acknowledged_by = $3
+        WHERE id = $4 AND resolved_at IS NULL AND acknowledged_at IS NULL
+        RETURNING id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        "#,
+        IncidentStatus::Acknowledged.to_string(),
+        acknowledged_at.naive_utc(),
+        acknowledged_by,
+        incident_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(row.map(Incident::from))
+}
+
+/// Get an incident by id
+pub async fn get(pool: &PgPool, incident_id: Uuid) -> Result<Option<Incident>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        SELECT id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        FROM incidents
+        WHERE id = $1
+        "#,
+        incident_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Incident::from))
+}
+
+/// Get the open incident of a service, if there is one
+pub async fn get_open_for_service(pool: &PgPool, service_id: Uuid) -> Result<Option<Incident>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        SELECT id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        FROM incidents
+        WHERE service_id = $1 AND resolved_at IS NULL
+        "#,
+        service_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Incident::from))
+}
+
+/// List incidents across all services, most recent first
+pub async fn list(
+    pool: &PgPool,
+    status: Option<IncidentStatus>,
+    limit: i64,
+) -> Result<Vec<Incident>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        SELECT id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        FROM incidents
+        WHERE $1::VARCHAR IS NULL OR status = $1
+        ORDER BY started_at DESC
+        LIMIT $2
+        "#,
+        status.map(|s| s.to_string()),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Incident::from).collect())
+}
+
+/// List incidents of a single service, most recent first
+pub async fn list_for_service(
+    pool: &PgPool,
+    service_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Incident>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        IncidentRow,
+        r#"
+        SELECT id, service_id, status, started_at, resolved_at, duration_seconds,
+               acknowledged_at, acknowledged_by
+        FROM incidents
+        WHERE service_id = $1
+        ORDER BY started_at DESC
+        LIMIT $2
+        "#,
+        service_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Incident::from).collect())
+}
+
+/// Append an entry to an incident's timeline
+pub async fn add_event(
+    conn: &mut PgConnection,
+    incident_id: Uuid,
+    kind: IncidentEventKind,
+    healthcheck_result_id: Option<Uuid>,
+    message: Option<String>,
+    created_at: DateTime<Utc>,
+) -> Result<Uuid, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO incident_events (incident_id, kind, healthcheck_result_id, message, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        incident_id,
+        kind.to_string(),
+        healthcheck_result_id,
+        message,
+        created_at.naive_utc()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(row.id)
+}
+
+/// Get the timeline of an incident in chronological order
+pub async fn get_timeline(pool: &PgPool, incident_id: Uuid) -> Result<Vec<IncidentEvent>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, incident_id, kind, healthcheck_result_id, message, created_at
+        FROM incident_events
+        WHERE incident_id = $1
+        ORDER BY created_at, id
+        "#,
+        incident_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| IncidentEvent {
+            id: row.id,
+            incident_id: row.incident_id,
+            kind: row.kind.into(),
+            healthcheck_result_id: row.healthcheck_result_id,
+            message: row.message,
+            created_at: row.created_at.and_utc(),
+        })
+        .collect())
+}
+
+/// Get the latest consecutive failed results of a service, oldest first.
+///
+/// Only the results that advanced the state count: the ones from `location`, the scheduler's
+/// own when None, taken outside maintenance windows.
+pub async fn get_failure_streak(
+    pool: &PgPool,
+    service_id: Uuid,
+    location: Option<&str>,
+    limit: i64,
+) -> Result<Vec<(Uuid, DateTime<Utc>, Option<String>)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, success, message, created_at
+        FROM healthcheck_results
+        WHERE service_id = $1 AND location IS NOT DISTINCT FROM $2 AND NOT maintenance
+        ORDER BY created_at DESC
+        LIMIT $3
+        "#,
+        service_id,
+        location,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut streak: Vec<(Uuid, DateTime<Utc>, Option<String>)> = rows
+        .into_iter()
+        .take_while(|row| !row.success)
+        .map(|row| (row.id, row.created_at.and_utc(), row.message))
+        .collect();
+    streak.reverse();
+
+    Ok(streak)
+}
@@ -0,0 +1,272 @@
+pub mod models;
+pub mod db;
+
+pub use models::*;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use rstat_core::{CheckOutcome, ServiceState, ServiceStatus, StatusTransition};
+
+/// Key identifying the outage of a service in external paging tools
+pub fn dedup_key(service_id: Uuid) -> String {
+    format!("rstat-{}", service_id)
+}
+
+/// The service a dedup key was derived from
+pub fn service_from_dedup_key(key: &str) -> Option<Uuid> {
+    key.strip_prefix("rstat-").and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// IncidentManager opens and resolves incidents as services change state
+#[derive(Clone)]
+pub struct IncidentManager {
+    pub pool: PgPool,
+}
+
+impl IncidentManager {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Update the incidents of a service after a healthcheck.
+    ///
+    /// Opens an incident when the service goes DOWN, attaching the failed results
+    /// that caused it, attaches further failures while it stays DOWN, and
+    /// resolves the incident when the service recovers. `location` is where the
+    /// check ran, None for the scheduler's own checks.
+    pub async fn record(
+        &self,
+        service_id: Uuid,
+        location: Option<&str>,
+        state: &ServiceState,
+        transition: Option<StatusTransition>,
+        outcome: CheckOutcome,
+        result_id: Option<Uuid>,
+    ) -> Result<Option<IncidentChange>, anyhow::Error> {
+        match IncidentAction::for_check(state, transition, outcome) {
+            Some(IncidentAction::Open) => {
+                let incident = self.open(service_id, location, state.consecutive_failures, result_id).await?;
+                Ok(Some(IncidentChange::Opened(incident)))
+            }
+            Some(IncidentAction::Attach) => {
+                if let Some(incident) = db::get_open_for_service(&self.pool, service_id).await? {
+                    let mut conn = self.pool.acquire().await?;
+                    db::add_event(&mut conn, incident.id, IncidentEventKind::Failure, result_id, None, Utc::now()).await?;
+                }
+                Ok(None)
+            }
+            Some(IncidentAction::Resolve) => {
+                let incident = self.resolve(service_id, result_id).await?;
+                Ok(incident.map(IncidentChange::Resolved))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn open(
+        &self,
+        service_id: Uuid,
+        location: Option<&str>,
+        failure_count: u32,
+        result_id: Option<Uuid>,
+    ) -> Result<Incident, anyhow::Error> {
+        if let Some(incident) = db::get_open_for_service(&self.pool, service_id).await? {
+            return Ok(incident);
+        }
+
+        let streak = db::get_failure_streak(&self.pool, service_id, location, failure_count.max(1) as i64).await?;
+        let events = opening_timeline(streak, result_id, Utc::now());
+        // The outage started with the first failure of the streak
+        let started_at = events[0].created_at;
+
+        // The incident is only ever stored together with its timeline
+        let mut tx = self.pool.begin().await?;
+        let incident = db::create(&mut tx, service_id, started_at).await?;
+        for event in events {
+            db::add_event(&mut tx, incident.id, event.kind, event.result_id, event.message, event.created_at).await?;
+        }
+        tx.commit().await?;
+
+        info!("Opened incident {} for service {}", incident.id, service_id);
+        Ok(incident)
+    }
+
+    async fn resolve(&self, service_id: Uuid, result_id: Option<Uuid>) -> Result<Option<Incident>, anyhow::Error> {
+        let Some(incident) = db::get_open_for_service(&self.pool, service_id).await? else {
+            return Ok(None);
+        };
+
+        let resolved_at = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        db::add_event(&mut tx, incident.id, IncidentEventKind::Resolved, result_id, Some("Service recovered".to_string()), resolved_at).await?;
+        let incident = db::resolve(&mut tx, incident.id, resolved_at).await?;
+        tx.commit().await?;
+
+        info!(
+            "Resolved incident {} for service {} after {}s",
+            incident.id,
+            service_id,
+            incident.duration_seconds.unwrap_or_default()
+        );
+        Ok(Some(incident))
+    }
+
+    /// Acknowledge an incident, recording who did it on its timeline.
+    ///
+    /// Returns None when the incident is already acknowledged or resolved.
+    pub async fn acknowledge(
+        &self,
+        incident_id: Uuid,
+        acknowledged_by: Option<String>,
+    ) -> Result<Option<Incident>, anyhow::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let Some(incident) = db::acknowledge(&mut tx, incident_id, acknowledged_by.clone(), now).await? else {
+            return Ok(None);
+        };
+
+        let message = match &acknowledged_by {
+            Some(by) => format!("Acknowledged by {}", by),
+            None => "Acknowledged".to_string(),
+        };
+        db::add_event(&mut tx, incident.id, IncidentEventKind::Acknowledged, None, Some(message), now).await?;
+        tx.commit().await?;
+
+        info!("Incident {} for service {} acknowledged", incident.id, incident.service_id);
+        Ok(Some(incident))
+    }
+
+    /// Acknowledge the open incident of a service, if there is one.
+    pub async fn acknowledge_service(
+        &self,
+        service_id: Uuid,
+        acknowledged_by: Option<String>,
+    ) -> Result<Option<Incident>, anyhow::Error> {
+        match db::get_open_for_service(&self.pool, service_id).await? {
+            Some(incident) => self.acknowledge(incident.id, acknowledged_by).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Get an incident together with its timeline
+    pub async fn get_details(&self, incident_id: Uuid) -> Result<Option<IncidentDetails>, anyhow::Error> {
+        let Some(incident) = db::get(&self.pool, incident_id).await? else {
+            return Ok(None);
+        };
+        let timeline = db::get_timeline(&self.pool, incident_id).await?;
+
+        Ok(Some(IncidentDetails { incident, timeline }))
+    }
+}
+
+/// IncidentAction is what a healthcheck does to the incidents of its service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IncidentAction {
+    Open,
+    Attach,
+    Resolve,
+}
+
+impl IncidentAction {
+    /// The action for a check that left the service in `state` after `transition`
+    fn for_check(state: &ServiceState, transition: Option<StatusTransition>, outcome: CheckOutcome) -> Option<Self> {
+        match transition {
+            Some(t) if t.to == ServiceStatus::Down => Some(IncidentAction::Open),
+            Some(t) if t.from == ServiceStatus::Down => Some(IncidentAction::Resolve),
+            None if state.status == ServiceStatus::Down && outcome == CheckOutcome::Failed => Some(IncidentAction::Attach),
+            _ => None,
+        }
+    }
+}
+
+/// PendingEvent is a timeline entry yet to be stored
+#[derive(Debug, Clone, PartialEq)]
+struct PendingEvent {
+    kind: IncidentEventKind,
+    result_id: Option<Uuid>,
+    message: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// The timeline of a new incident: opened with the first failure of the streak, followed by
+/// the failures of the streak and the result that brought the service down.
+fn opening_timeline(
+    streak: Vec<(Uuid, DateTime<Utc>, Option<String>)>,
+    result_id: Option<Uuid>,
+    now: DateTime<Utc>,
+) -> Vec<PendingEvent> {
+    let started_at = streak.first().map_or(now, |(_, at, _)| *at);
+    let mut events = vec![PendingEvent {
+        kind: IncidentEventKind::Opened,
+        result_id: None,
+        message: Some("Service is down".to_string()),
+        created_at: started_at,
+    }];
+
+    let mut attached = false;
+    for (id, created_at, message) in streak {
+        attached |= Some(id) == result_id;
+        events.push(PendingEvent { kind: IncidentEventKind::Failure, result_id: Some(id), message, created_at });
+    }
+    if !attached {
+        events.push(PendingEvent { kind: IncidentEventKind::Failure, result_id, message: None, created_at: now });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup_key_round_trip() {
+        let service_id = Uuid::new_v4();
+        assert_eq!(service_from_dedup_key(&dedup_key(service_id)), Some(service_id));
+        assert_eq!(service_from_dedup_key("other-key"), None);
+    }
+
+    #[test]
+    fn test_incident_action_for_check() {
+        let down = ServiceState { status: ServiceStatus::Down, consecutive_failures: 3, ..ServiceState::default() };
+        let up = ServiceState { status: ServiceStatus::Up, ..ServiceState::default() };
+        let went_down = StatusTransition { from: ServiceStatus::Up, to: ServiceStatus::Down };
+        let recovered = StatusTransition { from: ServiceStatus::Down, to: ServiceStatus::Up };
+
+        assert_eq!(IncidentAction::for_check(&down, Some(went_down), CheckOutcome::Failed), Some(IncidentAction::Open));
+        assert_eq!(IncidentAction::for_check(&down, None, CheckOutcome::Failed), Some(IncidentAction::Attach));
+        assert_eq!(IncidentAction::for_check(&up, Some(recovered), CheckOutcome::Healthy), Some(IncidentAction::Resolve));
+        // Successes while still down and failures while up leave incidents alone
+        assert_eq!(IncidentAction::for_check(&down, None, CheckOutcome::Healthy), None);
+        assert_eq!(IncidentAction::for_check(&up, None, CheckOutcome::Failed), None);
+    }
+
+    #[test]
+    fn test_opening_timeline() {
+        let now = Utc::now();
+        let first = (Uuid::new_v4(), now - chrono::Duration::seconds(60), Some("timeout".to_string()));
+        let last = (Uuid::new_v4(), now - chrono::Duration::seconds(30), None);
+
+        // The result that brought the service down is the last of the streak
+        let events = opening_timeline(vec![first.clone(), last.clone()], Some(last.0), now);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, IncidentEventKind::Opened);
+        assert_eq!(events[0].created_at, first.1);
+        assert_eq!(events[1].result_id, Some(first.0));
+        assert_eq!(events[1].message, first.2);
+        assert_eq!(events[2].result_id, Some(last.0));
+
+        // A result that is not stored yet is still attached
+        let result_id = Uuid::new_v4();
+        let events = opening_timeline(vec![first.clone()], Some(result_id), now);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[2], PendingEvent { kind: IncidentEventKind::Failure, result_id: Some(result_id), message: None, created_at: now });
+
+        // Without a streak the outage starts now
+        let events = opening_timeline(vec![], None, now);
+        assert_eq!(events[0].created_at, now);
+        assert_eq!(events.len(), 2);
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Incident represents an outage of a service, from the moment it went down until it recovered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: Uuid,
+    pub service_id: Uuid,
+    pub status: IncidentStatus,
+    pub started_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Length of the outage, set once the incident is resolved.
+    pub duration_seconds: Option<i64>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// Who acknowledged the incident, as reported by the paging tool.
+    pub acknowledged_by: Option<String>,
+}
+
+/// IncidentDetails is an incident together with its timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentDetails {
+    #[serde(flatten)]
+    pub incident: Incident,
+    pub timeline: Vec<IncidentEvent>,
+}
+
+/// IncidentEvent is a single entry on an incident's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentEvent {
+    pub id: Uuid,
+    pub incident_id: Uuid,
+    pub kind: IncidentEventKind,
+    /// The healthcheck result that caused this entry, if any.
+    pub healthcheck_result_id: Option<Uuid>,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IncidentStatus {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IncidentEventKind {
+    /// The service went down.
+    Opened,
+    /// A failing check attached to the incident.
+    Failure,
+    /// Someone acknowledged the incident.
+    Acknowledged,
+    /// The service recovered.
+    Resolved,
+}
+
+/// IncidentChange describes what a healthcheck did to a service's incidents
+#[derive(Debug, Clone)]
+pub enum IncidentChange {
+    Opened(Incident),
+    Resolved(Incident),
+}
+
+impl Display for IncidentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IncidentStatus::Open => write!(f, "OPEN"),
+            IncidentStatus::Acknowledged => write!(f, "ACKNOWLEDGED"),
+            IncidentStatus::Resolved => write!(f, "RESOLVED"),
+        }
+    }
+}
+
+impl From<String> for IncidentStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "ACKNOWLEDGED" => IncidentStatus::Acknowledged,
+            "RESOLVED" => IncidentStatus::Resolved,
+            _ => IncidentStatus::Open,
+        }
+    }
+}
+
+impl Display for IncidentEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IncidentEventKind::Opened => write!(f, "OPENED"),
+            IncidentEventKind::Failure => write!(f, "FAILURE"),
+            IncidentEventKind::Acknowledged => write!(f, "ACKNOWLEDGED"),
+            IncidentEventKind::Resolved => write!(f, "RESOLVED"),
+        }
+    }
+}
+
+impl From<String> for IncidentEventKind {
+    fn from(kind: String) -> Self {
+        match kind.as_str() {
+            "OPENED" => IncidentEventKind::Opened,
+            "ACKNOWLEDGED" => IncidentEventKind::Acknowledged,
+            "RESOLVED" => IncidentEventKind::Resolved,
+            _ => IncidentEventKind::Failure,
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::models::{MaintenanceSettings, MaintenanceWindow};
+
+/// MaintenanceRow mirrors a row of the maintenance_windows table
+struct MaintenanceRow {
+    id: Uuid,
+    name: String,
+    service_id: Option<Uuid>,
+    tag: Option<String>,
+    starts_at: NaiveDateTime,
+    ends_at: Option<NaiveDateTime>,
+    recurrence: Option<serde_json::Value>,
+    created_at: NaiveDateTime,
+}
+
+impl From<MaintenanceRow> for MaintenanceWindow {
+    fn from(row: MaintenanceRow) -> Self {
+        MaintenanceWindow {
+            id: row.id,
+            settings: MaintenanceSettings {
+                name: row.name,
+                service_id: row.service_id,
+                tag: row.tag,
+                starts_at: row.starts_at.and_utc(),
+                ends_at: row.ends_at.map(|ends_at| ends_at.and_utc()),
+                recurrence: row.recurrence.and_then(|recurrence| {
+                    serde_json::from_value(recurrence)
+                        .map_err(|err| warn!("Maintenance window {} has an invalid recurrence: {}", row.id, err))
+                        .ok()
+                }),
+            },
+            created_at: row.created_at.and_utc(),
+        }
+    }
+}
+
+fn encode_recurrence(settings: &MaintenanceSettings) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    settings
+        .recurrence
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|err| sqlx::Error::Encode(Box::new(err)))
+}
+
+pub async fn create(pool: &PgPool, settings: &MaintenanceSettings) -> Result<MaintenanceWindow, sqlx::Error> {
+    let recurrence = encode_recurrence(settings)?;
+    let row = sqlx::query_as!(
+        MaintenanceRow,
+        r#"
+        INSERT INTO maintenance_windows (name, service_id, tag, starts_at, ends_at, recurrence)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, name, service_id, tag, starts_at, ends_at, recurrence, created_at
+        "#,
+        settings.name,
+        settings.service_id,
+        settings.tag,
+        settings.starts_at.naive_utc(),
+        settings.ends_at.map(|ends_at| ends_at.naive_utc()),
+        recurrence
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.into())
+}
+
+/// Replace the settings of a maintenance window, returning it if it exists
+pub async fn update(
+    pool: &PgPool,
+    id: Uuid,
+    settings: &MaintenanceSettings,
+) -> Result<Option<MaintenanceWindow>, sqlx::Error> {
+    let recurrence = encode_recurrence(settings)?;
+    let row = sqlx::query_as!(
+        MaintenanceRow,
+        r#"
+        UPDATE maintenance_windows
+        SET name = $2, service_id = $3, tag = $4, starts_at = $5, ends_at = $6, recurrence = $7
+        WHERE id = $1
+        RETURNING id, name, service_id, tag, starts_at, ends_at, recurrence, created_at
+        "#,
+        id,
+        settings.name,
+        settings.service_id,
+        settings.tag,
+        settings.starts_at.naive_utc(),
+        settings.ends_at.map(|ends_at| ends_at.naive_utc()),
+        recurrence
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(MaintenanceWindow::from))
+}
+
+pub async fn get(pool: &PgPool, id: Uuid) -> Result<Option<MaintenanceWindow>, sqlx::Error> {
+    let row = sqlx::query_as!(
+        MaintenanceRow,
+        r#"
+        SELECT id, name, service_id, tag, starts_at, ends_at, recurrence, created_at
+        FROM maintenance_windows
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(MaintenanceWindow::from))
+}
+
+pub async fn list(pool: &PgPool) -> Result<Vec<MaintenanceWindow>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        MaintenanceRow,
+        r#"
+        SELECT id, name, service_id, tag, starts_at, ends_at, recurrence, created_at
+        FROM maintenance_windows
+        ORDER BY starts_at DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(MaintenanceWindow::from).collect())
+}
+
+/// Windows of a service, its tags or all services that have started and not ended by `at`.
+///
+/// Recurring windows still have to be checked for being open at `at`.
+pub async fn current_for_service(
+    pool: &PgPool,
+    service_id: Uuid,
+    tags: &[String],
+    at: DateTime<Utc>,
+) -> Result<Vec<MaintenanceWindow>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        MaintenanceRow,
+        r#"
+        SELECT id, name, service_id, tag, starts_at, ends_at, recurrence, created_at
+        FROM maintenance_windows
+        WHERE (service_id = $1 OR tag = ANY($2) OR (service_id IS NULL AND tag IS NULL))
+          AND starts_at <= $3 AND (ends_at IS NULL OR ends_at > $3)
+        "#,
+        service_id,
+        tags,
+        at.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(MaintenanceWindow::from).collect())
+}
+
+/// Delete a maintenance window. Returns whether the window existed.
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query!("DELETE FROM maintenance_windows WHERE id = $1", id)
+        .execute(pool)
+        .await?;
+
+    Ok(deleted.rows_affected() > 0)
+}
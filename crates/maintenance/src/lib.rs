@@ -0,0 +1,34 @@
+pub mod db;
+pub mod models;
+
+pub use models::*;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use rstat_core::Service;
+
+/// Validate and store a maintenance window
+pub async fn create(pool: &PgPool, settings: &MaintenanceSettings) -> Result<MaintenanceWindow, anyhow::Error> {
+    settings.validate()?;
+    Ok(db::create(pool, settings).await?)
+}
+
+/// Validate and replace the settings of a maintenance window, returning it if it exists
+pub async fn update(
+    pool: &PgPool,
+    id: Uuid,
+    settings: &MaintenanceSettings,
+) -> Result<Option<MaintenanceWindow>, anyhow::Error> {
+    settings.validate()?;
+    Ok(db::update(pool, id, settings).await?)
+}
+
+/// The maintenance window a service is in at `at`, if any
+pub async fn active(pool: &PgPool, service: &Service, at: DateTime<Utc>) -> Result<Option<MaintenanceWindow>, sqlx::Error> {
+    let windows = db::current_for_service(pool, service.id, &service.options.tags, at).await?;
+    Ok(windows
+        .into_iter()
+        .find(|window| window.settings.applies_to(service) && window.settings.is_active(at)))
+}
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use rstat_core::Service;
+
+/// MaintenanceWindow is planned maintenance of one service, the services with a tag, or all services.
+///
+/// Checks keep running during the window, but their results are flagged, the services
+/// don't change state, so no alerts go out, and the time is left out of uptime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub settings: MaintenanceSettings,
+    pub created_at: DateTime<Utc>,
+}
+
+/// MaintenanceSettings describe what a maintenance window applies to and when
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceSettings {
+    pub name: String,
+    /// The service under maintenance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<Uuid>,
+    /// The tag of the services under maintenance. All services are when neither a
+    /// service nor a tag is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Start of a one-off window, or from when a recurring window recurs.
+    pub starts_at: DateTime<Utc>,
+    /// End of a one-off window, or until when a recurring window recurs, forever when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Makes the window recur, e.g. every Sunday night.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+}
+
+/// Recurrence opens a window on every occurrence of a cron expression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recurrence {
+    /// Cron expression (minute, hour, day of month, month, day of week) the window opens on.
+    pub cron: String,
+    /// How long the window stays open each time.
+    pub duration_minutes: u32,
+    /// IANA time zone of the cron expression, UTC when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl MaintenanceSettings {
+    /// Check the scope, bounds and recurrence of the window
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.service_id.is_some() && self.tag.is_some() {
+            return Err(anyhow::anyhow!("A maintenance window applies to a service or a tag, not both"));
+        }
+        if self.ends_at.is_some_and(|ends_at| ends_at <= self.starts_at) {
+            return Err(anyhow::anyhow!("A maintenance window must end after it starts"));
+        }
+
+        match &self.recurrence {
+            Some(recurrence) => {
+                if recurrence.duration_minutes == 0 {
+                    return Err(anyhow::anyhow!("A recurring maintenance window must last at least a minute"));
+                }
+                recurrence.timezone()?;
+                recurrence.cron()?;
+            }
+            None if self.ends_at.is_none() => {
+                return Err(anyhow::anyhow!("A one-off maintenance window needs an end"));
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the window applies to a service
+    pub fn applies_to(&self, service: &Service) -> bool {
+        match (&self.service_id, &self.tag) {
+            (Some(service_id), _) => *service_id == service.id,
+            (None, Some(tag)) => service.options.tags.contains(tag),
+            (None, None) => true,
+        }
+    }
+
+    /// Whether the window is open at `at`
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        if at < self.starts_at || self.ends_at.is_some_and(|ends_at| at >= ends_at) {
+            return false;
+        }
+
+        match &self.recurrence {
+            Some(recurrence) => recurrence.is_open(self.starts_at, at),
+            None => true,
+        }
+    }
+}
+
+impl Recurrence {
+    /// Whether a window opened from `since` on is still open at `at`
+    fn is_open(&self, since: DateTime<Utc>, at: DateTime<Utc>) -> bool {
+        let (Ok(tz), Ok(cron)) = (self.timezone(), self.cron()) else {
+            return false;
+        };
+
+        // The window is open if it opened during the last `duration_minutes`
+        let opened_after = at - chrono::Duration::minutes(self.duration_minutes as i64);
+        let (from, inclusive) = if opened_after >= since { (opened_after, false) } else { (since, true) };
+        cron.find_next_occurrence(&from.with_timezone(&tz), inclusive)
+            .is_ok_and(|opened_at| opened_at.with_timezone(&Utc) <= at)
+    }
+
+    fn timezone(&self) -> Result<Tz, anyhow::Error> {
+        match &self.timezone {
+            Some(timezone) => timezone
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Unknown time zone {}", timezone)),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    fn cron(&self) -> Result<Cron, anyhow::Error> {
+        Cron::new(&self.cron)
+            .parse()
+            .map_err(|err| anyhow::anyhow!("Invalid cron expression {}: {}", self.cron, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_maintenance_window_is_active() {
+        let deploy: MaintenanceSettings = serde_json::from_str(
+            r#"{"name": "Deploy", "tag": "payments", "starts_at": "2025-07-16T20:00:00Z", "ends_at": "2025-07-16T21:00:00Z"}"#,
+        )
+        .unwrap();
+        assert!(deploy.validate().is_ok());
+        assert!(!deploy.is_active(at("2025-07-16T19:59:59Z")));
+        assert!(deploy.is_active(at("2025-07-16T20:30:00Z")));
+        assert!(!deploy.is_active(at("2025-07-16T21:00:00Z")));
+
+        // Sunday nights 02:00 to 03:30 in Berlin, summer time
+        let weekly = MaintenanceSettings {
+            name: "Backups".to_string(),
+            service_id: None,
+            tag: None,
+            starts_at: at("2025-07-01T00:00:00Z"),
+            ends_at: None,
+            recurrence: Some(Recurrence {
+                cron: "0 2 * * SUN".to_string(),
+                duration_minutes: 90,
+                timezone: Some("Europe/Berlin".to_string()),
+            }),
+        };
+        assert!(weekly.validate().is_ok());
+        assert!(weekly.is_active(at("2025-07-20T00:00:00Z")));
+        assert!(weekly.is_active(at("2025-07-20T01:29:59Z")));
+        assert!(!weekly.is_active(at("2025-07-20T01:30:00Z")));
+        assert!(!weekly.is_active(at("2025-07-19T00:30:00Z")));
+        // Not before the window starts recurring
+        assert!(!MaintenanceSettings { starts_at: at("2025-07-20T00:30:00Z"), ..weekly.clone() }.is_active(at("2025-07-20T01:00:00Z")));
+
+        assert!(MaintenanceSettings { ends_at: None, ..deploy.clone() }.validate().is_err());
+        assert!(MaintenanceSettings { service_id: Some(Uuid::new_v4()), ..deploy }.validate().is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use sqlx::PgPool;
+use tokio::task;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use rstat_core::{ErrorKind, HealthCheckResult, Service};
+use crate::calculator::MetricsCalculator;
+use crate::db;
+use crate::models::{LatencyPercentiles, Resolution, ServiceMetric, TimeCoverage};
+use crate::sketch::LatencySketch;
+use crate::timeline::{self, Coverage, Sample, TimeSpan};
+
+/// MetricAggregate holds the running counters of a time bucket.
+///
+/// Aggregates of the same bucket can be merged, which is what lets metrics be
+/// updated as results come in instead of rescanning the bucket's results.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricAggregate {
+    pub total_checks: u32,
+    pub successful_checks: u32,
+    /// Sum of the response times of successful checks, in microseconds
+    pub latency_sum_us: i64,
+    pub sketch: LatencySketch,
+    /// Time covered by results, in milliseconds
+    pub up_ms: i64,
+    pub down_ms: i64,
+    pub unknown_ms: i64,
+    /// Time covered by results of checks during maintenance, left out of uptime
+    pub maintenance_ms: i64,
+    /// Time the service was paused, left out of uptime
+    pub paused_ms: i64,
+    /// Failed checks by kind of failure
+    pub failures: BTreeMap<ErrorKind, u32>,
+}
+
+impl MetricAggregate {
+    pub fn record(&mut self, result: &HealthCheckResult) {
+        // Checks during maintenance only count through the time they cover
+        if result.maintenance {
+            return;
+        }
+
+        self.total_checks += 1;
+        // Latency is only taken from successful checks
+        if result.success {
+            self.successful_checks += 1;
+            self.latency_sum_us += result.response_time as i64;
+            self.sketch.insert((result.response_time / 1000) as u32);
+        } else {
+            *self.failures.entry(result.error.unwrap_or(ErrorKind::Other)).or_default() += 1;
+        }
+    }
+
+    /// Add the time of a span falling into this bucket
+    pub fn cover(&mut self, span: &TimeSpan) {
+        let counter = match span.coverage {
+            Coverage::Up => &mut self.up_ms,
+            Coverage::Down => &mut self.down_ms,
+            Coverage::Unknown => &mut self.unknown_ms,
+            Coverage::Maintenance => &mut self.maintenance_ms,
+            Coverage::Paused => &mut self.paused_ms,
+        };
+        *counter += span.duration_ms();
+    }
+
+    pub fn merge(&mut self, other: &MetricAggregate) {
+        self.total_checks += other.total_checks;
+        self.successful_checks += other.successful_checks;
+        self.latency_sum_us += other.latency_sum_us;
+        self.sketch.merge(&other.sketch);
+        self.up_ms += other.up_ms;
+        self.down_ms += other.down_ms;
+        self.unknown_ms += other.unknown_ms;
+        self.maintenance_ms += other.maintenance_ms;
+        self.paused_ms += other.paused_ms;
+        for (kind, count) in &other.failures {
+            *self.failures.entry(*kind).or_default() += count;
+        }
+    }
+
+    /// Share of the known time the service was up.
+    ///
+    /// Unknown, maintenance and paused time count neither way, a bucket spent under
+    /// maintenance or paused is fully up. Until results cover any time, e.g. right after
+    /// the first check, this falls back to the ratio of successful checks.
+    pub fn uptime_percentage(&self) -> f64 {
+        let known_ms = self.up_ms + self.down_ms;
+        if known_ms == 0 && self.total_checks == 0 && self.maintenance_ms + self.paused_ms > 0 {
+            return 100.0;
+        }
+        if known_ms == 0 {
+            return ServiceMetric::calculate_uptime_percentage(self.successful_checks, self.total_checks);
+        }
+        self.up_ms as f64 / known_ms as f64 * 100.0
+    }
+
+    pub fn time(&self) -> TimeCoverage {
+        TimeCoverage::from_ms(self.up_ms, self.down_ms, self.unknown_ms, self.maintenance_ms, self.paused_ms)
+    }
+
+    pub fn average_latency_ms(&self) -> u32 {
+        if self.successful_checks == 0 {
+            0
+        } else {
+            (self.latency_sum_us / self.successful_checks as i64 / 1000) as u32
+        }
+    }
+
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        self.sketch.percentiles()
+    }
+}
+
+/// A service's bucket at one resolution
+type BucketKey = (Uuid, Resolution, DateTime<Utc>);
+
+/// How long before a pending aggregate was started a recalculation may have read its results.
+/// Covers the time between storing a result and recording it, and clock skew between instances.
+const RECALCULATION_MARGIN: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Results of a bucket waiting to be flushed
+struct Pending {
+    aggregate: MetricAggregate,
+    /// When the first of the results was recorded
+    since: DateTime<Utc>,
+}
+
+impl Pending {
+    fn new(since: DateTime<Utc>) -> Self {
+        Self { aggregate: MetricAggregate::default(), since }
+    }
+}
+
+/// A result whose covered time is added on the next flush
+struct Recorded {
+    created_at: DateTime<Utc>,
+    recorded_at: DateTime<Utc>,
+}
+
+/// The results of a service recorded since the last flush
+struct RecordedResults {
+    max_gap: chrono::Duration,
+    results: Vec<Recorded>,
+}
+
+/// Aggregator collects healthcheck results in memory and periodically merges them
+/// into the stored daily, hourly and 5-minute metrics.
+///
+/// Pending results are flushed on shutdown. A bucket recalculated by `rstat metrics calculate`
+/// after its pending results were stored is rebuilt from the stored results rather than
+/// merged into, as the recalculation may already have counted them.
+///
+/// Recording a result does not touch the database: the time it covers since the result
+/// before it is worked out on the next flush, with one lookup per service.
+pub struct Aggregator {
+    pool: PgPool,
+    pending: Mutex<HashMap<BucketKey, Pending>>,
+    recorded: Mutex<HashMap<Uuid, RecordedResults>>,
+}
+
+impl Aggregator {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            pending: Mutex::new(HashMap::new()),
+            recorded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a stored healthcheck result to the buckets it falls into. The time covered
+    /// since the service's previous result is added on the next flush.
+    pub fn record(&self, service: &Service, result: &HealthCheckResult) {
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        for resolution in [Resolution::Daily, Resolution::Hourly, Resolution::FiveMinutes] {
+            let bucket_start = resolution.truncate(result.created_at);
+            pending
+                .entry((service.id, resolution, bucket_start))
+                .or_insert_with(|| Pending::new(now))
+                .aggregate
+                .record(result);
+        }
+
+        let max_gap = chrono::Duration::from_std(service.interval).unwrap_or_default() * timeline::GAP_FACTOR;
+        self.recorded
+            .lock()
+            .unwrap()
+            .entry(service.id)
+            .or_insert_with(|| RecordedResults { max_gap, results: Vec::new() })
+            .results
+            .push(Recorded { created_at: result.created_at, recorded_at: now });
+    }
+
+    /// Add the time covered by the recorded results to the pending buckets.
+    ///
+    /// The results before them are read from the database rather than remembered, as other
+    /// instances may have checked the services in between. Services whose results cannot
+    /// be read are kept for the next flush.
+    async fn cover_recorded(&self) -> Option<anyhow::Error> {
+        let recorded = std::mem::take(&mut *self.recorded.lock().unwrap());
+        let mut last_error = None;
+
+        for (service_id, recorded) in recorded {
+            let spans = match self.covered(service_id, &recorded).await {
+                Ok(spans) => spans,
+                Err(e) => {
+                    let mut retry = self.recorded.lock().unwrap();
+                    let retry = retry
+                        .entry(service_id)
+                        .or_insert_with(|| RecordedResults { max_gap: recorded.max_gap, results: Vec::new() });
+                    retry.results.extend(recorded.results);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let mut pending = self.pending.lock().unwrap();
+            for (recorded_at, span) in spans {
+                for resolution in [Resolution::Daily, Resolution::Hourly, Resolution::FiveMinutes] {
+                    for (bucket_start, part) in span.split(resolution) {
+                        pending
+                            .entry((service_id, resolution, bucket_start))
+                            .or_insert_with(|| Pending::new(recorded_at))
+                            .aggregate
+                            .cover(&part);
+                    }
+                }
+            }
+        }
+        last_error
+    }
+
+    /// The spans covered by the recorded results of a service, with when each result was recorded
+    async fn covered(
+        &self,
+        service_id: Uuid,
+        recorded: &RecordedResults,
+    ) -> Result<Vec<(DateTime<Utc>, TimeSpan)>, anyhow::Error> {
+        let (Some(first), Some(last)) = (
+            recorded.results.iter().map(|result| result.created_at).min(),
+            recorded.results.iter().map(|result| result.created_at).max(),
+        ) else {
+            return Ok(vec![]);
+        };
+
+        let samples = db::get_samples_since_last_before(&self.pool, service_id, first, last).await?;
+        let start = samples.first().map_or(first, |(at, _)| *at);
+        let pauses = db::get_pauses(&self.pool, service_id, start, last).await?;
+
+        Ok(recorded
+            .results
+            .iter()
+            .flat_map(|result| {
+                covered_since_previous(&samples, result.created_at, recorded.max_gap, &pauses)
+                    .into_iter()
+                    .map(|span| (result.recorded_at, span))
+            })
+            .collect())
+    }
+
+    /// Flush pending aggregates every `interval` in a background task
+    pub fn start(self: Arc<Self>, interval: Duration) {
+        task::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match self.flush().await {
+                    Ok(0) => {}
+                    Ok(flushed) => debug!("Flushed {} metric buckets", flushed),
+                    Err(e) => error!("Failed to flush metrics: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Merge pending aggregates into the database, returning how many buckets were written.
+    ///
+    /// Buckets that fail to be written are kept and retried on the next flush.
+    pub async fn flush(&self) -> Result<usize, anyhow::Error> {
+        let mut last_error = self.cover_recorded().await;
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut flushed = 0;
+
+        for ((service_id, resolution, bucket_start), bucket) in pending {
+            match self.merge(service_id, resolution, bucket_start, &bucket).await {
+                Ok(()) => flushed += 1,
+                Err(e) => {
+                    let mut pending = self.pending.lock().unwrap();
+                    let retry = pending
+                        .entry((service_id, resolution, bucket_start))
+                        .or_insert_with(|| Pending::new(bucket.since));
+                    retry.aggregate.merge(&bucket.aggregate);
+                    retry.since = retry.since.min(bucket.since);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(flushed),
+        }
+    }
+
+    async fn merge(
+        &self,
+        service_id: Uuid,
+        resolution: Resolution,
+        bucket_start: DateTime<Utc>,
+        bucket: &Pending,
+    ) -> Result<(), anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        db::lock_bucket(&mut tx, service_id, resolution, bucket_start).await?;
+
+        let stored = match db::lock_aggregate(&mut tx, service_id, resolution, bucket_start).await? {
+            // The stored results include the pending ones, which the recalculation may have counted
+            Some((_, Some(recalculated_at))) if recalculated_at + RECALCULATION_MARGIN > bucket.since => {
+                MetricsCalculator::new(self.pool.clone())
+                    .aggregate(service_id, resolution, bucket_start, bucket_start + resolution.bucket())
+                    .await?
+                    .remove(&bucket_start)
+                    .unwrap_or_default()
+            }
+            Some((mut stored, _)) => {
+                stored.merge(&bucket.aggregate);
+                stored
+            }
+            None => bucket.aggregate.clone(),
+        };
+        db::store_aggregate(&mut tx, service_id, resolution, bucket_start, &stored, None).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// The time a result created at `created_at` covers since the sample before it, None
+/// when it is the first result of the service
+fn covered_since_previous(
+    samples: &[Sample],
+    created_at: DateTime<Utc>,
+    max_gap: chrono::Duration,
+    pauses: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<TimeSpan> {
+    // Stored results are precise to the microsecond, the result itself is not its previous one
+    let stored_at = created_at.trunc_subsecs(6);
+    let index = samples.partition_point(|(at, _)| *at < stored_at);
+    match index.checked_sub(1) {
+        Some(previous) => timeline::pause(timeline::between(samples[previous], created_at, max_gap), pauses),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(success: bool, latency_ms: u128) -> HealthCheckResult {
+        HealthCheckResult {
+            id: Uuid::new_v4(),
+            success,
+            degraded: false,
+            response_time: latency_ms * 1000,
+            code: if success { 200 } else { 500 },
+            message: String::new(),
+            error: None,
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_aggregates_merge_like_a_single_pass() {
+        let results = [result(true, 100), result(false, 5000), result(true, 300), result(true, 200)];
+
+        let mut whole = MetricAggregate::default();
+        results.iter().for_each(|r| whole.record(r));
+
+        let mut stored = MetricAggregate::default();
+        let mut increment = MetricAggregate::default();
+        results[..2].iter().for_each(|r| stored.record(r));
+        results[2..].iter().for_each(|r| increment.record(r));
+        stored.merge(&increment);
+
+        assert_eq!(stored, whole);
+        assert_eq!(whole.total_checks, 4);
+        assert_eq!(whole.uptime_percentage(), 75.0);
+        assert_eq!(whole.average_latency_ms(), 200);
+        assert_eq!(whole.percentiles().max_latency_ms, 300);
+        assert_eq!(whole.failures.get(&ErrorKind::Other), Some(&1));
+
+        // Checks during maintenance are not counted
+        whole.record(&HealthCheckResult { maintenance: true, ..result(false, 0) });
+        assert_eq!(whole.total_checks, 4);
+
+        // Once results cover time, uptime is weighted by it and unknown, maintenance and paused time are left out
+        let up = TimeSpan { start: Utc::now(), end: Utc::now() + chrono::Duration::minutes(9), coverage: Coverage::Up };
+        let down = TimeSpan { end: up.start + chrono::Duration::minutes(1), coverage: Coverage::Down, ..up };
+        let unknown = TimeSpan { coverage: Coverage::Unknown, ..up };
+        let maintenance = TimeSpan { coverage: Coverage::Maintenance, ..up };
+        let paused = TimeSpan { coverage: Coverage::Paused, ..down };
+        [up, down, unknown, maintenance, paused].iter().for_each(|span| whole.cover(span));
+        assert!((whole.uptime_percentage() - 90.0).abs() < 1e-9);
+        assert_eq!(whole.time().unknown_seconds, 540);
+        assert_eq!(whole.time().maintenance_seconds, 540);
+        assert_eq!(whole.time().paused_seconds, 60);
+
+        // A bucket the service spent paused is not down
+        let mut paused_bucket = MetricAggregate::default();
+        paused_bucket.cover(&paused);
+        assert_eq!(paused_bucket.uptime_percentage(), 100.0);
+    }
+
+    #[test]
+    fn test_results_cover_the_time_since_the_sample_before_them() {
+        let start = Utc::now().trunc_subsecs(6);
+        let minutes = |m: i64| start + chrono::Duration::minutes(m);
+        let max_gap = chrono::Duration::minutes(2);
+        // The failure at 1 was recorded by another instance
+        let samples = [(minutes(0), Coverage::Up), (minutes(1), Coverage::Down), (minutes(2), Coverage::Up)];
+
+        // Results are recorded with more precision than they are stored with
+        let recorded = minutes(2) + chrono::Duration::nanoseconds(500);
+        assert!(covered_since_previous(&samples, minutes(0), max_gap, &[]).is_empty());
+        assert_eq!(covered_since_previous(&samples, recorded, max_gap, &[])[0].start, minutes(1));
+        assert_eq!(
+            covered_since_previous(&samples, minutes(2), max_gap, &[]),
+            vec![TimeSpan { start: minutes(1), end: minutes(2), coverage: Coverage::Down }]
+        );
+
+        let paused = covered_since_previous(&samples, minutes(1), max_gap, &[(minutes(0), minutes(1))]);
+        assert!(paused.iter().all(|span| span.coverage == Coverage::Paused));
+    }
+}
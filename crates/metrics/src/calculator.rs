@@ -1,9 +1,13 @@
-use chrono::{NaiveDate, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use rstat_core::HealthCheckResult;
-use crate::models::{ServiceMetric, ServiceMetricsSummary};
+use rstat_core::{ErrorKind, HealthCheckResult};
+use crate::aggregator::MetricAggregate;
+use crate::models::{MetricRollup, Resolution, ServiceMetric, ServiceMetricsSummary};
+use crate::timeline::{self, Coverage, Sample};
 use crate::db;
 
 /// MetricsCalculator handles the computation of service metrics from health check results
@@ -16,69 +20,30 @@ impl MetricsCalculator {
         Self { pool }
     }
 
-    /// Calculate and store daily metrics for a service
+    /// Recalculate and store daily metrics for a service from its stored results
     pub async fn calculate_daily_metrics(
         &self,
         service_id: Uuid,
         date: NaiveDate,
     ) -> Result<ServiceMetric, anyhow::Error> {
-        // Get all health check results for the service on the specified date
-        let results = self.get_health_check_results_for_date(service_id, date).await?;
-        
-        if results.is_empty() {
-            // No results for this date, create a zero metric
-            let metric = ServiceMetric::new(service_id, date, 0.0, 0, 0, 0);
-            let _ = db::upsert_metric(
-                &self.pool,
-                service_id,
-                date,
-                metric.uptime_percentage,
-                metric.average_latency_ms,
-                metric.total_checks,
-                metric.successful_checks,
-            )
-            .await?;
-            return Ok(metric);
-        }
+        let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let aggregate = self
+            .recalculate(service_id, Resolution::Daily, start, start + chrono::Duration::days(1))
+            .await?
+            .remove(&start)
+            .unwrap_or_default();
 
-        // Calculate metrics from results
-        let total_checks = results.len() as u32;
-        let successful_checks = results.iter().filter(|r| r.success).count() as u32;
-        let uptime_percentage = ServiceMetric::calculate_uptime_percentage(successful_checks, total_checks);
-        
-        // Calculate average latency (only from successful checks)
-        let successful_results: Vec<&HealthCheckResult> = results.iter().filter(|r| r.success).collect();
-        let average_latency_ms = if successful_results.is_empty() {
-            0
-        } else {
-            let total_latency_microseconds: u128 = successful_results.iter().map(|r| r.response_time).sum();
-            let average_latency_microseconds = total_latency_microseconds / successful_results.len() as u128;
-            // Convert microseconds to milliseconds
-            (average_latency_microseconds / 1000) as u32
-        };
-
-        // Create the metric
-        let metric = ServiceMetric::new(
+        let mut metric = ServiceMetric::new(
             service_id,
             date,
-            uptime_percentage,
-            average_latency_ms,
-            total_checks,
-            successful_checks,
+            aggregate.uptime_percentage(),
+            aggregate.average_latency_ms(),
+            aggregate.percentiles(),
+            aggregate.total_checks,
+            aggregate.successful_checks,
         );
-
-        // Store in database
-        let _ = db::upsert_metric(
-            &self.pool,
-            service_id,
-            date,
-            metric.uptime_percentage,
-            metric.average_latency_ms,
-            metric.total_checks,
-            metric.successful_checks,
-        )
-        .await?;
-
+        metric.time = aggregate.time();
+        metric.failures = aggregate.failures.clone();
         Ok(metric)
     }
 
@@ -107,7 +72,7 @@ impl MetricsCalculator {
         while current_date <= end_date {
             let metric = self.calculate_daily_metrics(service_id, current_date).await?;
             metrics.push(metric);
-            current_date = current_date + chrono::Duration::days(1);
+            current_date += chrono::Duration::days(1);
         }
 
         Ok(metrics)
@@ -119,25 +84,51 @@ impl MetricsCalculator {
         service_id: Uuid,
         days: Option<u32>,
     ) -> Result<ServiceMetricsSummary, anyhow::Error> {
-        // First, ensure we have calculated metrics for the requested period
-        let days = days.unwrap_or(30);
-        let end_date = Utc::now().date_naive();
-        let start_date = end_date - chrono::Duration::days(days as i64);
-        
-        // Calculate any missing metrics in the range
-        self.calculate_metrics_for_range(
-            service_id,
-            start_date,
-            end_date,
-        )
-        .await?;
-
-        // Get the summary
-        let summary = db::get_metrics_summary(&self.pool, service_id, Some(days)).await?;
+        // Metrics are kept up to date by the Aggregator as results come in
+        let summary = db::get_metrics_summary(&self.pool, service_id, days).await?;
         Ok(summary)
     }
 
-    /// Update metrics for all services
+    /// Recalculate and store the rollups of a service from its results since `since`
+    pub async fn calculate_rollups(
+        &self,
+        service_id: Uuid,
+        resolution: Resolution,
+        since: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        let end = Utc::now();
+        if resolution == Resolution::Daily {
+            self.calculate_metrics_for_range(service_id, since.date_naive(), end.date_naive()).await?;
+            return Ok(());
+        }
+
+        self.recalculate(service_id, resolution, resolution.truncate(since), end).await?;
+        Ok(())
+    }
+
+    /// Get the metrics of a service for the last `days` days at the given resolution
+    pub async fn get_rollups(
+        &self,
+        service_id: Uuid,
+        resolution: Resolution,
+        days: u32,
+    ) -> Result<Vec<MetricRollup>, anyhow::Error> {
+        let end = Utc::now();
+        let start = resolution.truncate(end - chrono::Duration::days(days as i64));
+
+        let rollups = match resolution {
+            Resolution::FiveMinutes => db::get_five_minute_rollups(&self.pool, service_id, start, end).await?,
+            Resolution::Hourly => db::get_hourly_rollups(&self.pool, service_id, start, end).await?,
+            Resolution::Daily => db::get_metrics_for_service_last_days(&self.pool, service_id, days)
+                .await?
+                .into_iter()
+                .map(MetricRollup::from)
+                .collect(),
+        };
+        Ok(rollups)
+    }
+
+    /// Recalculate today's metrics and rollups for all services from their stored results
     pub async fn update_all_service_metrics(&self) -> Result<(), anyhow::Error> {
         // Get all services
         let services = rstat_service::all(&self.pool).await?;
@@ -146,30 +137,103 @@ impl MetricsCalculator {
             if let Err(e) = self.calculate_today_metrics(service.id).await {
                 tracing::warn!("Failed to calculate metrics for service {}: {}", service.name, e);
             }
+
+            let today = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+            for resolution in [Resolution::Hourly, Resolution::FiveMinutes] {
+                if let Err(e) = self.calculate_rollups(service.id, resolution, today).await {
+                    tracing::warn!("Failed to calculate {:?} rollups for service {}: {}", resolution, service.name, e);
+                }
+            }
         }
 
         Ok(())
     }
 
-    /// Get health check results for a specific date
-    async fn get_health_check_results_for_date(
+    /// Rebuild and store the buckets of [start, end) from the stored results.
+    ///
+    /// The buckets stay locked until all of them are stored, so a running Aggregator
+    /// cannot merge into them in between. Its pending aggregates may already be counted
+    /// once stored, so marking the buckets as recalculated makes it rebuild them instead.
+    async fn recalculate(
         &self,
         service_id: Uuid,
-        date: NaiveDate,
-    ) -> Result<Vec<HealthCheckResult>, anyhow::Error> {
-        let start_datetime = date.and_hms_opt(0, 0, 0).unwrap();
-        let end_datetime = date.and_hms_opt(23, 59, 59).unwrap();
+        resolution: Resolution,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<BTreeMap<DateTime<Utc>, MetricAggregate>, anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut bucket_start = start;
+        while bucket_start < end {
+            db::lock_bucket(&mut tx, service_id, resolution, bucket_start).await?;
+            bucket_start += resolution.bucket();
+        }
+
+        let recalculated_at = Utc::now();
+        let mut buckets = self.aggregate(service_id, resolution, start, end).await?;
+        // Every day is stored, even without results
+        if resolution == Resolution::Daily {
+            buckets.entry(start).or_default();
+        }
+        for (bucket_start, aggregate) in &buckets {
+            db::store_aggregate(&mut tx, service_id, resolution, *bucket_start, aggregate, Some(recalculated_at))
+                .await?;
+        }
 
+        tx.commit().await?;
+        Ok(buckets)
+    }
+
+    /// Aggregate the stored results of a service into the buckets of [start, end)
+    pub(crate) async fn aggregate(
+        &self,
+        service_id: Uuid,
+        resolution: Resolution,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<BTreeMap<DateTime<Utc>, MetricAggregate>, anyhow::Error> {
+        let service = rstat_service::get(&self.pool, service_id).await?;
+        let results = self.get_health_check_results(service_id, start, end).await?;
+
+        let mut buckets: BTreeMap<DateTime<Utc>, MetricAggregate> = BTreeMap::new();
+        for result in &results {
+            buckets.entry(resolution.truncate(result.created_at)).or_default().record(result);
+        }
+
+        // The results on either side of the period tell what its edges were covered by
+        let mut samples: Vec<Sample> = Vec::new();
+        samples.extend(db::get_last_sample_before(&self.pool, service_id, start).await?);
+        samples.extend(
+            results.iter().map(|result| (result.created_at, Coverage::of(result.success, result.maintenance))),
+        );
+        samples.extend(db::get_first_sample_from(&self.pool, service_id, end).await?);
+
+        let max_gap = chrono::Duration::from_std(service.interval)? * timeline::GAP_FACTOR;
+        let pauses = db::get_pauses(&self.pool, service_id, start, end).await?;
+        for span in timeline::pause(timeline::spans(&samples, start, end, max_gap), &pauses) {
+            for (bucket_start, part) in span.split(resolution) {
+                buckets.entry(bucket_start).or_default().cover(&part);
+            }
+        }
+        Ok(buckets)
+    }
+
+    /// Get health check results created in [start, end)
+    async fn get_health_check_results(
+        &self,
+        service_id: Uuid,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<HealthCheckResult>, anyhow::Error> {
         let rows = sqlx::query!(
             r#"
-            SELECT id, success, code, response_time, message, created_at
+            SELECT id, success, degraded, code, response_time, message, error_kind, maintenance, created_at
             FROM healthcheck_results 
-            WHERE service_id = $1 AND created_at >= $2 AND created_at <= $3
+            WHERE service_id = $1 AND created_at >= $2 AND created_at < $3
             ORDER BY created_at
             "#,
             service_id,
-            start_datetime,
-            end_datetime
+            start.naive_utc(),
+            end.naive_utc()
         )
         .fetch_all(&self.pool)
         .await?;
@@ -179,9 +243,15 @@ impl MetricsCalculator {
             .map(|row| HealthCheckResult {
                 id: row.id,
                 success: row.success,
+                degraded: row.degraded,
                 code: row.code.unwrap_or_default().parse::<u64>().unwrap_or_default(),
                 response_time: row.response_time.unwrap_or_default().try_into().unwrap_or_default(),
                 message: row.message.unwrap_or_default(),
+                error: row.error_kind.map(ErrorKind::from),
+                certificate: None,
+                timings: None,
+                location: None,
+                maintenance: row.maintenance,
                 created_at: row.created_at.and_utc(),
             })
             .collect();
@@ -1,46 +1,13 @@
-use chrono::{NaiveDate, Utc};
-use sqlx::PgPool;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use sqlx::{PgConnection, PgPool};
 use uuid::Uuid;
 use bigdecimal::BigDecimal;
 use num_traits::{ToPrimitive, FromPrimitive};
 
-use crate::models::{ServiceMetric, ServiceMetricsSummary};
-
-/// Upsert a service metric for a specific date
-pub async fn upsert_metric(
-    pool: &PgPool,
-    service_id: Uuid,
-    date: NaiveDate,
-    uptime_percentage: f64,
-    average_latency_ms: u32,
-    total_checks: u32,
-    successful_checks: u32,
-) -> Result<Uuid, sqlx::Error> {
-    let result = sqlx::query!(
-        r#"
-        INSERT INTO service_metrics (service_id, date, uptime_percentage, average_latency_ms, total_checks, successful_checks)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        ON CONFLICT (service_id, date) 
-        DO UPDATE SET 
-            uptime_percentage = EXCLUDED.uptime_percentage,
-            average_latency_ms = EXCLUDED.average_latency_ms,
-            total_checks = EXCLUDED.total_checks,
-            successful_checks = EXCLUDED.successful_checks,
-            updated_at = CURRENT_TIMESTAMP
-        RETURNING id
-        "#,
-        service_id,
-        date,
-        BigDecimal::from_f64(uptime_percentage).unwrap_or(BigDecimal::from(0)),
-        average_latency_ms as i32,
-        total_checks as i32,
-        successful_checks as i32
-    )
-    .fetch_one(pool)
-    .await?;
-
-    Ok(result.id)
-}
+use crate::aggregator::MetricAggregate;
+use crate::models::{LatencyPercentiles, MetricRollup, Resolution, ServiceMetric, ServiceMetricsSummary, TimeCoverage};
+use crate::sketch::LatencySketch;
+use crate::timeline::{Coverage, Sample};
 
 /// Get metrics for a service within a date range
 pub async fn get_metrics_for_service(
@@ -52,7 +19,8 @@ pub async fn get_metrics_for_service(
     let rows = sqlx::query!(
         r#"
         SELECT id, service_id, date, uptime_percentage, average_latency_ms, 
-               total_checks, successful_checks, created_at, updated_at
+               min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+               up_ms, down_ms, unknown_ms, maintenance_ms, paused_ms, total_checks, successful_checks, failures, created_at, updated_at
         FROM service_metrics 
         WHERE service_id = $1 AND date >= $2 AND date <= $3
         ORDER BY date DESC
@@ -72,8 +40,18 @@ pub async fn get_metrics_for_service(
             date: row.date,
             uptime_percentage: row.uptime_percentage.to_f64().unwrap_or(0.0),
             average_latency_ms: row.average_latency_ms as u32,
+            latency: LatencyPercentiles {
+                min_latency_ms: row.min_latency_ms as u32,
+                max_latency_ms: row.max_latency_ms as u32,
+                p50_latency_ms: row.p50_latency_ms as u32,
+                p90_latency_ms: row.p90_latency_ms as u32,
+                p95_latency_ms: row.p95_latency_ms as u32,
+                p99_latency_ms: row.p99_latency_ms as u32,
+            },
+            time: TimeCoverage::from_ms(row.up_ms, row.down_ms, row.unknown_ms, row.maintenance_ms, row.paused_ms),
             total_checks: row.total_checks as u32,
             successful_checks: row.successful_checks as u32,
+            failures: serde_json::from_value(row.failures).unwrap_or_default(),
             created_at: row.created_at.and_utc(),
             updated_at: row.updated_at.and_utc(),
         })
@@ -108,8 +86,32 @@ pub async fn get_metrics_summary(
 ) -> Result<ServiceMetricsSummary, sqlx::Error> {
     let days = days.unwrap_or(30);
     let metrics = get_metrics_for_service_last_days(pool, service_id, days).await?;
-    
-    Ok(ServiceMetricsSummary::from_metrics(service_id, metrics))
+    let start_date = Utc::now().date_naive() - chrono::Duration::days(days as i64);
+
+    let mut summary = ServiceMetricsSummary::from_metrics(service_id, metrics);
+    summary.latency = get_latency_sketch(pool, service_id, start_date).await?.percentiles();
+    Ok(summary)
+}
+
+/// Merge the latency sketches of the daily metrics of a service since a date
+pub async fn get_latency_sketch(
+    pool: &PgPool,
+    service_id: Uuid,
+    start_date: NaiveDate,
+) -> Result<LatencySketch, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT latency_sketch FROM service_metrics WHERE service_id = $1 AND date >= $2",
+        service_id,
+        start_date
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut sketch = LatencySketch::default();
+    for row in rows {
+        sketch.merge(&serde_json::from_value(row.latency_sketch).unwrap_or_default());
+    }
+    Ok(sketch)
 }
 
 /// Get metrics for all services within a date range
@@ -121,7 +123,8 @@ pub async fn get_all_metrics(
     let rows = sqlx::query!(
         r#"
         SELECT id, service_id, date, uptime_percentage, average_latency_ms, 
-               total_checks, successful_checks, created_at, updated_at
+               min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+               up_ms, down_ms, unknown_ms, maintenance_ms, paused_ms, total_checks, successful_checks, failures, created_at, updated_at
         FROM service_metrics 
         WHERE date >= $1 AND date <= $2
         ORDER BY service_id, date DESC
@@ -140,8 +143,18 @@ pub async fn get_all_metrics(
             date: row.date,
             uptime_percentage: row.uptime_percentage.to_f64().unwrap_or(0.0),
             average_latency_ms: row.average_latency_ms as u32,
+            latency: LatencyPercentiles {
+                min_latency_ms: row.min_latency_ms as u32,
+                max_latency_ms: row.max_latency_ms as u32,
+                p50_latency_ms: row.p50_latency_ms as u32,
+                p90_latency_ms: row.p90_latency_ms as u32,
+                p95_latency_ms: row.p95_latency_ms as u32,
+                p99_latency_ms: row.p99_latency_ms as u32,
+            },
+            time: TimeCoverage::from_ms(row.up_ms, row.down_ms, row.unknown_ms, row.maintenance_ms, row.paused_ms),
             total_checks: row.total_checks as u32,
             successful_checks: row.successful_checks as u32,
+            failures: serde_json::from_value(row.failures).unwrap_or_default(),
             created_at: row.created_at.and_utc(),
             updated_at: row.updated_at.and_utc(),
         })
@@ -160,7 +173,8 @@ pub async fn get_today_metrics(
     let row = sqlx::query!(
         r#"
         SELECT id, service_id, date, uptime_percentage, average_latency_ms, 
-               total_checks, successful_checks, created_at, updated_at
+               min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+               up_ms, down_ms, unknown_ms, maintenance_ms, paused_ms, total_checks, successful_checks, failures, created_at, updated_at
         FROM service_metrics 
         WHERE service_id = $1 AND date = $2
         "#,
@@ -176,26 +190,465 @@ pub async fn get_today_metrics(
         date: row.date,
         uptime_percentage: row.uptime_percentage.to_f64().unwrap_or(0.0),
         average_latency_ms: row.average_latency_ms as u32,
+        latency: LatencyPercentiles {
+            min_latency_ms: row.min_latency_ms as u32,
+            max_latency_ms: row.max_latency_ms as u32,
+            p50_latency_ms: row.p50_latency_ms as u32,
+            p90_latency_ms: row.p90_latency_ms as u32,
+            p95_latency_ms: row.p95_latency_ms as u32,
+            p99_latency_ms: row.p99_latency_ms as u32,
+        },
+        time: TimeCoverage::from_ms(row.up_ms, row.down_ms, row.unknown_ms, row.maintenance_ms, row.paused_ms),
         total_checks: row.total_checks as u32,
         successful_checks: row.successful_checks as u32,
+        failures: serde_json::from_value(row.failures).unwrap_or_default(),
         created_at: row.created_at.and_utc(),
         updated_at: row.updated_at.and_utc(),
     }))
 }
 
-/// Delete old metrics (cleanup function)
+/// A row of one of the rollup tables
+struct RollupRow {
+    id: Uuid,
+    service_id: Uuid,
+    bucket_start: NaiveDateTime,
+    uptime_percentage: BigDecimal,
+    average_latency_ms: i32,
+    min_latency_ms: i32,
+    max_latency_ms: i32,
+    p50_latency_ms: i32,
+    p90_latency_ms: i32,
+    p95_latency_ms: i32,
+    p99_latency_ms: i32,
+    up_ms: i64,
+    down_ms: i64,
+    unknown_ms: i64,
+    maintenance_ms: i64,
+    paused_ms: i64,
+    total_checks: i32,
+    successful_checks: i32,
+    failures: serde_json::Value,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+}
+
+impl RollupRow {
+    fn into_rollup(self, resolution: Resolution) -> MetricRollup {
+        MetricRollup {
+            id: self.id,
+            service_id: self.service_id,
+            resolution,
+            bucket_start: self.bucket_start.and_utc(),
+            uptime_percentage: self.uptime_percentage.to_f64().unwrap_or(0.0),
+            average_latency_ms: self.average_latency_ms as u32,
+            latency: LatencyPercentiles {
+                min_latency_ms: self.min_latency_ms as u32,
+                max_latency_ms: self.max_latency_ms as u32,
+                p50_latency_ms: self.p50_latency_ms as u32,
+                p90_latency_ms: self.p90_latency_ms as u32,
+                p95_latency_ms: self.p95_latency_ms as u32,
+                p99_latency_ms: self.p99_latency_ms as u32,
+            },
+            time: TimeCoverage::from_ms(self.up_ms, self.down_ms, self.unknown_ms, self.maintenance_ms, self.paused_ms),
+            total_checks: self.total_checks as u32,
+            successful_checks: self.successful_checks as u32,
+            failures: serde_json::from_value(self.failures).unwrap_or_default(),
+            created_at: self.created_at.and_utc(),
+            updated_at: self.updated_at.and_utc(),
+        }
+    }
+}
+
+/// Get hourly rollups for a service with buckets starting in [start, end)
+pub async fn get_hourly_rollups(
+    pool: &PgPool,
+    service_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<MetricRollup>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        RollupRow,
+        r#"
+        SELECT id, service_id, bucket_start, uptime_percentage, average_latency_ms,
+               min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+               up_ms, down_ms, unknown_ms, maintenance_ms, paused_ms, total_checks, successful_checks, failures,
+               created_at, updated_at
+        FROM service_metrics_hourly
+        WHERE service_id = $1 AND bucket_start >= $2 AND bucket_start < $3
+        ORDER BY bucket_start DESC
+        "#,
+        service_id,
+        start.naive_utc(),
+        end.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.into_rollup(Resolution::Hourly)).collect())
+}
+
+/// Get 5-minute rollups for a service with buckets starting in [start, end)
+pub async fn get_five_minute_rollups(
+    pool: &PgPool,
+    service_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<MetricRollup>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        RollupRow,
+        r#"
+        SELECT id, service_id, bucket_start, uptime_percentage, average_latency_ms,
+               min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+               up_ms, down_ms, unknown_ms, maintenance_ms, paused_ms, total_checks, successful_checks, failures,
+               created_at, updated_at
+        FROM service_metrics_5m
+        WHERE service_id = $1 AND bucket_start >= $2 AND bucket_start < $3
+        ORDER BY bucket_start DESC
+        "#,
+        service_id,
+        start.naive_utc(),
+        end.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.into_rollup(Resolution::FiveMinutes)).collect())
+}
+
+/// Columns holding the running counters of a bucket, the same in every metrics table
+const AGGREGATE_COLUMNS: &str = "total_checks, successful_checks, latency_sum_us, latency_sketch, up_ms, down_ms, \
+    unknown_ms, maintenance_ms, paused_ms, failures";
+
+/// AggregateTable is where the buckets of a resolution are stored
+struct AggregateTable {
+    name: &'static str,
+    /// Column holding the start of a bucket
+    bucket: &'static str,
+    /// Type the start of a bucket is stored as, daily metrics are stored by date
+    bucket_type: &'static str,
+}
+
+impl From<Resolution> for AggregateTable {
+    fn from(resolution: Resolution) -> Self {
+        match resolution {
+            Resolution::Daily => AggregateTable { name: "service_metrics", bucket: "date", bucket_type: "DATE" },
+            Resolution::Hourly => {
+                AggregateTable { name: "service_metrics_hourly", bucket: "bucket_start", bucket_type: "TIMESTAMP" }
+            }
+            Resolution::FiveMinutes => {
+                AggregateTable { name: "service_metrics_5m", bucket: "bucket_start", bucket_type: "TIMESTAMP" }
+            }
+        }
+    }
+}
+
+/// Hold the lock of a bucket until the transaction ends.
+///
+/// Taken by both the aggregator merging into a bucket and a recalculation rebuilding it,
+/// including buckets that have no row yet.
+pub async fn lock_bucket(
+    conn: &mut PgConnection,
+    service_id: Uuid,
+    resolution: Resolution,
+    bucket_start: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let key = format!("metrics:{}:{:?}:{}", service_id, resolution, bucket_start.timestamp());
+    sqlx::query!("SELECT pg_advisory_xact_lock(hashtextextended($1, 0))", key)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// The aggregate stored for a bucket and when it was last recalculated, locking its row
+/// until the transaction ends
+pub async fn lock_aggregate(
+    conn: &mut PgConnection,
+    service_id: Uuid,
+    resolution: Resolution,
+    bucket_start: DateTime<Utc>,
+) -> Result<Option<(MetricAggregate, Option<DateTime<Utc>>)>, sqlx::Error> {
+    let table = AggregateTable::from(resolution);
+    let sql = format!(
+        "SELECT {}, recalculated_at FROM {} WHERE service_id = $1 AND {} = $2::{} FOR UPDATE",
+        AGGREGATE_COLUMNS, table.name, table.bucket, table.bucket_type
+    );
+    let row: Option<LockedAggregateRow> = sqlx::query_as(&sql)
+        .bind(service_id)
+        .bind(bucket_start.naive_utc())
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(row.map(|row| (row.aggregate.into(), row.recalculated_at.map(|at| at.and_utc()))))
+}
+
+/// Store the aggregate of a bucket along with the metrics derived from it, replacing what was stored.
+///
+/// `recalculated_at` is set when the aggregate was rebuilt from the stored results, and kept otherwise.
+pub async fn store_aggregate(
+    conn: &mut PgConnection,
+    service_id: Uuid,
+    resolution: Resolution,
+    bucket_start: DateTime<Utc>,
+    aggregate: &MetricAggregate,
+    recalculated_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    let table = AggregateTable::from(resolution);
+    let sql = format!(
+        r#"
+        INSERT INTO {table} (service_id, {bucket}, uptime_percentage, average_latency_ms,
+                             min_latency_ms, max_latency_ms, p50_latency_ms, p90_latency_ms, p95_latency_ms, p99_latency_ms,
+                             {columns}, recalculated_at)
+        VALUES ($1, $2::{bucket_type}, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)
+        ON CONFLICT (service_id, {bucket})
+        DO UPDATE SET
+            uptime_percentage = EXCLUDED.uptime_percentage,
+            average_latency_ms = EXCLUDED.average_latency_ms,
+            min_latency_ms = EXCLUDED.min_latency_ms,
+            max_latency_ms = EXCLUDED.max_latency_ms,
+            p50_latency_ms = EXCLUDED.p50_latency_ms,
+            p90_latency_ms = EXCLUDED.p90_latency_ms,
+            p95_latency_ms = EXCLUDED.p95_latency_ms,
+            p99_latency_ms = EXCLUDED.p99_latency_ms,
+            total_checks = EXCLUDED.total_checks,
+            successful_checks = EXCLUDED.successful_checks,
+            latency_sum_us = EXCLUDED.latency_sum_us,
+            latency_sketch = EXCLUDED.latency_sketch,
+            up_ms = EXCLUDED.up_ms,
+            down_ms = EXCLUDED.down_ms,
+            unknown_ms = EXCLUDED.unknown_ms,
+            maintenance_ms = EXCLUDED.maintenance_ms,
+            paused_ms = EXCLUDED.paused_ms,
+            failures = EXCLUDED.failures,
+            recalculated_at = COALESCE(EXCLUDED.recalculated_at, {table}.recalculated_at),
+            updated_at = CURRENT_TIMESTAMP
+        "#,
+        table = table.name,
+        bucket = table.bucket,
+        bucket_type = table.bucket_type,
+        columns = AGGREGATE_COLUMNS,
+    );
+
+    let latency = aggregate.percentiles();
+    sqlx::query(&sql)
+        .bind(service_id)
+        .bind(bucket_start.naive_utc())
+        .bind(BigDecimal::from_f64(aggregate.uptime_percentage()).unwrap_or(BigDecimal::from(0)))
+        .bind(aggregate.average_latency_ms() as i32)
+        .bind(latency.min_latency_ms as i32)
+        .bind(latency.max_latency_ms as i32)
+        .bind(latency.p50_latency_ms as i32)
+        .bind(latency.p90_latency_ms as i32)
+        .bind(latency.p95_latency_ms as i32)
+        .bind(latency.p99_latency_ms as i32)
+        .bind(aggregate.total_checks as i32)
+        .bind(aggregate.successful_checks as i32)
+        .bind(aggregate.latency_sum_us)
+        .bind(serde_json::to_value(&aggregate.sketch).unwrap())
+        .bind(aggregate.up_ms)
+        .bind(aggregate.down_ms)
+        .bind(aggregate.unknown_ms)
+        .bind(aggregate.maintenance_ms)
+        .bind(aggregate.paused_ms)
+        .bind(serde_json::to_value(&aggregate.failures).unwrap())
+        .bind(recalculated_at.map(|at| at.naive_utc()))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Merge the aggregates of the buckets of a service starting at or after `since`
+pub async fn get_aggregate_since(
+    pool: &PgPool,
+    service_id: Uuid,
+    resolution: Resolution,
+    since: DateTime<Utc>,
+) -> Result<MetricAggregate, sqlx::Error> {
+    let table = AggregateTable::from(resolution);
+    let sql = format!(
+        "SELECT {} FROM {} WHERE service_id = $1 AND {} >= $2::{}",
+        AGGREGATE_COLUMNS, table.name, table.bucket, table.bucket_type
+    );
+    let rows: Vec<AggregateRow> = sqlx::query_as(&sql)
+        .bind(service_id)
+        .bind(resolution.truncate(since).naive_utc())
+        .fetch_all(pool)
+        .await?;
+
+    let mut aggregate = MetricAggregate::default();
+    for row in rows {
+        aggregate.merge(&row.into());
+    }
+    Ok(aggregate)
+}
+
+/// Running counters of a bucket, as stored in any of the metrics tables
+#[derive(sqlx::FromRow)]
+struct AggregateRow {
+    total_checks: i32,
+    successful_checks: i32,
+    latency_sum_us: i64,
+    latency_sketch: serde_json::Value,
+    up_ms: i64,
+    down_ms: i64,
+    unknown_ms: i64,
+    maintenance_ms: i64,
+    paused_ms: i64,
+    failures: serde_json::Value,
+}
+
+/// A bucket's counters along with when it was last recalculated
+#[derive(sqlx::FromRow)]
+struct LockedAggregateRow {
+    #[sqlx(flatten)]
+    aggregate: AggregateRow,
+    recalculated_at: Option<NaiveDateTime>,
+}
+
+impl From<AggregateRow> for MetricAggregate {
+    fn from(row: AggregateRow) -> Self {
+        Self {
+            total_checks: row.total_checks as u32,
+            successful_checks: row.successful_checks as u32,
+            latency_sum_us: row.latency_sum_us,
+            sketch: serde_json::from_value(row.latency_sketch).unwrap_or_default(),
+            up_ms: row.up_ms,
+            down_ms: row.down_ms,
+            unknown_ms: row.unknown_ms,
+            maintenance_ms: row.maintenance_ms,
+            paused_ms: row.paused_ms,
+            failures: serde_json::from_value(row.failures).unwrap_or_default(),
+        }
+    }
+}
+
+/// The latest result of a service recorded before `before`
+pub async fn get_last_sample_before(
+    pool: &PgPool,
+    service_id: Uuid,
+    before: DateTime<Utc>,
+) -> Result<Option<Sample>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT created_at, success, maintenance FROM healthcheck_results
+        WHERE service_id = $1 AND created_at < $2
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        service_id,
+        before.naive_utc()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| (row.created_at.and_utc(), Coverage::of(row.success, row.maintenance))))
+}
+
+/// The results of a service recorded in [start, end], along with the last one before `start`,
+/// oldest first
+pub async fn get_samples_since_last_before(
+    pool: &PgPool,
+    service_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Sample>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT created_at, success, maintenance FROM healthcheck_results
+        WHERE service_id = $1 AND created_at <= $3 AND created_at >= COALESCE(
+            (SELECT max(created_at) FROM healthcheck_results WHERE service_id = $1 AND created_at < $2),
+            $2
+        )
+        ORDER BY created_at
+        "#,
+        service_id,
+        start.naive_utc(),
+        end.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.created_at.and_utc(), Coverage::of(row.success, row.maintenance)))
+        .collect())
+}
+
+/// The earliest result of a service recorded at or after `from`
+pub async fn get_first_sample_from(
+    pool: &PgPool,
+    service_id: Uuid,
+    from: DateTime<Utc>,
+) -> Result<Option<Sample>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT created_at, success, maintenance FROM healthcheck_results
+        WHERE service_id = $1 AND created_at >= $2
+        ORDER BY created_at
+        LIMIT 1
+        "#,
+        service_id,
+        from.naive_utc()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| (row.created_at.and_utc(), Coverage::of(row.success, row.maintenance))))
+}
+
+/// The periods a service was paused within [start, end), oldest first
+pub async fn get_pauses(
+    pool: &PgPool,
+    service_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>, sqlx::Error> {
+    let pauses = rstat_service::db::pauses(pool, service_id, start, end).await?;
+    Ok(pauses
+        .into_iter()
+        .map(|(paused_at, resumed_at)| (paused_at, resumed_at.unwrap_or(end)))
+        .collect())
+}
+
+/// Delete old metrics (cleanup function): daily metrics older than `older_than_days`,
+/// and rollups older than that or their retention, whichever is shorter
 pub async fn delete_old_metrics(
     pool: &PgPool,
     older_than_days: u32,
 ) -> Result<u64, sqlx::Error> {
-    let cutoff_date = Utc::now().date_naive() - chrono::Duration::days(older_than_days as i64);
-    
-    let result = sqlx::query!(
-        "DELETE FROM service_metrics WHERE date < $1",
-        cutoff_date
-    )
-    .execute(pool)
-    .await?;
+    let today = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+
+    let mut deleted = 0;
+    for resolution in [Resolution::Daily, Resolution::Hourly, Resolution::FiveMinutes] {
+        let days = resolution.retention_days().map_or(older_than_days, |days| days.min(older_than_days));
+        deleted += delete_buckets_before(pool, resolution, today - chrono::Duration::days(days as i64)).await?;
+    }
+    Ok(deleted)
+}
+
+/// Delete the rollups past their retention
+pub async fn delete_expired_rollups(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let now = Utc::now();
+
+    let mut deleted = 0;
+    for resolution in [Resolution::Hourly, Resolution::FiveMinutes] {
+        if let Some(days) = resolution.retention_days() {
+            deleted += delete_buckets_before(pool, resolution, now - chrono::Duration::days(days as i64)).await?;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Delete the buckets of a resolution starting before `before`
+async fn delete_buckets_before(
+    pool: &PgPool,
+    resolution: Resolution,
+    before: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let table = AggregateTable::from(resolution);
+    let sql = format!("DELETE FROM {} WHERE {} < $1::{}", table.name, table.bucket, table.bucket_type);
+    let result = sqlx::query(&sql).bind(before.naive_utc()).execute(pool).await?;
 
     Ok(result.rows_affected())
-} 
\ No newline at end of file
+}
@@ -1,7 +1,14 @@
 pub mod models;
 pub mod db;
 pub mod calculator;
+pub mod aggregator;
+pub mod retention;
+pub mod sketch;
+pub mod timeline;
 
 pub use models::*;
 pub use db::*;
-pub use calculator::*; 
\ No newline at end of file
+pub use calculator::*;
+pub use aggregator::*;
+pub use retention::*;
+pub use sketch::*;
\ No newline at end of file
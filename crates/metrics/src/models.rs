@@ -1,4 +1,7 @@
+use std::collections::BTreeMap;
+
 use chrono::{NaiveDate, DateTime, Utc};
+use rstat_core::ErrorKind;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -11,8 +14,14 @@ pub struct ServiceMetric {
     pub date: NaiveDate,
     pub uptime_percentage: f64,
     pub average_latency_ms: u32,
+    #[serde(flatten)]
+    pub latency: LatencyPercentiles,
+    #[serde(flatten)]
+    pub time: TimeCoverage,
     pub total_checks: u32,
     pub successful_checks: u32,
+    /// Failed checks by kind of failure
+    pub failures: BTreeMap<ErrorKind, u32>,
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created_at: DateTime<Utc>,
     #[serde(with = "chrono::serde::ts_seconds")]
@@ -26,6 +35,14 @@ pub struct ServiceMetricsSummary {
     pub current_uptime: f64,
     pub current_latency_ms: u32,
     pub average_latency_ms: u32,
+    /// Latency distribution over the whole period
+    #[serde(flatten)]
+    pub latency: LatencyPercentiles,
+    /// Time up, down and unknown over the whole period
+    #[serde(flatten)]
+    pub time: TimeCoverage,
+    /// Failed checks by kind of failure over the whole period
+    pub failures: BTreeMap<ErrorKind, u32>,
     pub uptime_data: Vec<UptimeDataPoint>,
 }
 
@@ -35,6 +52,173 @@ pub struct UptimeDataPoint {
     pub date: String, // ISO date string (YYYY-MM-DD)
     pub uptime_percentage: f64,
     pub latency_ms: u32,
+    pub p95_latency_ms: u32,
+    pub downtime_seconds: u64,
+    pub unknown_seconds: u64,
+    pub maintenance_seconds: u64,
+    pub paused_seconds: u64,
+}
+
+/// TimeCoverage is how long a service was up, down, or not known to be either.
+///
+/// Each result covers the time until the next one, so uptime is measured in time
+/// rather than in checks, and gaps without results are counted as unknown. Time
+/// covered by checks during maintenance windows and time the service was paused
+/// are counted apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeCoverage {
+    pub uptime_seconds: u64,
+    pub downtime_seconds: u64,
+    pub unknown_seconds: u64,
+    pub maintenance_seconds: u64,
+    pub paused_seconds: u64,
+}
+
+impl TimeCoverage {
+    pub fn from_ms(up_ms: i64, down_ms: i64, unknown_ms: i64, maintenance_ms: i64, paused_ms: i64) -> Self {
+        Self {
+            uptime_seconds: (up_ms / 1000) as u64,
+            downtime_seconds: (down_ms / 1000) as u64,
+            unknown_seconds: (unknown_ms / 1000) as u64,
+            maintenance_seconds: (maintenance_ms / 1000) as u64,
+            paused_seconds: (paused_ms / 1000) as u64,
+        }
+    }
+
+    pub fn merge(&mut self, other: &TimeCoverage) {
+        self.uptime_seconds += other.uptime_seconds;
+        self.downtime_seconds += other.downtime_seconds;
+        self.unknown_seconds += other.unknown_seconds;
+        self.maintenance_seconds += other.maintenance_seconds;
+        self.paused_seconds += other.paused_seconds;
+    }
+}
+
+/// Resolution is the size of the time buckets metrics are rolled up into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "5m", alias = "5min")]
+    FiveMinutes,
+    #[serde(rename = "1h", alias = "hourly")]
+    Hourly,
+    #[serde(rename = "1d", alias = "daily")]
+    Daily,
+}
+
+impl Resolution {
+    /// Length of a bucket
+    pub fn bucket(&self) -> chrono::Duration {
+        match self {
+            Resolution::FiveMinutes => chrono::Duration::minutes(5),
+            Resolution::Hourly => chrono::Duration::hours(1),
+            Resolution::Daily => chrono::Duration::days(1),
+        }
+    }
+
+    /// How many days are returned when no period is requested
+    pub fn default_days(&self) -> u32 {
+        match self {
+            Resolution::FiveMinutes => 1,
+            Resolution::Hourly => 7,
+            Resolution::Daily => 30,
+        }
+    }
+
+    /// How many days buckets are kept. Rollups serve recent history and burn rates, daily
+    /// metrics are kept until cleaned up.
+    pub fn retention_days(&self) -> Option<u32> {
+        match self {
+            Resolution::FiveMinutes => Some(7),
+            Resolution::Hourly => Some(30),
+            Resolution::Daily => None,
+        }
+    }
+
+    /// Start of the bucket a timestamp falls into
+    pub fn truncate(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = timestamp.timestamp();
+        let start = seconds - seconds.rem_euclid(self.bucket().num_seconds());
+        DateTime::from_timestamp(start, 0).unwrap_or(timestamp)
+    }
+}
+
+/// MetricRollup represents the metrics of a service within one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRollup {
+    pub id: Uuid,
+    pub service_id: Uuid,
+    pub resolution: Resolution,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub bucket_start: DateTime<Utc>,
+    pub uptime_percentage: f64,
+    pub average_latency_ms: u32,
+    #[serde(flatten)]
+    pub latency: LatencyPercentiles,
+    #[serde(flatten)]
+    pub time: TimeCoverage,
+    pub total_checks: u32,
+    pub successful_checks: u32,
+    /// Failed checks by kind of failure
+    pub failures: BTreeMap<ErrorKind, u32>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<ServiceMetric> for MetricRollup {
+    fn from(metric: ServiceMetric) -> Self {
+        Self {
+            id: metric.id,
+            service_id: metric.service_id,
+            resolution: Resolution::Daily,
+            bucket_start: metric.date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            uptime_percentage: metric.uptime_percentage,
+            average_latency_ms: metric.average_latency_ms,
+            latency: metric.latency,
+            time: metric.time,
+            total_checks: metric.total_checks,
+            successful_checks: metric.successful_checks,
+            failures: metric.failures,
+            created_at: metric.created_at,
+            updated_at: metric.updated_at,
+        }
+    }
+}
+
+/// LatencyPercentiles describes the latency distribution of successful checks, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub min_latency_ms: u32,
+    pub max_latency_ms: u32,
+    pub p50_latency_ms: u32,
+    pub p90_latency_ms: u32,
+    pub p95_latency_ms: u32,
+    pub p99_latency_ms: u32,
+}
+
+impl LatencyPercentiles {
+    /// Compute percentiles from latency samples using the nearest-rank method
+    pub fn from_samples(mut samples: Vec<u32>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+
+        let rank = |percentile: f64| {
+            let index = (percentile / 100.0 * samples.len() as f64).ceil() as usize;
+            samples[index.clamp(1, samples.len()) - 1]
+        };
+
+        Self {
+            min_latency_ms: samples[0],
+            max_latency_ms: samples[samples.len() - 1],
+            p50_latency_ms: rank(50.0),
+            p90_latency_ms: rank(90.0),
+            p95_latency_ms: rank(95.0),
+            p99_latency_ms: rank(99.0),
+        }
+    }
 }
 
 impl ServiceMetric {
@@ -44,6 +228,7 @@ impl ServiceMetric {
         date: NaiveDate,
         uptime_percentage: f64,
         average_latency_ms: u32,
+        latency: LatencyPercentiles,
         total_checks: u32,
         successful_checks: u32,
     ) -> Self {
@@ -53,14 +238,19 @@ impl ServiceMetric {
             date,
             uptime_percentage,
             average_latency_ms,
+            latency,
+            time: TimeCoverage::default(),
             total_checks,
             successful_checks,
+            failures: BTreeMap::new(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 
-    /// Calculate uptime percentage from check results
+    /// Calculate uptime percentage from check results.
+    ///
+    /// Metrics are time-weighted once results cover some time, see `MetricAggregate::uptime_percentage`.
     pub fn calculate_uptime_percentage(successful_checks: u32, total_checks: u32) -> f64 {
         if total_checks == 0 {
             0.0
@@ -79,13 +269,16 @@ impl ServiceMetricsSummary {
                 current_uptime: 0.0,
                 current_latency_ms: 0,
                 average_latency_ms: 0,
+                latency: LatencyPercentiles::default(),
+                time: TimeCoverage::default(),
+                failures: BTreeMap::new(),
                 uptime_data: vec![],
             };
         }
 
         // Sort metrics by date (most recent first)
         let mut sorted_metrics = metrics;
-        sorted_metrics.sort_by(|a, b| b.date.cmp(&a.date));
+        sorted_metrics.sort_by_key(|m| std::cmp::Reverse(m.date));
 
         // Current metrics (most recent day)
         let current_metric = &sorted_metrics[0];
@@ -96,6 +289,15 @@ impl ServiceMetricsSummary {
         let total_latency: u32 = sorted_metrics.iter().map(|m| m.average_latency_ms).sum();
         let average_latency_ms = total_latency / sorted_metrics.len() as u32;
 
+        let mut time = TimeCoverage::default();
+        let mut failures = BTreeMap::new();
+        for metric in &sorted_metrics {
+            time.merge(&metric.time);
+            for (kind, count) in &metric.failures {
+                *failures.entry(*kind).or_default() += count;
+            }
+        }
+
         // Convert to uptime data points
         let uptime_data = sorted_metrics
             .iter()
@@ -103,6 +305,11 @@ impl ServiceMetricsSummary {
                 date: metric.date.format("%Y-%m-%d").to_string(),
                 uptime_percentage: metric.uptime_percentage,
                 latency_ms: metric.average_latency_ms,
+                p95_latency_ms: metric.latency.p95_latency_ms,
+                downtime_seconds: metric.time.downtime_seconds,
+                unknown_seconds: metric.time.unknown_seconds,
+                maintenance_seconds: metric.time.maintenance_seconds,
+                paused_seconds: metric.time.paused_seconds,
             })
             .collect();
 
@@ -111,6 +318,11 @@ impl ServiceMetricsSummary {
             current_uptime,
             current_latency_ms,
             average_latency_ms,
+            // Percentiles of different days can't be combined, they are computed
+            // from the merged latency sketches by db::get_metrics_summary
+            latency: LatencyPercentiles::default(),
+            time,
+            failures,
             uptime_data,
         }
     }
@@ -129,4 +341,37 @@ where
 {
     let s = String::deserialize(deserializer)?;
     NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(serde::de::Error::custom)
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles_nearest_rank() {
+        let samples: Vec<u32> = (1..=100).rev().collect();
+        let latency = LatencyPercentiles::from_samples(samples);
+
+        assert_eq!(latency.min_latency_ms, 1);
+        assert_eq!(latency.max_latency_ms, 100);
+        assert_eq!(latency.p50_latency_ms, 50);
+        assert_eq!(latency.p90_latency_ms, 90);
+        assert_eq!(latency.p95_latency_ms, 95);
+        assert_eq!(latency.p99_latency_ms, 99);
+
+        let single = LatencyPercentiles::from_samples(vec![42]);
+        assert_eq!(single.p50_latency_ms, 42);
+        assert_eq!(single.p99_latency_ms, 42);
+        assert_eq!(LatencyPercentiles::from_samples(vec![]), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_resolution_truncate() {
+        let timestamp = DateTime::parse_from_rfc3339("2025-07-07T10:37:42Z").unwrap().to_utc();
+
+        assert_eq!(Resolution::FiveMinutes.truncate(timestamp).to_rfc3339(), "2025-07-07T10:35:00+00:00");
+        assert_eq!(Resolution::Hourly.truncate(timestamp).to_rfc3339(), "2025-07-07T10:00:00+00:00");
+        assert_eq!(Resolution::Daily.truncate(timestamp).to_rfc3339(), "2025-07-07T00:00:00+00:00");
+        assert_eq!(serde_json::from_str::<Resolution>("\"hourly\"").unwrap(), Resolution::Hourly);
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::task;
+use tracing::{debug, error, info};
+
+use rstat_core::{Leader, RETENTION_LOCK};
+use crate::db;
+
+/// Retention periodically deletes hourly and 5-minute rollups past their retention.
+///
+/// Daily metrics are only deleted by `rstat metrics cleanup`. Only one of the rstat
+/// instances sharing a database deletes rollups at a time.
+pub struct Retention {
+    pool: PgPool,
+    leader: Leader,
+}
+
+impl Retention {
+    pub fn new(pool: PgPool) -> Self {
+        Self { leader: Leader::new(pool.clone(), RETENTION_LOCK), pool }
+    }
+
+    /// Delete expired rollups every `interval` in a background task
+    pub fn start(mut self, interval: Duration) {
+        task::spawn(async move {
+            loop {
+                if !self.leader.is_leader().await {
+                    debug!("Another instance is deleting expired rollups");
+                } else {
+                    match db::delete_expired_rollups(&self.pool).await {
+                        Ok(0) => {}
+                        Ok(deleted) => info!("Deleted {} expired rollups", deleted),
+                        Err(e) => error!("Failed to delete expired rollups: {}", e),
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::LatencyPercentiles;
+
+/// Ratio between the bounds of consecutive bins, giving percentiles within 1% of the true value
+const GAMMA: f64 = 1.02;
+
+/// LatencySketch is a mergeable histogram of latencies in milliseconds.
+///
+/// Bins grow logarithmically (as in DDSketch), so a sketch stays small however many
+/// samples it holds, and sketches of different periods can be merged into one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencySketch {
+    pub count: u64,
+    pub min_ms: u32,
+    pub max_ms: u32,
+    /// Samples below 1ms
+    pub zero: u64,
+    pub bins: BTreeMap<i32, u64>,
+}
+
+impl LatencySketch {
+    pub fn insert(&mut self, latency_ms: u32) {
+        if self.count == 0 || latency_ms < self.min_ms {
+            self.min_ms = latency_ms;
+        }
+        self.max_ms = self.max_ms.max(latency_ms);
+        self.count += 1;
+
+        if latency_ms == 0 {
+            self.zero += 1;
+        } else {
+            *self.bins.entry(bin(latency_ms)).or_default() += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &LatencySketch) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 || other.min_ms < self.min_ms {
+            self.min_ms = other.min_ms;
+        }
+        self.max_ms = self.max_ms.max(other.max_ms);
+        self.count += other.count;
+        self.zero += other.zero;
+        for (bin, count) in &other.bins {
+            *self.bins.entry(*bin).or_default() += count;
+        }
+    }
+
+    /// Estimate the latency at a quantile between 0 and 1, using the nearest rank
+    pub fn quantile(&self, quantile: f64) -> u32 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let rank = ((quantile * self.count as f64).ceil() as u64).clamp(1, self.count);
+        if rank <= self.zero {
+            return 0;
+        }
+
+        let mut seen = self.zero;
+        for (bin, count) in &self.bins {
+            seen += count;
+            if seen >= rank {
+                return value(*bin).clamp(self.min_ms, self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    /// Estimate how many samples are at most `latency_ms`
+    pub fn count_at_most(&self, latency_ms: u32) -> u64 {
+        if latency_ms == 0 {
+            return self.zero;
+        }
+        let threshold = bin(latency_ms);
+        self.zero + self.bins.range(..=threshold).map(|(_, count)| count).sum::<u64>()
+    }
+
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles {
+            min_latency_ms: self.min_ms,
+            max_latency_ms: self.max_ms,
+            p50_latency_ms: self.quantile(0.50),
+            p90_latency_ms: self.quantile(0.90),
+            p95_latency_ms: self.quantile(0.95),
+            p99_latency_ms: self.quantile(0.99),
+        }
+    }
+}
+
+/// The bin holding latencies in (GAMMA^(bin-1), GAMMA^bin]
+fn bin(latency_ms: u32) -> i32 {
+    ((latency_ms as f64).ln() / GAMMA.ln()).ceil() as i32
+}
+
+/// The latency a bin stands for, chosen to minimise the relative error of its range
+fn value(bin: i32) -> u32 {
+    (2.0 * GAMMA.powi(bin) / (GAMMA + 1.0)).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sketch_quantiles_and_merge() {
+        let samples: Vec<u32> = (0..10_000).map(|i| (i * 7919 % 10_000) / 10).collect();
+
+        let mut whole = LatencySketch::default();
+        let (mut first, mut second) = (LatencySketch::default(), LatencySketch::default());
+        for (i, sample) in samples.iter().enumerate() {
+            whole.insert(*sample);
+            if i % 3 == 0 { first.insert(*sample) } else { second.insert(*sample) }
+        }
+        first.merge(&second);
+        assert_eq!(first, whole);
+
+        let exact = LatencyPercentiles::from_samples(samples);
+        let estimated = whole.percentiles();
+        assert_eq!(estimated.min_latency_ms, exact.min_latency_ms);
+        assert_eq!(estimated.max_latency_ms, exact.max_latency_ms);
+        for (estimate, exact) in [
+            (estimated.p50_latency_ms, exact.p50_latency_ms),
+            (estimated.p90_latency_ms, exact.p90_latency_ms),
+            (estimated.p99_latency_ms, exact.p99_latency_ms),
+        ] {
+            let error = (estimate as f64 - exact as f64).abs() / exact as f64;
+            assert!(error <= 0.01, "estimate {} too far from {}", estimate, exact);
+        }
+
+        let exact_below = (0..10_000u32).filter(|i| (i * 7919 % 10_000) / 10 <= 500).count() as f64;
+        assert!((whole.count_at_most(500) as f64 - exact_below).abs() / exact_below <= 0.01);
+
+        let roundtrip: LatencySketch = serde_json::from_value(serde_json::to_value(&whole).unwrap()).unwrap();
+        assert_eq!(roundtrip, whole);
+        assert_eq!(serde_json::from_str::<LatencySketch>("{}").unwrap(), LatencySketch::default());
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::Resolution;
+
+/// How many check intervals a result vouches for before the state of a service is unknown
+pub const GAP_FACTOR: i32 = 2;
+
+/// A point of a service's timeline: when a result was recorded and what it tells about the service
+pub type Sample = (DateTime<Utc>, Coverage);
+
+/// Coverage is what is known about a service during a span of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    Up,
+    Down,
+    /// No result was recorded recently enough to tell
+    Unknown,
+    /// The service was under maintenance, whether it was up or not
+    Maintenance,
+    /// The service was paused and not checked
+    Paused,
+}
+
+impl Coverage {
+    /// What the result of a check tells until the next one
+    pub fn of(success: bool, maintenance: bool) -> Self {
+        match (success, maintenance) {
+            (_, true) => Coverage::Maintenance,
+            (true, false) => Coverage::Up,
+            (false, false) => Coverage::Down,
+        }
+    }
+}
+
+/// TimeSpan is a stretch of time [start, end) with a single coverage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSpan {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub coverage: Coverage,
+}
+
+impl TimeSpan {
+    pub fn duration_ms(&self) -> i64 {
+        (self.end - self.start).num_milliseconds()
+    }
+
+    /// The part of the span within [start, end), if any
+    pub fn clip(self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<TimeSpan> {
+        let clipped = TimeSpan {
+            start: self.start.max(start),
+            end: self.end.min(end),
+            ..self
+        };
+        (clipped.start < clipped.end).then_some(clipped)
+    }
+
+    /// Split the span at the bucket boundaries of a resolution, keyed by bucket start
+    pub fn split(self, resolution: Resolution) -> Vec<(DateTime<Utc>, TimeSpan)> {
+        let mut parts = Vec::new();
+        let mut bucket = resolution.truncate(self.start);
+        while bucket < self.end {
+            let next = bucket + resolution.bucket();
+            if let Some(part) = self.clip(bucket, next) {
+                parts.push((bucket, part));
+            }
+            bucket = next;
+        }
+        parts
+    }
+}
+
+/// The spans between a result and the next one, recorded at `until`.
+///
+/// A result covers the time until the next result, but for at most `max_gap`:
+/// past that the scheduler stalled or the service was not checked, and the
+/// rest of the gap is unknown rather than up or down.
+pub fn between(from: Sample, until: DateTime<Utc>, max_gap: Duration) -> Vec<TimeSpan> {
+    let (start, coverage) = from;
+    if until <= start {
+        return vec![];
+    }
+
+    let covered = until.min(start + max_gap);
+    let mut spans = vec![TimeSpan { start, end: covered, coverage }];
+    if covered < until {
+        spans.push(TimeSpan { start: covered, end: until, coverage: Coverage::Unknown });
+    }
+    spans
+}
+
+/// Mark the parts of spans falling in pauses [start, end) as paused.
+///
+/// Pauses are ordered and don't overlap, as a service is only paused once at a time.
+pub fn pause(spans: Vec<TimeSpan>, pauses: &[(DateTime<Utc>, DateTime<Utc>)]) -> Vec<TimeSpan> {
+    let mut paused = Vec::with_capacity(spans.len());
+    for span in spans {
+        let mut rest = Some(span);
+        for &(start, end) in pauses {
+            let Some(span) = rest else { break };
+            paused.extend(span.clip(span.start, start));
+            paused.extend(span.clip(start, end).map(|part| TimeSpan { coverage: Coverage::Paused, ..part }));
+            rest = span.clip(end, span.end);
+        }
+        paused.extend(rest);
+    }
+    paused
+}
+
+/// The spans covered by consecutive samples, clipped to [start, end).
+///
+/// The last sample covers nothing, its span is only known once the next result comes in.
+pub fn spans(samples: &[Sample], start: DateTime<Utc>, end: DateTime<Utc>, max_gap: Duration) -> Vec<TimeSpan> {
+    samples
+        .windows(2)
+        .flat_map(|pair| between(pair[0], pair[1].0, max_gap))
+        .filter_map(|span| span.clip(start, end))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2025-07-10T{}Z", time)).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_spans_cover_until_next_result() {
+        let max_gap = Duration::minutes(2);
+        let samples = [
+            (at("09:59:00"), Coverage::Up),
+            (at("10:00:30"), Coverage::Down),
+            (at("10:01:00"), Coverage::Up),
+            // The scheduler stalled for 10 minutes
+            (at("10:11:00"), Coverage::Maintenance),
+            (at("10:12:00"), Coverage::Up),
+        ];
+        let spans = spans(&samples, at("10:00:00"), at("11:00:00"), max_gap);
+
+        let total = |coverage: Coverage| -> i64 {
+            spans.iter().filter(|s| s.coverage == coverage).map(|s| s.duration_ms()).sum()
+        };
+        // 30s before the first result of the hour, then 2 minutes after the last one before the stall
+        assert_eq!(total(Coverage::Up), 150_000);
+        assert_eq!(total(Coverage::Down), 30_000);
+        assert_eq!(total(Coverage::Unknown), 480_000);
+        // Results of checks during maintenance are neither up nor down
+        assert_eq!(total(Coverage::Maintenance), 60_000);
+
+        // The service was paused during the stall and into the maintenance
+        let paused = pause(spans.clone(), &[(at("10:05:00"), at("10:10:00")), (at("10:10:30"), at("10:11:30"))]);
+        let total = |coverage: Coverage| -> i64 {
+            paused.iter().filter(|s| s.coverage == coverage).map(|s| s.duration_ms()).sum()
+        };
+        assert_eq!(total(Coverage::Paused), 360_000);
+        assert_eq!(total(Coverage::Unknown), 150_000);
+        assert_eq!(total(Coverage::Maintenance), 30_000);
+        assert_eq!(total(Coverage::Up), 150_000);
+
+        let stall = TimeSpan { start: at("10:03:00"), end: at("10:11:00"), coverage: Coverage::Unknown };
+        let parts = stall.split(Resolution::FiveMinutes);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].0, at("10:00:00"));
+        assert_eq!(parts[1].1.duration_ms(), 300_000);
+        assert_eq!(parts[2].1.end, at("10:11:00"));
+    }
+}
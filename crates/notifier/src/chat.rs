@@ -0,0 +1,290 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use rstat_core::{ChatConfig, ChatPlatform, Event, ServiceStatus, WebhookConfig};
+
+use crate::webhook::WebhookNotifier;
+use crate::Notifier;
+
+/// ChatNotifier posts events to Slack, Discord or Teams incoming webhooks in their native format.
+pub struct ChatNotifier {
+    name: String,
+    config: ChatConfig,
+    dashboard_url: Option<String>,
+    webhook: WebhookNotifier,
+}
+
+impl ChatNotifier {
+    pub fn new(config: ChatConfig, dashboard_url: Option<String>) -> Self {
+        Self::with_client(config, dashboard_url, Client::new())
+    }
+
+    pub fn with_client(config: ChatConfig, dashboard_url: Option<String>, client: Client) -> Self {
+        let webhook = WebhookNotifier::with_client(
+            WebhookConfig {
+                url: config.url.clone(),
+                ..WebhookConfig::default()
+            },
+            client,
+        );
+
+        Self {
+            name: config.name.clone().unwrap_or_else(|| config.platform.to_string()),
+            config,
+            dashboard_url,
+            webhook,
+        }
+    }
+
+    /// The message for an event in the format of the configured platform.
+    pub fn payload(&self, event: &Event) -> Value {
+        let message = Message::new(event, self.dashboard_url.as_deref());
+        match self.config.platform {
+            ChatPlatform::Slack => message.slack(),
+            ChatPlatform::Discord => message.discord(),
+            ChatPlatform::Teams => message.teams(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for ChatNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        if self.config.events.is_empty() {
+            return !matches!(event, Event::CheckCompleted { .. });
+        }
+        self.config.events.iter().any(|name| name == event.name())
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        let body = serde_json::to_string(&self.payload(event))?;
+        self.webhook.post(event, &body).await
+    }
+}
+
+/// Message holds the parts shared by every chat format.
+struct Message<'a> {
+    title: String,
+    summary: String,
+    status: ServiceStatus,
+    facts: Vec<(&'static str, String)>,
+    error: Option<&'a str>,
+    link: Option<&'a str>,
+    timestamp: String,
+}
+
+impl<'a> Message<'a> {
+    fn new(event: &'a Event, dashboard_url: Option<&'a str>) -> Self {
+        let service = event.service();
+        let status = event.status();
+
+        let mut facts = vec![("Status", status.to_string())];
+        if let Some(latency_ms) = event.latency_ms() {
+            facts.push(("Latency", format!("{}ms", latency_ms)));
+        }
+        match event {
+            Event::IncidentOpened { incident_id, .. } => facts.push(("Incident", incident_id.to_string())),
+            Event::IncidentResolved { incident_id, duration_seconds, .. } => {
+                facts.push(("Incident", incident_id.to_string()));
+                facts.push(("Duration", format!("{}s", duration_seconds)));
+            }
+            Event::CertificateExpiring { certificate, .. } => {
+                facts.push(("Expires", certificate.not_after.format("%Y-%m-%d").to_string()));
+            }
+            Event::ErrorBudgetBurn { window, burn_rate, budget_remaining, .. } => {
+                facts.push(("Burn rate", format!("{:.1}x over {}", burn_rate, window)));
+                facts.push(("Budget left", format!("{:.1}%", budget_remaining)));
+            }
+            _ => {}
+        }
+        facts.push(("Check", service.kind.clone()));
+
+        Self {
+            title: format!("{} is {}", service.name, status),
+            summary: event.to_string(),
+            status,
+            facts,
+            error: event.message(),
+            link: dashboard_url,
+            timestamp: event.occurred_at().to_rfc3339(),
+        }
+    }
+
+    fn color(&self) -> u32 {
+        match self.status {
+            ServiceStatus::Up => 0x2EB67D,
+            ServiceStatus::Down => 0xE01E5A,
+            ServiceStatus::Degraded => 0xECB22E,
+            ServiceStatus::Unknown | ServiceStatus::Paused => 0x868686,
+        }
+    }
+
+    /// Slack Block Kit, wrapped in an attachment to get the coloured bar
+    fn slack(&self) -> Value {
+        let title = match self.link {
+            Some(link) => format!("*<{}|{}>*", link, self.title),
+            None => format!("*{}*", self.title),
+        };
+
+        let mut blocks = vec![
+            json!({ "type": "section", "text": { "type": "mrkdwn", "text": title } }),
+            json!({
+                "type": "section",
+                "fields": self.facts.iter()
+                    .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+                    .collect::<Vec<_>>()
+            }),
+        ];
+        if let Some(error) = self.error {
+            blocks.push(json!({ "type": "section", "text": { "type": "mrkdwn", "text": format!("*Error*\n```{}```", error) } }));
+        }
+        blocks.push(json!({
+            "type": "context",
+            "elements": [{ "type": "mrkdwn", "text": format!("rstat • {}", self.timestamp) }]
+        }));
+
+        json!({
+            "text": self.summary,
+            "attachments": [{ "color": format!("#{:06X}", self.color()), "blocks": blocks }]
+        })
+    }
+
+    /// Discord embed
+    fn discord(&self) -> Value {
+        let mut fields: Vec<Value> = self
+            .facts
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+            .collect();
+        if let Some(error) = self.error {
+            fields.push(json!({ "name": "Error", "value": format!("```{}```", error), "inline": false }));
+        }
+
+        let mut embed = json!({
+            "title": self.title,
+            "description": self.summary,
+            "color": self.color(),
+            "fields": fields,
+            "timestamp": self.timestamp,
+            "footer": { "text": "rstat" }
+        });
+        if let Some(link) = self.link {
+            embed["url"] = json!(link);
+        }
+
+        json!({ "embeds": [embed] })
+    }
+
+    /// Microsoft Teams adaptive card
+    fn teams(&self) -> Value {
+        let color = match self.status {
+            ServiceStatus::Up => "Good",
+            ServiceStatus::Down => "Attention",
+            ServiceStatus::Degraded => "Warning",
+            ServiceStatus::Unknown | ServiceStatus::Paused => "Default",
+        };
+
+        let mut body = vec![
+            json!({ "type": "TextBlock", "text": self.title, "weight": "Bolder", "size": "Medium", "color": color }),
+            json!({ "type": "TextBlock", "text": self.summary, "wrap": true }),
+            json!({
+                "type": "FactSet",
+                "facts": self.facts.iter()
+                    .map(|(name, value)| json!({ "title": name, "value": value }))
+                    .collect::<Vec<_>>()
+            }),
+        ];
+        if let Some(error) = self.error {
+            body.push(json!({ "type": "TextBlock", "text": error, "wrap": true, "fontType": "Monospace", "color": color }));
+        }
+
+        let mut card = json!({
+            "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+            "type": "AdaptiveCard",
+            "version": "1.4",
+            "msteams": { "width": "Full" },
+            "body": body
+        });
+        if let Some(link) = self.link {
+            card["actions"] = json!([{ "type": "Action.OpenUrl", "title": "Open dashboard", "url": link }]);
+        }
+
+        json!({
+            "type": "message",
+            "attachments": [{ "contentType": "application/vnd.microsoft.card.adaptive", "content": card }]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, routing::post, Json, Router};
+    use chrono::Utc;
+    use rstat_core::ServiceRef;
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    /// Start a local capture server recording every JSON body posted to it
+    async fn start_capture() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/",
+                post(|State(captured): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>| async move {
+                    captured.lock().unwrap().push(body);
+                }),
+            )
+            .with_state(captured.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (url, captured)
+    }
+
+    #[tokio::test]
+    async fn test_platform_payloads() {
+        let (url, captured) = start_capture().await;
+        let down = Event::ServiceDown {
+            service: ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() },
+            previous: ServiceStatus::Up,
+            message: "Assertion failed: body does not contain \"ok\"".to_string(),
+            latency_ms: Some(120),
+            occurred_at: Utc::now(),
+        };
+
+        for platform in [ChatPlatform::Slack, ChatPlatform::Discord, ChatPlatform::Teams] {
+            let config = ChatConfig { platform, url: url.clone(), name: None, events: vec![] };
+            let notifier = ChatNotifier::new(config, Some("https://status.example.com".to_string()));
+            notifier.notify(&down).await.unwrap();
+        }
+
+        let captured = captured.lock().unwrap();
+        let (slack, discord, teams) = (&captured[0], &captured[1], &captured[2]);
+
+        let attachment = &slack["attachments"][0];
+        assert_eq!(attachment["color"], "#E01E5A");
+        assert_eq!(attachment["blocks"][0]["text"]["text"], "*<https://status.example.com|API is DOWN>*");
+        assert_eq!(attachment["blocks"][1]["fields"][1]["text"], "*Latency*\n120ms");
+        assert!(attachment["blocks"][2]["text"]["text"].as_str().unwrap().contains("body does not contain"));
+
+        let embed = &discord["embeds"][0];
+        assert_eq!(embed["color"], 0xE01E5A);
+        assert_eq!(embed["url"], "https://status.example.com");
+        assert_eq!(embed["fields"][0]["value"], "DOWN");
+
+        let card = &teams["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][0]["color"], "Attention");
+        assert_eq!(card["actions"][0]["url"], "https://status.example.com");
+    }
+}
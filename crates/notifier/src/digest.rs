@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, Timelike, Utc};
+use sqlx::PgPool;
+use tokio::task;
+use tracing::{debug, error, info, warn};
+
+use rstat_core::{EmailConfig, Leader, Service, DIGEST_LOCK};
+use rstat_metrics::{MetricsCalculator, ServiceMetric};
+
+use crate::email::Mailer;
+
+/// Digest emails a daily summary of each service's metrics for the previous day.
+///
+/// Global recipients get every service, recipients configured on a service only get that service.
+pub struct Digest {
+    calculator: MetricsCalculator,
+    mailer: Mailer,
+    recipients: Vec<EmailConfig>,
+}
+
+impl Digest {
+    pub fn new(pool: PgPool, mailer: Mailer, recipients: Vec<EmailConfig>) -> Self {
+        Self {
+            calculator: MetricsCalculator::new(pool),
+            mailer,
+            recipients: recipients.into_iter().filter(|r| r.digest).collect(),
+        }
+    }
+
+    /// Start sending digests as a background task
+    pub fn start(self) {
+        task::spawn(async move {
+            self.run().await;
+        });
+    }
+
+    async fn run(self) {
+        let mut leader = Leader::new(self.calculator.pool.clone(), DIGEST_LOCK);
+        info!("Starting daily digest for {} global recipient groups", self.recipients.len());
+
+        loop {
+            // Wake up at the start of every hour and send the digests due at that hour
+            let now = Utc::now();
+            let seconds_into_hour = (now.minute() * 60 + now.second()) as u64;
+            tokio::time::sleep(Duration::from_secs(3600 - seconds_into_hour)).await;
+
+            // Only one of the instances sharing a database sends digests
+            if !leader.is_leader().await {
+                debug!("Another instance is sending daily digests");
+                continue;
+            }
+
+            let hour = Utc::now().hour() as u8;
+            match self.send(Some(hour)).await {
+                Ok(0) => {}
+                Ok(sent) => info!("Sent {} daily digest emails", sent),
+                Err(e) => error!("Failed to send daily digest: {}", e),
+            }
+        }
+    }
+
+    /// Send the digest to every recipient group due at `hour`, or to all of them when no hour is given.
+    /// Returns the number of emails sent.
+    pub async fn send(&self, hour: Option<u8>) -> Result<usize, anyhow::Error> {
+        let due = |config: &EmailConfig| config.digest && hour.is_none_or(|h| config.digest_hour == h);
+
+        let services = rstat_service::all(&self.calculator.pool).await?;
+        let wanted = self.recipients.iter().any(due)
+            || services.iter().any(|s| s.options.notifications.email.iter().any(due));
+        if !wanted {
+            return Ok(0);
+        }
+
+        let mut rows = Vec::with_capacity(services.len());
+        for service in services {
+            match self.calculator.calculate_yesterday_metrics(service.id).await {
+                Ok(metric) => rows.push((service, metric)),
+                Err(e) => warn!("Failed to calculate yesterday metrics for service {}: {}", service.name, e),
+            }
+        }
+
+        let date = Utc::now().date_naive() - chrono::Duration::days(1);
+        let subject = format!("[rstat] Daily digest for {}", date);
+        let mut sent = 0;
+
+        for config in self.recipients.iter().filter(|c| due(c)) {
+            match self.mailer.send(&config.to, &subject, format_digest(date, &rows)).await {
+                Ok(()) => sent += 1,
+                Err(e) => error!("Failed to send digest to {}: {}", config.to.join(","), e),
+            }
+        }
+
+        for row in &rows {
+            for config in row.0.options.notifications.email.iter().filter(|c| due(c)) {
+                match self.mailer.send(&config.to, &subject, format_digest(date, std::slice::from_ref(row))).await {
+                    Ok(()) => sent += 1,
+                    Err(e) => error!("Failed to send digest to {}: {}", config.to.join(","), e),
+                }
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+/// Format the digest body, one line per service
+pub fn format_digest(date: NaiveDate, rows: &[(Service, ServiceMetric)]) -> String {
+    let mut lines = vec![format!("Daily digest for {}", date), String::new()];
+    if rows.is_empty() {
+        lines.push("No services are being monitored.".to_string());
+    }
+
+    let width = rows.iter().map(|(service, _)| service.name.len()).max().unwrap_or(0);
+    for (service, metric) in rows {
+        lines.push(format!(
+            "{:<width$}  {:<8}  {:>7.2}% uptime  {:>5}ms avg latency  {}/{} checks",
+            service.name,
+            service.state.status.to_string(),
+            metric.uptime_percentage,
+            metric.average_latency_ms,
+            metric.successful_checks,
+            metric.total_checks,
+            width = width
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstat_core::{Kind, ServiceOptions, ServiceState, ServiceStatus, TcpChecker};
+    use rstat_metrics::LatencyPercentiles;
+    use uuid::Uuid;
+
+    fn service(name: &str, status: ServiceStatus) -> Service {
+        Service {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            kind: Kind::TCP(TcpChecker::default()),
+            interval: Duration::from_secs(60),
+            next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState { status, ..ServiceState::default() },
+        }
+    }
+
+    #[test]
+    fn test_format_digest() {
+        let date = NaiveDate::from_ymd_opt(2025, 7, 3).unwrap();
+        let api = service("API", ServiceStatus::Up);
+        let database = service("Database", ServiceStatus::Down);
+        let rows = vec![
+            (api.clone(), ServiceMetric::new(api.id, date, 99.93, 120, LatencyPercentiles::default(), 1440, 1439)),
+            (database.clone(), ServiceMetric::new(database.id, date, 50.0, 8, LatencyPercentiles::default(), 1440, 720)),
+        ];
+
+        assert_eq!(
+            format_digest(date, &rows),
+            "Daily digest for 2025-07-03\n\n\
+             API       UP          99.93% uptime    120ms avg latency  1439/1440 checks\n\
+             Database  DOWN        50.00% uptime      8ms avg latency  720/1440 checks"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use rstat_core::{EmailConfig, Event, SmtpConfig, SmtpSecurity};
+
+use crate::Notifier;
+
+/// Mailer sends plain text email through the configured SMTP server.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(config: &SmtpConfig) -> Result<Self, anyhow::Error> {
+        let builder = match config.security {
+            SmtpSecurity::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+
+        let mut builder = builder
+            .port(config.port)
+            .timeout(Some(Duration::from_secs(config.timeout as u64)));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+
+    pub async fn send(&self, to: &[String], subject: &str, body: String) -> Result<(), anyhow::Error> {
+        let mut message = Message::builder().from(self.from.clone()).subject(subject);
+        for recipient in to {
+            message = message.to(recipient.parse()?);
+        }
+
+        self.transport.send(message.body(body)?).await?;
+        Ok(())
+    }
+}
+
+/// EmailNotifier sends an email for each accepted event.
+pub struct EmailNotifier {
+    name: String,
+    config: EmailConfig,
+    mailer: Mailer,
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailConfig, mailer: Mailer) -> Self {
+        Self {
+            name: format!("email:{}", config.to.join(",")),
+            config,
+            mailer,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        self.config.events.iter().any(|name| name == event.name())
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        self.mailer.send(&self.config.to, &subject(event), body(event)).await
+    }
+}
+
+fn subject(event: &Event) -> String {
+    let service = &event.service().name;
+    match event {
+        Event::ServiceDown { .. } => format!("[rstat] {} is DOWN", service),
+        Event::ServiceRecovered { .. } => format!("[rstat] {} is UP", service),
+        Event::ServiceDegraded { .. } => format!("[rstat] {} is DEGRADED", service),
+        _ => format!("[rstat] {}", event),
+    }
+}
+
+fn body(event: &Event) -> String {
+    let service = event.service();
+    let mut lines = vec![
+        event.to_string(),
+        String::new(),
+        format!("Service: {} ({})", service.name, service.kind),
+        format!("Service ID: {}", service.id),
+        format!("Time: {}", event.occurred_at().format("%Y-%m-%d %H:%M:%S UTC")),
+    ];
+
+    match event {
+        Event::ServiceDown { previous, .. }
+        | Event::ServiceRecovered { previous, .. }
+        | Event::ServiceDegraded { previous, .. } => lines.push(format!("Previous status: {}", previous)),
+        Event::IncidentOpened { incident_id, .. } | Event::IncidentResolved { incident_id, .. } => {
+            lines.push(format!("Incident: {}", incident_id))
+        }
+        Event::ErrorBudgetBurn { severity, threshold, .. } => {
+            lines.push(format!("Alert: {} (burn rate threshold {:.1}x)", severity, threshold))
+        }
+        _ => {}
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use chrono::Utc;
+    use rstat_core::{ServiceRef, ServiceStatus};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    /// Start a local SMTP sink that accepts every message and records its DATA section
+    async fn start_smtp_sink() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+
+        let received = messages.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"220 localhost ESMTP sink\r\n").await.unwrap();
+
+                    let mut data: Option<String> = None;
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if let Some(message) = data.as_mut() {
+                            if line == "." {
+                                received.lock().unwrap().push(data.take().unwrap());
+                                writer.write_all(b"250 OK\r\n").await.unwrap();
+                            } else {
+                                message.push_str(&line);
+                                message.push('\n');
+                            }
+                            continue;
+                        }
+
+                        let command = line.to_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+                            b"250 localhost\r\n"
+                        } else if command.starts_with("DATA") {
+                            data = Some(String::new());
+                            b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                        } else if command.starts_with("QUIT") {
+                            writer.write_all(b"221 Bye\r\n").await.unwrap();
+                            break;
+                        } else {
+                            b"250 OK\r\n"
+                        };
+                        writer.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (port, messages)
+    }
+
+    fn local_smtp(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: None,
+            password: None,
+            security: SmtpSecurity::None,
+            from: "rstat <alerts@example.com>".to_string(),
+            timeout: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_down_alert_is_sent() {
+        let (port, messages) = start_smtp_sink().await;
+        let notifier = EmailNotifier::new(
+            EmailConfig { to: vec!["oncall@example.com".to_string()], ..EmailConfig::default() },
+            Mailer::new(&local_smtp(port)).unwrap(),
+        );
+
+        let down = Event::ServiceDown {
+            service: ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() },
+            previous: ServiceStatus::Up,
+            message: "connection refused".to_string(),
+            latency_ms: None,
+            occurred_at: Utc::now(),
+        };
+        assert!(notifier.accepts(&down));
+        notifier.notify(&down).await.unwrap();
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("Subject: [rstat] API is DOWN"));
+        assert!(messages[0].contains("To: oncall@example.com"));
+        assert!(messages[0].contains("API is DOWN: connection refused"));
+    }
+}
@@ -0,0 +1,208 @@
+pub mod chat;
+pub mod digest;
+pub mod email;
+pub mod log;
+pub mod pagerduty;
+pub mod service;
+pub mod template;
+pub mod webhook;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+use rstat_core::Event;
+
+/// Trait for all channels that events can be delivered to.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name of the channel, used in logs.
+    fn name(&self) -> &str;
+
+    /// Whether this channel wants the event. Completed checks are skipped by default.
+    fn accepts(&self, event: &Event) -> bool {
+        !matches!(event, Event::CheckCompleted { .. })
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error>;
+}
+
+/// Dispatcher fans each event out to every registered notifier.
+#[derive(Clone, Default)]
+pub struct Dispatcher {
+    notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, notifier: impl Notifier + 'static) {
+        self.notifiers.push(Arc::new(notifier));
+    }
+
+    pub fn len(&self) -> usize {
+        self.notifiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifiers.is_empty()
+    }
+
+    /// Deliver an event to all notifiers that accept it.
+    ///
+    /// Channels are notified concurrently and a failing channel does not affect the others.
+    pub async fn dispatch(&self, event: &Event) {
+        let deliveries = self
+            .notifiers
+            .iter()
+            .filter(|notifier| notifier.accepts(event))
+            .map(|notifier| deliver(notifier.as_ref(), event));
+
+        join_all(deliveries).await;
+    }
+
+    /// Dispatch events from the channel until all senders are dropped.
+    ///
+    /// Every channel delivers its events one after the other from its own queue, so events
+    /// arrive in the order they were published while a slow channel only holds up itself.
+    pub async fn run(self, mut events: mpsc::Receiver<Event>) {
+        let mut workers = Vec::with_capacity(self.notifiers.len());
+        let queues: Vec<_> = self
+            .notifiers
+            .iter()
+            .map(|notifier| {
+                let (tx, mut rx) = mpsc::unbounded_channel::<Arc<Event>>();
+                let worker = notifier.clone();
+                workers.push(tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        deliver(worker.as_ref(), &event).await;
+                    }
+                }));
+                (notifier.clone(), tx)
+            })
+            .collect();
+
+        while let Some(event) = events.recv().await {
+            let event = Arc::new(event);
+            for (notifier, queue) in &queues {
+                if notifier.accepts(&event) {
+                    let _ = queue.send(event.clone());
+                }
+            }
+        }
+
+        // Let the channels drain their queues
+        drop(queues);
+        join_all(workers).await;
+    }
+}
+
+async fn deliver(notifier: &dyn Notifier, event: &Event) {
+    match notifier.notify(event).await {
+        Ok(()) => debug!("Delivered {} event to {}", event.name(), notifier.name()),
+        Err(err) => error!("Failed to deliver {} event to {}: {}", event.name(), notifier.name(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use chrono::Utc;
+    use rstat_core::{ServiceRef, ServiceStatus};
+    use uuid::Uuid;
+
+    struct Recorder {
+        name: String,
+        fail: bool,
+        /// Take this long to deliver outages, as a slow channel would.
+        delay_down: std::time::Duration,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+            if matches!(event, Event::ServiceDown { .. }) {
+                tokio::time::sleep(self.delay_down).await;
+            }
+            self.received.lock().unwrap().push(format!("{}:{}", self.name, event.name()));
+            if self.fail {
+                anyhow::bail!("channel unavailable");
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_fans_out_to_every_channel() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new();
+        for (name, fail) in [("first", true), ("second", false)] {
+            dispatcher.register(Recorder {
+                name: name.to_string(),
+                fail,
+                delay_down: std::time::Duration::ZERO,
+                received: received.clone(),
+            });
+        }
+
+        dispatcher.dispatch(&service_down(service())).await;
+
+        let mut received = received.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(received, vec!["first:service_down", "second:service_down"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_delivers_in_order() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register(Recorder {
+            name: "slow".to_string(),
+            fail: false,
+            delay_down: std::time::Duration::from_millis(50),
+            received: received.clone(),
+        });
+
+        let (tx, rx) = mpsc::channel(10);
+        let service = service();
+        tx.send(service_down(service.clone())).await.unwrap();
+        tx.send(Event::ServiceRecovered {
+            service,
+            previous: ServiceStatus::Down,
+            latency_ms: Some(12),
+            occurred_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+        drop(tx);
+        dispatcher.run(rx).await;
+
+        assert_eq!(*received.lock().unwrap(), vec!["slow:service_down", "slow:service_recovered"]);
+    }
+
+    fn service() -> ServiceRef {
+        ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() }
+    }
+
+    fn service_down(service: ServiceRef) -> Event {
+        Event::ServiceDown {
+            service,
+            previous: ServiceStatus::Up,
+            message: "connection refused".to_string(),
+            latency_ms: None,
+            occurred_at: Utc::now(),
+        }
+    }
+}
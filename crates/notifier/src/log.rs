@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use tracing::info;
+
+use rstat_core::Event;
+
+use crate::Notifier;
+
+/// LogNotifier writes every event to the application log.
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn accepts(&self, _event: &Event) -> bool {
+        true
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        info!("Notification: {}", event);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use rstat_core::{Event, PagerDutyConfig, WebhookConfig};
+use rstat_incidents::dedup_key;
+
+use crate::webhook::WebhookNotifier;
+use crate::Notifier;
+
+/// PagerDutyNotifier triggers an alert when a service goes DOWN and resolves it when its incident is resolved.
+///
+/// Alerts are keyed by service, so repeated triggers for the same outage are merged by the paging tool.
+pub struct PagerDutyNotifier {
+    config: PagerDutyConfig,
+    dashboard_url: Option<String>,
+    webhook: WebhookNotifier,
+}
+
+impl PagerDutyNotifier {
+    pub fn new(config: PagerDutyConfig, dashboard_url: Option<String>) -> Self {
+        Self::with_client(config, dashboard_url, Client::new())
+    }
+
+    pub fn with_client(config: PagerDutyConfig, dashboard_url: Option<String>, client: Client) -> Self {
+        let webhook = WebhookNotifier::with_client(
+            WebhookConfig {
+                url: config.url.clone(),
+                max_retries: config.max_retries,
+                ..WebhookConfig::default()
+            },
+            client,
+        );
+
+        Self { config, dashboard_url, webhook }
+    }
+
+    /// The Events v2 request for an event, if it maps to an alert action.
+    pub fn payload(&self, event: &Event) -> Option<Value> {
+        let service = event.service();
+        let key = dedup_key(service.id);
+
+        match event {
+            Event::ServiceDown { previous, message, latency_ms, occurred_at, .. } => {
+                let mut payload = json!({
+                    "routing_key": self.config.routing_key,
+                    "event_action": "trigger",
+                    "dedup_key": key,
+                    "payload": {
+                        "summary": event.to_string(),
+                        "source": service.name,
+                        "severity": self.config.severity,
+                        "timestamp": occurred_at.to_rfc3339(),
+                        "component": service.kind,
+                        "custom_details": {
+                            "service_id": service.id,
+                            "message": message,
+                            "previous_status": previous.to_string(),
+                            "latency_ms": latency_ms,
+                        }
+                    }
+                });
+                if let Some(url) = &self.dashboard_url {
+                    payload["links"] = json!([{ "href": url, "text": "rstat dashboard" }]);
+                }
+                Some(payload)
+            }
+            Event::IncidentResolved { .. } => Some(json!({
+                "routing_key": self.config.routing_key,
+                "event_action": "resolve",
+                "dedup_key": key,
+            })),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for PagerDutyNotifier {
+    fn name(&self) -> &str {
+        "pagerduty"
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        matches!(event, Event::ServiceDown { .. } | Event::IncidentResolved { .. })
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        let Some(payload) = self.payload(event) else {
+            return Ok(());
+        };
+        self.webhook.post(event, &serde_json::to_string(&payload)?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use rstat_core::{ServiceRef, ServiceStatus};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_trigger_and_resolve_share_dedup_key() {
+        let notifier = PagerDutyNotifier::new(
+            PagerDutyConfig {
+                routing_key: "R0UT1NG".to_string(),
+                url: "http://127.0.0.1/v2/enqueue".to_string(),
+                severity: "critical".to_string(),
+                max_retries: 0,
+            },
+            None,
+        );
+        let service = ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() };
+
+        let trigger = notifier
+            .payload(&Event::ServiceDown {
+                service: service.clone(),
+                previous: ServiceStatus::Up,
+                message: "connection refused".to_string(),
+                latency_ms: None,
+                occurred_at: Utc::now(),
+            })
+            .unwrap();
+        let resolve = notifier
+            .payload(&Event::IncidentResolved {
+                service: service.clone(),
+                incident_id: Uuid::new_v4(),
+                started_at: Utc::now(),
+                resolved_at: Utc::now(),
+                duration_seconds: 60,
+            })
+            .unwrap();
+
+        assert_eq!(trigger["event_action"], "trigger");
+        assert_eq!(trigger["payload"]["source"], "API");
+        assert_eq!(resolve["event_action"], "resolve");
+        assert_eq!(trigger["dedup_key"], format!("rstat-{}", service.id));
+        assert_eq!(trigger["dedup_key"], resolve["dedup_key"]);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use reqwest::Client;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use rstat_core::Event;
+
+use crate::chat::ChatNotifier;
+use crate::email::{EmailNotifier, Mailer};
+use crate::pagerduty::PagerDutyNotifier;
+use crate::webhook::WebhookNotifier;
+use crate::{deliver, Notifier};
+
+/// ServiceNotifier delivers events to the channels configured on the service itself.
+///
+/// Like the global channels, every service channel delivers from its own queue, so a slow
+/// channel of one service does not hold up the alerts of the others.
+pub struct ServiceNotifier {
+    pool: PgPool,
+    client: Client,
+    mailer: Option<Mailer>,
+    dashboard_url: Option<String>,
+    queues: ChannelQueues,
+}
+
+impl ServiceNotifier {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool, client: Client::new(), mailer: None, dashboard_url: None, queues: ChannelQueues::default() }
+    }
+
+    /// Link chat messages to this dashboard
+    pub fn with_dashboard_url(mut self, dashboard_url: Option<String>) -> Self {
+        self.dashboard_url = dashboard_url;
+        self
+    }
+
+    /// Use this mailer for the email recipients of services
+    pub fn with_mailer(mut self, mailer: Mailer) -> Self {
+        self.mailer = Some(mailer);
+        self
+    }
+}
+
+#[async_trait]
+impl Notifier for ServiceNotifier {
+    fn name(&self) -> &str {
+        "service"
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        // Completed checks would load the service on every check, so service channels only
+        // get state changes. Each channel then filters those on its own.
+        !matches!(event, Event::CheckCompleted { .. })
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        let service = rstat_service::get(&self.pool, event.service().id).await?;
+
+        let notifications = service.options.notifications;
+        let mut errors = Vec::new();
+        let mut channels: Vec<(String, Box<dyn Notifier>)> = Vec::new();
+        for config in notifications.webhooks {
+            let key = channel_key("webhook", &config);
+            channels.push((key, Box::new(WebhookNotifier::with_client(config, self.client.clone()))));
+        }
+        for config in notifications.chat {
+            let key = channel_key("chat", &config);
+            let notifier = ChatNotifier::with_client(config, self.dashboard_url.clone(), self.client.clone());
+            channels.push((key, Box::new(notifier)));
+        }
+        for config in notifications.pagerduty {
+            let key = channel_key("pagerduty", &config);
+            let notifier = PagerDutyNotifier::with_client(config, self.dashboard_url.clone(), self.client.clone());
+            channels.push((key, Box::new(notifier)));
+        }
+        match &self.mailer {
+            Some(mailer) => {
+                for config in notifications.email {
+                    let key = channel_key("email", &config);
+                    channels.push((key, Box::new(EmailNotifier::new(config, mailer.clone()))));
+                }
+            }
+            // The other channels are still notified
+            None if !notifications.email.is_empty() => {
+                errors.push(format!("email: {} has recipients but no SMTP server is configured", service.name));
+            }
+            None => {}
+        }
+
+        // Delivery failures are logged by the queues
+        self.queues.send(service.id, channels, event);
+
+        if !errors.is_empty() {
+            anyhow::bail!(errors.join("; "));
+        }
+        Ok(())
+    }
+}
+
+type Queue = mpsc::UnboundedSender<Arc<Event>>;
+
+/// ChannelQueues keeps a queue and a worker for every channel of every service.
+///
+/// A channel is known by its configuration, so a channel whose configuration changes
+/// gets a new queue. The queue of a channel removed from its service is dropped once the
+/// service is notified again, and its worker stops after delivering what is left.
+#[derive(Default)]
+struct ChannelQueues {
+    queues: Mutex<HashMap<Uuid, HashMap<String, Queue>>>,
+}
+
+impl ChannelQueues {
+    /// Queue the event for the channels of the service that accept it
+    fn send(&self, service_id: Uuid, channels: Vec<(String, Box<dyn Notifier>)>, event: &Event) {
+        let event = Arc::new(event.clone());
+        let mut queues = self.queues.lock().unwrap();
+        let mut previous = queues.remove(&service_id).unwrap_or_default();
+        let mut current = HashMap::with_capacity(channels.len());
+        for (key, channel) in channels {
+            let accepts = channel.accepts(&event);
+            let queue = previous.remove(&key).unwrap_or_else(|| spawn_worker(channel));
+            if accepts {
+                let _ = queue.send(event.clone());
+            }
+            current.insert(key, queue);
+        }
+        queues.insert(service_id, current);
+    }
+}
+
+fn spawn_worker(channel: Box<dyn Notifier>) -> Queue {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Arc<Event>>();
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            deliver(channel.as_ref(), &event).await;
+        }
+    });
+    tx
+}
+
+fn channel_key(kind: &str, config: &impl std::fmt::Debug) -> String {
+    format!("{}:{:?}", kind, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use rstat_core::{ServiceRef, ServiceStatus};
+
+    struct Recorder {
+        name: String,
+        delay: Duration,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+            tokio::time::sleep(self.delay).await;
+            self.received.lock().unwrap().push(format!("{}:{}", self.name, event.name()));
+            Ok(())
+        }
+    }
+
+    fn recorder(name: &str, delay: Duration, received: &Arc<Mutex<Vec<String>>>) -> Vec<(String, Box<dyn Notifier>)> {
+        let recorder = Recorder { name: name.to_string(), delay, received: received.clone() };
+        vec![(name.to_string(), Box::new(recorder))]
+    }
+
+    fn event(status: ServiceStatus) -> Event {
+        let service = ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() };
+        match status {
+            ServiceStatus::Down => Event::ServiceDown {
+                service,
+                previous: ServiceStatus::Up,
+                message: "connection refused".to_string(),
+                latency_ms: None,
+                occurred_at: Utc::now(),
+            },
+            _ => Event::ServiceRecovered { service, previous: ServiceStatus::Down, latency_ms: None, occurred_at: Utc::now() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_channel_does_not_hold_up_other_services() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let queues = ChannelQueues::default();
+        let (slow, fast) = (Uuid::new_v4(), Uuid::new_v4());
+
+        queues.send(slow, recorder("slow", Duration::from_millis(200), &received), &event(ServiceStatus::Down));
+        queues.send(slow, recorder("slow", Duration::from_millis(200), &received), &event(ServiceStatus::Up));
+        queues.send(fast, recorder("fast", Duration::ZERO, &received), &event(ServiceStatus::Down));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*received.lock().unwrap(), vec!["fast:service_down"]);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["fast:service_down", "slow:service_down", "slow:service_recovered"]
+        );
+    }
+}
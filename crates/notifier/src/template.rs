@@ -0,0 +1,138 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use rstat_core::Event;
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z0-9_.]+)\s*\}\}").unwrap());
+
+/// Variables available to body templates for an event.
+pub fn variables(event: &Event) -> Map<String, Value> {
+    let service = event.service();
+    let mut vars = Map::new();
+    vars.insert("event".to_string(), json!(event.name()));
+    vars.insert("summary".to_string(), json!(event.to_string()));
+    vars.insert("occurred_at".to_string(), json!(event.occurred_at().to_rfc3339()));
+    vars.insert("service.id".to_string(), json!(service.id));
+    vars.insert("service.name".to_string(), json!(service.name));
+    vars.insert("service.kind".to_string(), json!(service.kind));
+    vars.insert("status".to_string(), json!(event.status().to_string()));
+    if let Some(latency_ms) = event.latency_ms() {
+        vars.insert("latency_ms".to_string(), json!(latency_ms));
+    }
+
+    match event {
+        Event::CheckCompleted { result, .. } => {
+            vars.insert("message".to_string(), json!(result.message));
+            vars.insert("code".to_string(), json!(result.code));
+            if let Some(error) = result.error {
+                vars.insert("error".to_string(), json!(error));
+            }
+        }
+        Event::ServiceDown { previous, message, .. } => {
+            vars.insert("previous_status".to_string(), json!(previous.to_string()));
+            vars.insert("message".to_string(), json!(message));
+        }
+        Event::ServiceRecovered { previous, .. } => {
+            vars.insert("previous_status".to_string(), json!(previous.to_string()));
+        }
+        Event::ServiceDegraded { previous, message, .. } => {
+            vars.insert("previous_status".to_string(), json!(previous.to_string()));
+            vars.insert("message".to_string(), json!(message));
+        }
+        Event::CertificateExpiring { certificate, .. } => {
+            vars.insert("message".to_string(), json!(event.to_string()));
+            vars.insert("certificate.days_until_expiry".to_string(), json!(certificate.days_until_expiry));
+            vars.insert("certificate.not_after".to_string(), json!(certificate.not_after.to_rfc3339()));
+        }
+        Event::IncidentOpened { incident_id, started_at, .. } => {
+            vars.insert("incident_id".to_string(), json!(incident_id));
+            vars.insert("started_at".to_string(), json!(started_at.to_rfc3339()));
+        }
+        Event::IncidentResolved { incident_id, started_at, resolved_at, duration_seconds, .. } => {
+            vars.insert("incident_id".to_string(), json!(incident_id));
+            vars.insert("started_at".to_string(), json!(started_at.to_rfc3339()));
+            vars.insert("resolved_at".to_string(), json!(resolved_at.to_rfc3339()));
+            vars.insert("duration_seconds".to_string(), json!(duration_seconds));
+        }
+        Event::ErrorBudgetBurn { objective, severity, window, burn_rate, threshold, budget_remaining, .. } => {
+            vars.insert("message".to_string(), json!(event.to_string()));
+            vars.insert("slo.objective".to_string(), json!(objective));
+            vars.insert("slo.severity".to_string(), json!(severity));
+            vars.insert("slo.window".to_string(), json!(window));
+            vars.insert("slo.burn_rate".to_string(), json!(burn_rate));
+            vars.insert("slo.threshold".to_string(), json!(threshold));
+            vars.insert("slo.budget_remaining".to_string(), json!(budget_remaining));
+        }
+    }
+
+    vars
+}
+
+/// Render a JSON template, replacing `{{variable}}` placeholders in string values.
+///
+/// A string made up of a single placeholder is replaced by the typed value, so
+/// `"{{latency_ms}}"` renders as a number. Unknown variables render as null, or
+/// as an empty string inside longer text.
+pub fn render(template: &Value, vars: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(text) => render_string(text, vars),
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, vars)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, vars)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn render_string(text: &str, vars: &Map<String, Value>) -> Value {
+    if let Some(captures) = PLACEHOLDER.captures(text) {
+        if captures.get(0).unwrap().as_str() == text {
+            return vars.get(&captures[1]).cloned().unwrap_or(Value::Null);
+        }
+    }
+
+    let rendered = PLACEHOLDER.replace_all(text, |captures: &regex::Captures| match vars.get(&captures[1]) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    });
+    Value::String(rendered.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_keeps_types_of_single_placeholders() {
+        let mut vars = Map::new();
+        vars.insert("service.name".to_string(), json!("API"));
+        vars.insert("latency_ms".to_string(), json!(42));
+        vars.insert("status".to_string(), json!("DOWN"));
+
+        let template = json!({
+            "text": "{{service.name}} is {{ status }} ({{latency_ms}}ms){{missing}}",
+            "latency": "{{latency_ms}}",
+            "tags": ["rstat", "{{status}}"],
+            "missing": "{{missing}}",
+            "static": true
+        });
+
+        assert_eq!(
+            render(&template, &vars),
+            json!({
+                "text": "API is DOWN (42ms)",
+                "latency": 42,
+                "tags": ["rstat", "DOWN"],
+                "missing": null,
+                "static": true
+            })
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, StatusCode};
+use sha2::Sha256;
+use tracing::info;
+
+use rstat_core::{Event, WebhookConfig};
+
+use crate::{template, Notifier};
+
+pub const SIGNATURE_HEADER: &str = "X-Rstat-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Rstat-Timestamp";
+pub const EVENT_HEADER: &str = "X-Rstat-Event";
+
+/// WebhookNotifier POSTs events as JSON to a configured URL.
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    client: Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self::with_client(config, Client::new())
+    }
+
+    pub fn with_client(config: WebhookConfig, client: Client) -> Self {
+        Self { config, client }
+    }
+
+    /// The request body for an event, rendered from the template if one is set.
+    pub fn body(&self, event: &Event) -> Result<String, anyhow::Error> {
+        let body = match &self.config.template {
+            Some(template) => template::render(template, &template::variables(event)),
+            None => serde_json::to_value(event)?,
+        };
+        Ok(serde_json::to_string(&body)?)
+    }
+
+    /// POST a body for an event, retrying temporary failures with exponential backoff.
+    pub async fn post(&self, event: &Event, body: &str) -> Result<(), anyhow::Error> {
+        let max_retries = self.config.max_retries;
+        let mut attempts: u8 = 0;
+
+        loop {
+            match self.send(event, body).await {
+                Ok(()) => return Ok(()),
+                Err(Attempt::Abort(message)) => anyhow::bail!(message),
+                Err(Attempt::Retry(message)) => {
+                    if attempts >= max_retries {
+                        anyhow::bail!("{} (after {} attempts)", message, attempts + 1);
+                    }
+
+                    let backoff = Duration::from_millis(self.config.backoff_ms.saturating_mul(1 << attempts));
+                    attempts += 1;
+                    info!(
+                        "Retrying webhook {} in {}ms... attempt {}/{}: {}",
+                        self.name(),
+                        backoff.as_millis(),
+                        attempts,
+                        max_retries,
+                        message
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+            }
+        }
+    }
+
+    async fn send(&self, event: &Event, body: &str) -> Result<(), Attempt> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .timeout(Duration::from_secs(self.config.timeout as u64))
+            .header("Content-Type", "application/json")
+            .header(EVENT_HEADER, event.name());
+
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        if let Some(secret) = &self.config.secret {
+            let timestamp = Utc::now().timestamp().to_string();
+            request = request
+                .header(TIMESTAMP_HEADER, &timestamp)
+                .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &timestamp, body)));
+        }
+
+        let response = request
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| Attempt::Retry(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let message = format!("{} responded with {}", self.config.url, status);
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::REQUEST_TIMEOUT {
+            Err(Attempt::Retry(message))
+        } else {
+            Err(Attempt::Abort(message))
+        }
+    }
+}
+
+/// Attempt is the reason a delivery attempt failed.
+enum Attempt {
+    /// The failure may be temporary.
+    Retry(String),
+    /// Trying again will not help.
+    Abort(String),
+}
+
+/// Sign a request body with HMAC-SHA256 over `{timestamp}.{body}`, hex encoded.
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        self.config.name.as_deref().unwrap_or(&self.config.url)
+    }
+
+    fn accepts(&self, event: &Event) -> bool {
+        if self.config.events.is_empty() {
+            return !matches!(event, Event::CheckCompleted { .. });
+        }
+        self.config.events.iter().any(|name| name == event.name())
+    }
+
+    async fn notify(&self, event: &Event) -> Result<(), anyhow::Error> {
+        let body = self.body(event)?;
+        self.post(event, &body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use rstat_core::{ServiceRef, ServiceStatus};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+
+    #[derive(Clone, Default)]
+    struct Received {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+    }
+
+    /// Start a stand-in webhook receiver that fails the first `failures` requests
+    async fn start_receiver(failures: usize) -> (String, Received) {
+        let received = Received::default();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(move |State(received): State<Received>, headers: HeaderMap, body: String| async move {
+                    let mut requests = received.requests.lock().unwrap();
+                    requests.push((headers, body));
+                    if requests.len() <= failures {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    }
+                }),
+            )
+            .with_state(received.clone());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (url, received)
+    }
+
+    fn service_down() -> Event {
+        Event::ServiceDown {
+            service: ServiceRef { id: Uuid::new_v4(), name: "API".to_string(), kind: "HTTP".to_string() },
+            previous: ServiceStatus::Up,
+            message: "connection refused".to_string(),
+            latency_ms: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_templated_delivery_is_retried() {
+        let (url, received) = start_receiver(2).await;
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            url,
+            headers: [("X-Team".to_string(), "ops".to_string())].into(),
+            secret: Some("s3cret".to_string()),
+            template: Some(serde_json::json!({ "text": "{{service.name}} is {{status}}: {{message}}" })),
+            backoff_ms: 10,
+            ..WebhookConfig::default()
+        });
+
+        notifier.notify(&service_down()).await.unwrap();
+
+        let requests = received.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+
+        let (headers, body) = requests.last().unwrap();
+        assert_eq!(body, r#"{"text":"API is DOWN: connection refused"}"#);
+        assert_eq!(headers["x-team"], "ops");
+        assert_eq!(headers["x-rstat-event"], "service_down");
+
+        let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+        let expected = format!("sha256={}", sign("s3cret", timestamp, body));
+        assert_eq!(headers[SIGNATURE_HEADER], expected.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let (url, received) = start_receiver(usize::MAX).await;
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            url,
+            max_retries: 1,
+            backoff_ms: 10,
+            ..WebhookConfig::default()
+        });
+
+        assert!(notifier.notify(&service_down()).await.is_err());
+        assert_eq!(received.requests.lock().unwrap().len(), 2);
+    }
+}
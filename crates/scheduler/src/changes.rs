@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use sqlx::postgres::PgListener;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Channel the services trigger notifies on
+pub const SERVICES_CHANNEL: &str = "rstat_services";
+
+/// Change is a change to the schedule of the services
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// A service was added or rescheduled, or no longer runs when `next_run` is unset.
+    Service { id: Uuid, next_run: Option<DateTime<Utc>> },
+    /// Notifications may have been missed, the schedule has to be reloaded.
+    Resync,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    id: Uuid,
+    next_run: Option<NaiveDateTime>,
+}
+
+impl Change {
+    fn parse(payload: &str) -> Result<Self, serde_json::Error> {
+        let payload: Payload = serde_json::from_str(payload)?;
+        Ok(Change::Service {
+            id: payload.id,
+            next_run: payload.next_run.map(|next_run| next_run.and_utc()),
+        })
+    }
+}
+
+/// Listen for changes to the services and forward them.
+///
+/// Stops once the receiver is dropped. The listener is set up before this returns, so no
+/// change made afterwards is missed.
+pub async fn listen(pool: &sqlx::PgPool) -> Result<mpsc::Receiver<Change>, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(SERVICES_CHANNEL).await?;
+
+    let (tx, rx) = mpsc::channel(1024);
+    tokio::spawn(async move {
+        loop {
+            let change = match listener.try_recv().await {
+                Ok(Some(notification)) => match Change::parse(notification.payload()) {
+                    Ok(change) => change,
+                    Err(err) => {
+                        warn!("Ignoring invalid service notification {}: {}", notification.payload(), err);
+                        continue;
+                    }
+                },
+                Ok(None) => {
+                    warn!("Lost the connection listening for service changes");
+                    Change::Resync
+                }
+                Err(err) => {
+                    error!("Failed to listen for service changes: {}", err);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Change::Resync
+                }
+            };
+
+            if tx.send(change).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_change() {
+        let id = Uuid::new_v4();
+        let change = Change::parse(&format!(r#"{{"id" : "{}", "next_run" : "2025-07-14T10:00:00.123456"}}"#, id)).unwrap();
+        let next_run = DateTime::parse_from_rfc3339("2025-07-14T10:00:00.123456Z").unwrap().to_utc();
+        assert_eq!(change, Change::Service { id, next_run: Some(next_run) });
+
+        let change = Change::parse(&format!(r#"{{"id" : "{}", "next_run" : null}}"#, id)).unwrap();
+        assert_eq!(change, Change::Service { id, next_run: None });
+    }
+}
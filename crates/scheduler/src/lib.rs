@@ -1,33 +1,53 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
 use tokio::task;
-use futures::future::join_all;
 use tracing::{debug, error, info, warn};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
-use rstat_core::{Service, HealthCheckRequest};
-use rstat_service;
-use rstat_healthcheck;
-use rstat_metrics::MetricsCalculator;
+use rstat_core::{
+    CheckOutcome, Event, HealthCheckRequest, HealthCheckResult, Service, ServiceRef, ServiceStatus,
+    StatusTransition,
+};
+use rstat_incidents::{IncidentChange, IncidentManager};
+use rstat_metrics::{Aggregator, Retention};
+use rstat_slo::SloMonitor;
 
+pub mod changes;
+pub mod limits;
+pub mod maintenance;
 pub mod metrics_updater;
+pub mod queue;
+pub mod telemetry;
 
-pub async fn start_scheduler(db: sqlx::PgPool, result_tx: mpsc::Sender<String>) -> Result<(), anyhow::Error> {
-    let scheduler = Scheduler::new(db.clone(), result_tx)
+use changes::Change;
+use limits::{Limits, Slot, Throttle};
+use queue::Queue;
+
+/// How long a claimed service is kept from other instances. Its next run is set once
+/// the check completes; the lease only matters if the instance dies mid-check.
+const CLAIM_LEASE: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Longest the scheduler sleeps when no service is due
+const IDLE_WAIT: Duration = Duration::from_secs(60);
+
+/// Intervals after which a location that stopped reporting no longer counts for the service state
+const LOCATION_STALE_INTERVALS: i32 = 3;
+
+pub async fn start_scheduler(scheduler: Scheduler) -> Result<(), anyhow::Error> {
+    let scheduler = scheduler
         .init()
         .await
         .expect("Failed to initialize scheduler");
     
-    // Start metrics updater
-    let metrics_calculator = MetricsCalculator::new(db.clone());
-    let metrics_updater = metrics_updater::MetricsUpdater::new(
-        metrics_calculator,
-        Duration::from_secs(300), // Update metrics every 5 minutes
-    );
-    metrics_updater.start();
+    // Results are aggregated in memory and merged into the metrics tables periodically
+    scheduler.aggregator.clone().start(Duration::from_secs(10));
+    SloMonitor::new(scheduler.db.clone(), scheduler.event_tx.clone()).start(Duration::from_secs(60));
+    Retention::new(scheduler.db.clone()).start(Duration::from_secs(3600));
     
-    info!("Starting scheduler and metrics updater");
+    info!("Starting scheduler, metrics aggregator, SLO monitor and rollup retention");
     scheduler.start().await;
     
     Ok(())
@@ -36,52 +56,138 @@ pub async fn start_scheduler(db: sqlx::PgPool, result_tx: mpsc::Sender<String>)
 #[derive(Clone)]
 pub struct Scheduler {
     pub db: sqlx::PgPool,
-    pub result_tx: mpsc::Sender<String>,
+    pub event_tx: mpsc::Sender<Event>,
+    pub aggregator: Arc<Aggregator>,
+    pub limits: Limits,
+    throttle: Throttle,
 }
 
 impl Scheduler {
-    pub fn new(db: sqlx::PgPool, result_tx: mpsc::Sender<String>) -> Self {
+    pub fn new(db: sqlx::PgPool, event_tx: mpsc::Sender<Event>, aggregator: Arc<Aggregator>) -> Self {
+        let limits = Limits::default();
         Self {
             db,
-            result_tx,
+            event_tx,
+            aggregator,
+            throttle: Throttle::new(&limits),
+            limits,
         }
     }
 
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.throttle = Throttle::new(&limits);
+        self.limits = limits;
+        self
+    }
+
     pub async fn init(self) -> Result<Self, anyhow::Error> {
+        telemetry::describe();
         info!("Scheduler initialized");
         Ok(self)
     }
 
-    async fn get_due_services(&self) -> Result<Vec<Service>, anyhow::Error> {
-        let services = rstat_service::all(&self.db).await?;
+    /// Claim the due services for a check. Services claimed by another instance are left out.
+    async fn claim(&self, due: Vec<(Uuid, DateTime<Utc>)>) -> Result<Vec<Service>, anyhow::Error> {
         let now = Utc::now();
-        
-        let due_services: Vec<Service> = services
-            .into_iter()
-            .filter(|service| service.next_run <= now)
-            .collect();
-        
-        Ok(due_services)
+        let lag = due
+            .iter()
+            .map(|(_, next_run)| (now - *next_run).num_milliseconds())
+            .max()
+            .unwrap_or(0);
+
+        let ids: Vec<Uuid> = due.into_iter().map(|(id, _)| id).collect();
+        let claimed = rstat_service::claim(&self.db, &ids, now, now + CLAIM_LEASE).await?;
+        telemetry::record_tick(claimed.len(), lag as f64 / 1000.0);
+
+        Ok(claimed)
     }
 
-    async fn run_healthcheck(&self, service: &Service) {
+    /// Load the schedule of every service checked by the scheduler
+    async fn load_queue(&self) -> Result<Queue, anyhow::Error> {
+        let queue = Queue::new(rstat_service::db::next_runs(&self.db).await?);
+        info!("Scheduled {} services", queue.len());
+        Ok(queue)
+    }
+
+    async fn run_healthcheck(&self, service: &Service, slot: Slot) {
+        let _permit = self.throttle.acquire(slot, service.kind.host().as_deref()).await;
         info!("Running healthcheck for service: {}", service.name);
         
         let request = HealthCheckRequest::new(service.clone());
         let healthcheck = rstat_healthcheck::perform_check(request).await;
+        self.process(service, healthcheck.map_err(|err| err.to_string())).await;
+
+        // Update the next_run timestamp
+        let next_run = self.next_run(service, Utc::now());
+        if let Err(err) = rstat_service::db::update_next_run(&self.db, service.id, next_run).await {
+            error!("Failed to update next_run for service {}: {}", service.name, err);
+        }
+    }
+
+    /// When to check a service next after a check at `at`.
+    ///
+    /// Intervals are jittered so services don't stay in lockstep, cron schedules run on time.
+    fn next_run(&self, service: &Service, at: DateTime<Utc>) -> DateTime<Utc> {
+        let scheduled = match &service.options.schedule {
+            Some(schedule) if schedule.cron.is_some() => schedule.next_run(at, service.interval),
+            Some(schedule) => schedule.next_run(at + self.limits.jitter(service.interval), service.interval),
+            None => return self.limits.next_run(at, service.interval),
+        };
+
+        // Schedules that never match are rejected when the service is created
+        scheduled.unwrap_or_else(|| {
+            warn!("The schedule of service {} never matches, checking it on its interval", service.name);
+            self.limits.next_run(at, service.interval)
+        })
+    }
+
+    /// Store the outcome of a check performed by the scheduler and act on it.
+    ///
+    /// An error means the check could not be performed at all. During a maintenance window
+    /// results are flagged and the service keeps its state, so no alerts go out.
+    pub async fn process(&self, service: &Service, healthcheck: Result<HealthCheckResult, String>) {
+        self.handle(service, None, healthcheck).await
+    }
+
+    /// Store the outcome of a check performed by an agent at `location` and act on it, see `process`.
+    ///
+    /// The outcome advances the state of the location, which the service state follows.
+    pub async fn process_from(&self, service: &Service, location: &str, healthcheck: Result<HealthCheckResult, String>) {
+        self.handle(service, Some(location), healthcheck).await
+    }
 
-        match healthcheck {
+    async fn handle(&self, service: &Service, location: Option<&str>, mut healthcheck: Result<HealthCheckResult, String>) {
+        let outcome = match &healthcheck {
+            Ok(result) => CheckOutcome::from(result),
+            Err(_) => CheckOutcome::Failed,
+        };
+        telemetry::record_check(service, healthcheck.as_ref().ok());
+        let mut result_id = None;
+
+        let checked_at = maintenance::checked_at(&healthcheck, Utc::now());
+        let window = match rstat_maintenance::active(&self.db, service, checked_at).await {
+            Ok(window) => window,
+            Err(err) => {
+                error!("Failed to get the maintenance windows of service {}: {}", service.name, err);
+                None
+            }
+        };
+        let advance = maintenance::flag(&mut healthcheck, window.as_ref());
+
+        let result = match healthcheck {
             Ok(result) => {
                 debug!("Healthcheck successful for service: {}", service.name);
                 match rstat_healthcheck::db::create(
                     &self.db,
-                    result,
+                    result.clone(),
                     service.id,
                 )
                 .await
                 {
                     Ok(id) => {
-                        info!("Healthcheck result created with id: {}", id)
+                        info!("Healthcheck result created with id: {}", id);
+                        result_id = Some(id);
+                        self.aggregator.record(service, &result);
                     }
                     Err(err) => error!(
                         "Cannot save healthcheck result to db for service {} with err: {}",
@@ -89,60 +195,246 @@ impl Scheduler {
                     ),
                 }
                 
-                // Update metrics for this service after successful health check
-                let metrics_calculator = MetricsCalculator::new(self.db.clone());
-                if let Err(e) = metrics_calculator.calculate_today_metrics(service.id).await {
-                    warn!("Failed to update metrics for service {}: {}", service.name, e);
-                }
-                
-                let _ = self.result_tx.send(format!("Healthcheck completed for {}", service.name)).await;
+                self.emit(Event::CheckCompleted {
+                    service: ServiceRef::from(service),
+                    result: result.clone(),
+                })
+                .await;
+                Ok(result)
             }
             Err(err) => {
                 error!("Healthcheck failed for service {} with error: {}", service.name, err);
-                
-                // Still update metrics even if health check failed
-                let metrics_calculator = MetricsCalculator::new(self.db.clone());
-                if let Err(e) = metrics_calculator.calculate_today_metrics(service.id).await {
-                    warn!("Failed to update metrics for service {}: {}", service.name, e);
+                Err(err)
+            }
+        };
+
+        if advance {
+            self.update_state(service, location, outcome, &result, result_id).await;
+        } else if let Some(window) = window {
+            debug!("Service {} is under maintenance ({}), keeping its state", service.name, window.settings.name);
+        }
+    }
+
+    /// Advance the service state machine with the latest outcome and persist it
+    async fn update_state(
+        &self,
+        service: &Service,
+        location: Option<&str>,
+        outcome: CheckOutcome,
+        result: &Result<HealthCheckResult, String>,
+        result_id: Option<uuid::Uuid>,
+    ) {
+        let (state, transition) = match location {
+            None => {
+                let mut state = service.state.clone();
+                let transition = state.apply(outcome, &service.options);
+                match rstat_service::db::update_state(&self.db, service.id, &state).await {
+                    Ok(true) => (state, transition),
+                    Ok(false) => {
+                        debug!("Service {} was paused or deleted during its check, ignoring the result", service.name);
+                        return;
+                    }
+                    Err(err) => {
+                        error!("Failed to update state for service {}: {}", service.name, err);
+                        return;
+                    }
                 }
             }
+            Some(location) => {
+                let stale_after = chrono::Duration::from_std(service.interval).unwrap_or_default() * LOCATION_STALE_INTERVALS;
+                let applied = rstat_service::db::apply_location_outcome(
+                    &self.db,
+                    service.id,
+                    location,
+                    outcome,
+                    &service.options,
+                    stale_after,
+                )
+                .await;
+                match applied {
+                    Ok(Some(applied)) => applied,
+                    Ok(None) => {
+                        debug!("Service {} was paused or deleted, ignoring the result from {}", service.name, location);
+                        return;
+                    }
+                    Err(err) => {
+                        error!("Failed to update state for service {} from {}: {}", service.name, location, err);
+                        return;
+                    }
+                }
+            }
+        };
+
+        if let Some(transition) = transition {
+            info!("Service {} changed from {} to {}", service.name, transition.from, transition.to);
+            if let Some(event) = transition_event(service, transition, result) {
+                self.emit(event).await;
+            }
         }
 
-        // Update the next_run timestamp
-        let next_run = Utc::now() + chrono::Duration::from_std(service.interval).unwrap();
-        if let Err(err) = rstat_service::db::update_next_run(&self.db, service.id, next_run).await {
-            error!("Failed to update next_run for service {}: {}", service.name, err);
+        let incidents = IncidentManager::new(self.db.clone());
+        match incidents.record(service.id, location, &state, transition, outcome, result_id).await {
+            Ok(Some(IncidentChange::Opened(incident))) => {
+                self.emit(Event::IncidentOpened {
+                    service: ServiceRef::from(service),
+                    incident_id: incident.id,
+                    started_at: incident.started_at,
+                })
+                .await;
+            }
+            Ok(Some(IncidentChange::Resolved(incident))) => {
+                self.emit(Event::IncidentResolved {
+                    service: ServiceRef::from(service),
+                    incident_id: incident.id,
+                    started_at: incident.started_at,
+                    resolved_at: incident.resolved_at.unwrap_or_else(Utc::now),
+                    duration_seconds: incident.duration_seconds.unwrap_or_default(),
+                })
+                .await;
+            }
+            Ok(None) => {}
+            Err(err) => error!("Failed to update incidents for service {}: {}", service.name, err),
+        }
+    }
+
+    async fn emit(&self, event: Event) {
+        if let Err(err) = self.event_tx.send(event).await {
+            warn!("Failed to send event: {}", err);
         }
     }
 
     pub async fn start(&self) {
-        info!("Starting scheduler with periodic approach");
-        
+        info!("Starting scheduler with a queue kept in sync through service notifications");
+
         loop {
-            match self.get_due_services().await {
-                Ok(due_services) => {
-                    if !due_services.is_empty() {
-                        info!("Found {} services due for healthcheck", due_services.len());
-                        
-                        let tasks: Vec<_> = due_services.into_iter().map(|service| {
-                            let scheduler = self.clone();
-                            task::spawn(async move {
-                                scheduler.run_healthcheck(&service).await;
-                            })
-                        }).collect();
-
-                        join_all(tasks).await;
-                    } else {
-                        debug!("No services due for healthcheck");
+            if let Err(err) = self.run().await {
+                error!("Scheduler failed, restarting: {}", err);
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Load the schedule once, then keep it in sync with the changes to the services and
+    /// start each check when it is due.
+    ///
+    /// Rescheduling a service, including claiming it or completing its check on any
+    /// instance, notifies every scheduler, so the database is only read when a check is due.
+    async fn run(&self) -> Result<(), anyhow::Error> {
+        // Listen before loading, so no change made in between is missed
+        let mut changes = changes::listen(&self.db).await?;
+        let mut queue = self.load_queue().await?;
+
+        loop {
+            let wait = queue
+                .next_run()
+                .map(|next_run| (next_run - Utc::now()).to_std().unwrap_or_default())
+                .unwrap_or(IDLE_WAIT)
+                .min(IDLE_WAIT);
+
+            tokio::select! {
+                change = changes.recv() => match change {
+                    Some(Change::Service { id, next_run: Some(next_run) }) => queue.schedule(id, next_run),
+                    Some(Change::Service { id, next_run: None }) => queue.remove(id),
+                    Some(Change::Resync) => queue = self.load_queue().await?,
+                    None => return Err(anyhow::anyhow!("Stopped listening for service changes")),
+                },
+                _ = tokio::time::sleep(wait) => {
+                    let mut due = queue.pop_due(Utc::now());
+                    if due.is_empty() {
+                        continue;
+                    }
+
+                    // Only claim as many services as can start checking, the others stay due
+                    let slots = self.throttle.reserve(due.len()).await;
+                    for (id, next_run) in due.split_off(slots.len()) {
+                        queue.schedule(id, next_run);
+                    }
+
+                    // Claiming reschedules the services, their next run comes back as a change
+                    let services = match self.claim(due.clone()).await {
+                        Ok(services) => services,
+                        Err(err) => {
+                            error!("Failed to claim due services: {}", err);
+                            let retry = Utc::now() + chrono::Duration::seconds(1);
+                            for (id, _) in due {
+                                queue.schedule(id, retry);
+                            }
+                            continue;
+                        }
+                    };
+                    // Services claimed elsewhere come back through the change of their next run. In
+                    // case none comes, e.g. as the service was rescheduled before it was claimed,
+                    // they are tried again once a lease taken on them would have run out
+                    let retry = Utc::now() + CLAIM_LEASE;
+                    for (id, _) in due.iter().filter(|(id, _)| !services.iter().any(|service| service.id == *id)) {
+                        queue.schedule(*id, retry);
+                    }
+                    if services.is_empty() {
+                        debug!("Due services were claimed by another instance");
+                        continue;
+                    }
+
+                    info!("Found {} services due for healthcheck", services.len());
+                    // Each check runs on its own in a reserved slot, waiting for its host within the limits
+                    for (service, slot) in services.into_iter().zip(slots) {
+                        let scheduler = self.clone();
+                        task::spawn(async move {
+                            scheduler.run_healthcheck(&service, slot).await;
+                        });
                     }
-                }
-                Err(err) => {
-                    error!("Failed to get due services: {}", err);
                 }
             }
-            
-            // Wait 2 seconds before next check
-            tokio::time::sleep(Duration::from_secs(2)).await;
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Build the event announcing a status change, if the change is worth announcing.
+///
+/// A service leaving UNKNOWN for UP is not a recovery, and a degraded TLS check
+/// is reported as an expiring certificate.
+fn transition_event(
+    service: &Service,
+    transition: StatusTransition,
+    result: &Result<HealthCheckResult, String>,
+) -> Option<Event> {
+    let service_ref = ServiceRef::from(service);
+    let occurred_at = Utc::now();
+    let message = match result {
+        Ok(result) => result.message.clone(),
+        Err(err) => err.clone(),
+    };
+    let latency_ms = result.as_ref().ok().map(|result| (result.response_time / 1000) as u64);
+
+    match transition.to {
+        ServiceStatus::Down => Some(Event::ServiceDown {
+            service: service_ref,
+            previous: transition.from,
+            message,
+            latency_ms,
+            occurred_at,
+        }),
+        ServiceStatus::Up if matches!(transition.from, ServiceStatus::Down | ServiceStatus::Degraded) => {
+            Some(Event::ServiceRecovered {
+                service: service_ref,
+                previous: transition.from,
+                latency_ms,
+                occurred_at,
+            })
+        }
+        ServiceStatus::Degraded => match result {
+            Ok(HealthCheckResult { certificate: Some(certificate), .. }) => Some(Event::CertificateExpiring {
+                service: service_ref,
+                certificate: certificate.clone(),
+                occurred_at,
+            }),
+            _ => Some(Event::ServiceDegraded {
+                service: service_ref,
+                previous: transition.from,
+                message,
+                latency_ms,
+                occurred_at,
+            }),
+        },
+        _ => None,
+    }
+}
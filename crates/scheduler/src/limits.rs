@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Largest jitter, so a next run is never moved by most of the interval
+pub const MAX_JITTER: f64 = 0.5;
+
+/// Limits spread the load the scheduler puts on the services it checks
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// Checks running at once, across all services.
+    pub max_concurrent_checks: usize,
+    /// Checks running at once against a single host.
+    pub max_checks_per_host: usize,
+    /// Fraction of the interval a next run is randomly moved by, earlier or later, up to `MAX_JITTER`.
+    pub jitter: f64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_checks: 100,
+            max_checks_per_host: 10,
+            jitter: 0.1,
+        }
+    }
+}
+
+impl Limits {
+    /// The time of a next run `interval` after `at`, moved by a random jitter
+    pub fn next_run(&self, at: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+        at + chrono::Duration::from_std(interval).unwrap_or_default() + self.jitter(interval)
+    }
+
+    /// A random offset of up to `jitter` times the interval, earlier or later
+    pub fn jitter(&self, interval: Duration) -> chrono::Duration {
+        let max_jitter_ms = (interval.as_millis() as f64 * self.jitter.clamp(0.0, MAX_JITTER)) as i64;
+        let jitter_ms = match max_jitter_ms {
+            0 => 0,
+            max => rand::thread_rng().gen_range(-max..=max),
+        };
+        chrono::Duration::milliseconds(jitter_ms)
+    }
+}
+
+/// Throttle hands out permits to run checks within the limits
+#[derive(Clone)]
+pub struct Throttle {
+    checks: Arc<Semaphore>,
+    max_checks_per_host: usize,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+/// Slot of the global limit reserved for a check, released when dropped
+pub struct Slot {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Permit to run a check, released when dropped
+pub struct Permit {
+    _host: Option<OwnedSemaphorePermit>,
+    _check: Slot,
+}
+
+impl Throttle {
+    pub fn new(limits: &Limits) -> Self {
+        Self {
+            checks: Arc::new(Semaphore::new(limits.max_concurrent_checks.max(1))),
+            max_checks_per_host: limits.max_checks_per_host.max(1),
+            hosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Reserve slots for up to `count` checks, waiting until at least one is free.
+    ///
+    /// Slots are reserved before services are claimed, so only as many services are claimed
+    /// as can start right away and none waits for a slot while its claim lease runs out.
+    pub async fn reserve(&self, count: usize) -> Vec<Slot> {
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let first = self.checks.clone().acquire_owned().await.expect("The check semaphore is never closed");
+        let mut slots = vec![Slot { _permit: first }];
+        while slots.len() < count {
+            match self.checks.clone().try_acquire_owned() {
+                Ok(permit) => slots.push(Slot { _permit: permit }),
+                Err(_) => break,
+            }
+        }
+        slots
+    }
+
+    /// Wait until a check against `host` may run in a reserved slot
+    pub async fn acquire(&self, slot: Slot, host: Option<&str>) -> Permit {
+        let host = match host {
+            Some(host) => Some(self.host(host).acquire_owned().await.expect("Host semaphores are never closed")),
+            None => None,
+        };
+        Permit { _host: host, _check: slot }
+    }
+
+    fn host(&self, host: &str) -> Arc<Semaphore> {
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_checks_per_host)))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limits() {
+        let limits = Limits { max_concurrent_checks: 3, max_checks_per_host: 2, jitter: 0.1 };
+
+        let now = Utc::now();
+        for _ in 0..100 {
+            let next_run = limits.next_run(now, Duration::from_secs(60));
+            assert!(next_run >= now + chrono::Duration::seconds(54));
+            assert!(next_run <= now + chrono::Duration::seconds(66));
+        }
+
+        // Jitter never moves a run by most of the interval
+        let wild = Limits { jitter: 5.0, ..limits.clone() };
+        for _ in 0..100 {
+            assert!(wild.jitter(Duration::from_secs(60)).num_seconds().abs() <= 30);
+        }
+
+        let throttle = Throttle::new(&limits);
+        let mut slots = throttle.reserve(2).await;
+        assert_eq!(slots.len(), 2);
+        let _a = throttle.acquire(slots.pop().unwrap(), Some("a.example.com")).await;
+        let _b = throttle.acquire(slots.pop().unwrap(), Some("a.example.com")).await;
+        assert_eq!(throttle.host("a.example.com").available_permits(), 0);
+
+        // Only the free slots are reserved
+        let mut slots = throttle.reserve(5).await;
+        assert_eq!(slots.len(), 1);
+        assert_eq!(throttle.checks.available_permits(), 0);
+
+        // Other hosts only wait on the global limit
+        let c = throttle.acquire(slots.pop().unwrap(), Some("b.example.com")).await;
+        drop(c);
+        assert_eq!(throttle.checks.available_permits(), 1);
+        assert!(throttle.reserve(0).await.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+
+use rstat_core::HealthCheckResult;
+use rstat_maintenance::MaintenanceWindow;
+
+/// When a check was performed: when its result was taken, which for agents may be well
+/// before it is pushed, or `now` for checks that could not be performed at all
+pub fn checked_at(healthcheck: &Result<HealthCheckResult, String>, now: DateTime<Utc>) -> DateTime<Utc> {
+    match healthcheck {
+        Ok(result) => result.created_at,
+        Err(_) => now,
+    }
+}
+
+/// Flag the result of a check performed during a maintenance window.
+///
+/// Returns whether the outcome advances the service state. During maintenance the
+/// service keeps its state, so no alerts go out and no incidents are opened.
+pub fn flag(healthcheck: &mut Result<HealthCheckResult, String>, window: Option<&MaintenanceWindow>) -> bool {
+    if let Ok(result) = healthcheck {
+        result.maintenance = window.is_some();
+    }
+    window.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn result(created_at: DateTime<Utc>) -> HealthCheckResult {
+        HealthCheckResult {
+            id: Uuid::new_v4(),
+            success: false,
+            degraded: false,
+            response_time: 0,
+            code: 503,
+            message: "Service Unavailable".to_string(),
+            error: None,
+            certificate: None,
+            timings: None,
+            location: Some("eu-west".to_string()),
+            maintenance: false,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_checks_during_maintenance_keep_the_state() {
+        let window: MaintenanceWindow = serde_json::from_str(
+            r#"{"id": "6f1c2f0e-6d3a-4c59-9a39-2f5d0f8f6a11", "name": "Deploy", "starts_at": "2025-07-16T20:00:00Z", "ends_at": "2025-07-16T21:00:00Z", "created_at": "2025-07-16T12:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let mut failed = Ok(result(Utc::now()));
+        assert!(!flag(&mut failed, Some(&window)));
+        assert!(failed.unwrap().maintenance);
+
+        let mut unreachable = Err("connection refused".to_string());
+        assert!(!flag(&mut unreachable, Some(&window)));
+
+        let mut outside = Ok(result(Utc::now()));
+        assert!(flag(&mut outside, None));
+        assert!(!outside.unwrap().maintenance);
+    }
+
+    #[test]
+    fn test_checked_at_is_when_the_result_was_taken() {
+        let now = Utc::now();
+        let pushed_late = result(now - chrono::Duration::minutes(10));
+        assert_eq!(checked_at(&Ok(pushed_late.clone()), now), pushed_late.created_at);
+        assert_eq!(checked_at(&Err("timeout".to_string()), now), now);
+    }
+}
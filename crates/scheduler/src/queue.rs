@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Queue orders services by their next run.
+///
+/// Rescheduling a service does not touch the heap entry it already has: entries that no
+/// longer match the service's next run are skipped when they reach the top.
+#[derive(Default)]
+pub struct Queue {
+    heap: BinaryHeap<Reverse<(DateTime<Utc>, Uuid)>>,
+    next_runs: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl Queue {
+    pub fn new(next_runs: impl IntoIterator<Item = (Uuid, DateTime<Utc>)>) -> Self {
+        let mut queue = Self::default();
+        for (id, next_run) in next_runs {
+            queue.schedule(id, next_run);
+        }
+        queue
+    }
+
+    /// Run a service at `next_run`, replacing its previous run
+    pub fn schedule(&mut self, id: Uuid, next_run: DateTime<Utc>) {
+        if self.next_runs.insert(id, next_run) != Some(next_run) {
+            self.heap.push(Reverse((next_run, id)));
+        }
+    }
+
+    pub fn remove(&mut self, id: Uuid) {
+        self.next_runs.remove(&id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.next_runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next_runs.is_empty()
+    }
+
+    /// When the earliest service runs
+    pub fn next_run(&mut self) -> Option<DateTime<Utc>> {
+        while let Some(Reverse((next_run, id))) = self.heap.peek() {
+            if self.next_runs.get(id) == Some(next_run) {
+                return Some(*next_run);
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    /// Take the services due at `now` out of the queue, earliest first
+    pub fn pop_due(&mut self, now: DateTime<Utc>) -> Vec<(Uuid, DateTime<Utc>)> {
+        let mut due = Vec::new();
+        while let Some(next_run) = self.next_run().filter(|next_run| *next_run <= now) {
+            let Reverse((_, id)) = self.heap.pop().unwrap();
+            self.next_runs.remove(&id);
+            due.push((id, next_run));
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_queue_pops_due_services_in_order() {
+        let now = Utc::now();
+        let (a, b, c, d) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut queue = Queue::new([
+            (a, now - Duration::seconds(5)),
+            (b, now - Duration::seconds(10)),
+            (c, now + Duration::seconds(10)),
+            (d, now - Duration::seconds(1)),
+        ]);
+
+        // Rescheduled services only run at their latest next run
+        queue.schedule(a, now + Duration::seconds(20));
+        queue.schedule(c, now - Duration::seconds(2));
+        queue.remove(d);
+
+        let due: Vec<Uuid> = queue.pop_due(now).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(due, vec![b, c]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.next_run(), Some(now + Duration::seconds(20)));
+        assert!(queue.pop_due(now).is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+
+use rstat_core::{ErrorKind, HealthCheckResult, Service};
+
+pub const SERVICE_UP: &str = "rstat_service_up";
+pub const SERVICE_LATENCY: &str = "rstat_service_latency_seconds";
+pub const SERVICE_STATUS_CODE: &str = "rstat_service_status_code";
+pub const CHECKS_TOTAL: &str = "rstat_checks_total";
+pub const CHECKS_FAILED: &str = "rstat_checks_failed_total";
+pub const CHECK_ERRORS: &str = "rstat_check_errors_total";
+pub const CHECK_DURATION: &str = "rstat_check_duration_seconds";
+pub const DUE_SERVICES: &str = "rstat_scheduler_due_services";
+pub const SCHEDULER_LAG: &str = "rstat_scheduler_lag_seconds";
+
+/// Buckets of the check duration histogram, in seconds
+pub const CHECK_DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Register descriptions of the metrics published by the scheduler
+pub fn describe() {
+    describe_gauge!(SERVICE_UP, "Whether the last check of the service succeeded");
+    describe_gauge!(SERVICE_LATENCY, Unit::Seconds, "Response time of the last check");
+    describe_gauge!(SERVICE_STATUS_CODE, "Status code returned by the last check");
+    describe_counter!(CHECKS_TOTAL, "Number of checks performed");
+    describe_counter!(CHECKS_FAILED, "Number of failed checks");
+    describe_counter!(CHECK_ERRORS, "Number of failed checks by kind of failure");
+    describe_histogram!(CHECK_DURATION, Unit::Seconds, "Response time of checks");
+    describe_gauge!(DUE_SERVICES, "Number of services due for a check on the last scheduler tick");
+    describe_gauge!(SCHEDULER_LAG, Unit::Seconds, "How late the most overdue check was started");
+}
+
+/// Record the result of a check. A missing result means the check could not be performed.
+pub fn record_check(service: &Service, result: Option<&HealthCheckResult>) {
+    let labels = [
+        ("service", service.name.clone()),
+        ("service_id", service.id.to_string()),
+        ("kind", service.kind.to_string()),
+    ];
+
+    let success = result.is_some_and(|r| r.success);
+    counter!(CHECKS_TOTAL, &labels).increment(1);
+    if !success {
+        counter!(CHECKS_FAILED, &labels).increment(1);
+
+        let error = result.and_then(|r| r.error).unwrap_or(ErrorKind::Other);
+        let [service, service_id, kind] = labels.clone();
+        let error_labels = [service, service_id, kind, ("error", error.to_string())];
+        counter!(CHECK_ERRORS, &error_labels).increment(1);
+    }
+    gauge!(SERVICE_UP, &labels).set(if success { 1.0 } else { 0.0 });
+    gauge!(SERVICE_STATUS_CODE, &labels).set(result.map(|r| r.code as f64).unwrap_or(0.0));
+
+    if let Some(result) = result {
+        let seconds = result.response_time as f64 / 1_000_000.0;
+        gauge!(SERVICE_LATENCY, &labels).set(seconds);
+        histogram!(CHECK_DURATION, &labels).record(seconds);
+    }
+}
+
+/// Record a scheduler tick
+pub fn record_tick(due_services: usize, lag_seconds: f64) {
+    gauge!(DUE_SERVICES).set(due_services as f64);
+    gauge!(SCHEDULER_LAG).set(lag_seconds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+    use rstat_core::{Kind, ServiceOptions, ServiceState, TcpChecker};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_record_check() {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(CHECK_DURATION.to_string()), CHECK_DURATION_BUCKETS)
+            .unwrap()
+            .build_recorder();
+        let handle = recorder.handle();
+
+        let service = Service {
+            id: Uuid::new_v4(),
+            name: "Database".to_string(),
+            kind: Kind::TCP(TcpChecker::default()),
+            interval: Duration::from_secs(60),
+            next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
+        };
+        let result = HealthCheckResult {
+            id: Uuid::new_v4(),
+            success: true,
+            degraded: false,
+            code: 200,
+            response_time: 20_000,
+            message: String::new(),
+            error: None,
+            certificate: None,
+            timings: None,
+            location: None,
+            maintenance: false,
+            created_at: Utc::now(),
+        };
+
+        metrics::with_local_recorder(&recorder, || {
+            record_check(&service, Some(&result));
+            record_check(&service, None);
+            record_tick(3, 1.5);
+        });
+
+        let output = handle.render();
+        let labels = format!(r#"service="Database",service_id="{}",kind="TCP""#, service.id);
+        assert!(output.contains(&format!("rstat_checks_total{{{}}} 2", labels)));
+        assert!(output.contains(&format!("rstat_checks_failed_total{{{}}} 1", labels)));
+        assert!(output.contains(&format!("rstat_check_errors_total{{{},error=\"other\"}} 1", labels)));
+        assert!(output.contains(&format!("rstat_service_up{{{}}} 0", labels)));
+        assert!(output.contains(&format!("rstat_check_duration_seconds_bucket{{{},le=\"0.025\"}} 1", labels)));
+        assert!(output.contains("rstat_scheduler_due_services 3"));
+    }
+}
@@ -3,7 +3,7 @@ use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use uuid::Uuid;
 use rand::Rng;
 
-use rstat_core::{Service, HealthCheckResult, Kind, HttpChecker, TcpChecker};
+use rstat_core::{Service, HealthCheckResult, Kind, HttpChecker, TcpChecker, ServiceOptions, ServiceState};
 
 pub fn generate_services() -> Vec<Service> {
     vec![
@@ -17,9 +17,13 @@ pub fn generate_services() -> Vec<Service> {
                 body: None,
                 timeout: 5,
                 max_retries: 3,
+                assertions: vec![],
+                allow_insecure_redirects: false,
             }),
             interval: Duration::from_secs(30),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -32,6 +36,8 @@ pub fn generate_services() -> Vec<Service> {
             }),
             interval: Duration::from_secs(60),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -43,9 +49,13 @@ pub fn generate_services() -> Vec<Service> {
                 body: None,
                 timeout: 5,
                 max_retries: 3,
+                assertions: vec![],
+                allow_insecure_redirects: false,
             }),
             interval: Duration::from_secs(45),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -57,9 +67,13 @@ pub fn generate_services() -> Vec<Service> {
                 body: None,
                 timeout: 10,
                 max_retries: 2,
+                assertions: vec![],
+                allow_insecure_redirects: false,
             }),
             interval: Duration::from_secs(90),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -71,9 +85,13 @@ pub fn generate_services() -> Vec<Service> {
                 body: None,
                 timeout: 5,
                 max_retries: 3,
+                assertions: vec![],
+                allow_insecure_redirects: false,
             }),
             interval: Duration::from_secs(120),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -86,6 +104,8 @@ pub fn generate_services() -> Vec<Service> {
             }),
             interval: Duration::from_secs(30),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -98,6 +118,8 @@ pub fn generate_services() -> Vec<Service> {
             }),
             interval: Duration::from_secs(30),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -109,9 +131,13 @@ pub fn generate_services() -> Vec<Service> {
                 body: None,
                 timeout: 3,
                 max_retries: 1,
+                assertions: vec![],
+                allow_insecure_redirects: false,
             }),
             interval: Duration::from_secs(15),
             next_run: Utc::now(),
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
     ]
 }
@@ -196,20 +222,27 @@ pub fn generate_health_check_results(
             results.push(HealthCheckResult {
                 id: Uuid::new_v4(),
                 success,
+                degraded: false,
                 code: code as u64,
                 response_time: response_time as u128,
                 message,
+                error: None,
+                certificate: None,
+                timings: None,
+                location: None,
+                maintenance: false,
                 created_at: check_time,
             });
         }
         
-        current_time = current_time + ChronoDuration::days(1);
+        current_time += ChronoDuration::days(1);
     }
     
     results
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     
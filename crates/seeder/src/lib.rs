@@ -129,7 +129,7 @@ impl Seeder {
                 let mut current_date = start_date;
                 while current_date <= end_date {
                     let _ = calculator.calculate_daily_metrics(service.id, current_date).await;
-                    current_date = current_date + Duration::days(1);
+                    current_date += Duration::days(1);
                 }
             })
         });
@@ -1,19 +1,35 @@
-use std::{env, path::Path};
+use std::{env, fmt::Display, path::Path, str::FromStr, sync::Arc};
 
 use clap::Parser;
 use sqlx::migrate::Migrator;
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{info, warn};
 
 use tracing_subscriber::EnvFilter;
 
-use rstat_cli::{Cli, Commands, MetricsCommands, ConfigCommands};
+use rstat_cli::{AgentCommands, Cli, Commands, MaintenanceCommands, MetricsCommands, ConfigCommands, ServiceCommands};
 use rstat_api::{create_server, AppState};
-use rstat_scheduler;
 use rstat_seeder::Seeder;
-use rstat_metrics::MetricsCalculator;
+use rstat_metrics::{Aggregator, MetricsCalculator};
 use rstat_scheduler::metrics_updater::MetricsUpdater;
+use rstat_scheduler::limits::{Limits, MAX_JITTER};
+use rstat_scheduler::Scheduler;
+use rstat_scheduler::telemetry;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
 use rstat_config::ConfigLoader;
+use rstat_core::Event;
+use rstat_core::NotificationSettings;
+use rstat_maintenance::{MaintenanceSettings, Recurrence};
+use rstat_notifier::{
+    chat::ChatNotifier,
+    digest::Digest,
+    email::{EmailNotifier, Mailer},
+    log::LogNotifier,
+    pagerduty::PagerDutyNotifier,
+    service::ServiceNotifier,
+    webhook::WebhookNotifier,
+    Dispatcher,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -34,6 +50,10 @@ async fn main() -> Result<(), anyhow::Error> {
         Commands::Start => start().await?,
         Commands::Config { command } => handle_config_command(command).await?,
         Commands::Metrics { command } => handle_metrics_command(command).await?,
+        Commands::Agent { server, token } => rstat_agent::Runner::new(server, token).run().await,
+        Commands::Agents { command } => handle_agents_command(command).await?,
+        Commands::Maintenance { command } => handle_maintenance_command(command).await?,
+        Commands::Service { command } => handle_service_command(command).await?,
     }
 
     Ok(())
@@ -73,24 +93,34 @@ async fn start() -> Result<(), anyhow::Error> {
         }
     }
     
-    let (result_tx, result_rx) = mpsc::channel(100);
+    let notifications = config_loader.load_notifications().unwrap_or_else(|e| {
+        warn!("Failed to load notification settings: {}", e);
+        NotificationSettings::default()
+    });
+
+    let (event_tx, event_rx) = mpsc::channel(100);
+    let aggregator = Arc::new(Aggregator::new(pool.clone()));
+    let scheduler = Scheduler::new(pool.clone(), event_tx, aggregator.clone()).with_limits(scheduler_limits());
     
     // Create app state
-    let state = AppState { pool: pool.clone() };
+    let state = AppState {
+        pool: pool.clone(),
+        pagerduty_webhook_secret: env::var("PAGERDUTY_WEBHOOK_SECRET").ok(),
+        scheduler: scheduler.clone(),
+    };
     
     // Start scheduler
-    let scheduler_handle = tokio::spawn({
+    let scheduler_handle = tokio::spawn(rstat_scheduler::start_scheduler(scheduler));
+    
+    // Start notifier
+    let notifier_handle = tokio::spawn({
         let cloned_db = pool.clone();
-        let cloned_tx = result_tx.clone();
         async move {
-            rstat_scheduler::start_scheduler(cloned_db, cloned_tx).await
+            start_notifier(event_rx, cloned_db, notifications).await;
         }
     });
-    
-    // Start notifier
-    let notifier_handle = tokio::spawn(async move {
-        start_notifier(result_rx).await;
-    });
+
+    start_metrics_exporter()?;
 
     // Start server
     let server_handle = tokio::spawn(async move {
@@ -98,20 +128,56 @@ async fn start() -> Result<(), anyhow::Error> {
         start_server(app).await
     });
     
-    // Wait for all components to complete
-    let (scheduler_result, server_result, notifier_result) = tokio::join!(
-        scheduler_handle,
-        server_handle,
-        notifier_handle
-    );
-    
-    scheduler_result??;
-    server_result??;
-    notifier_result?;
+    // Wait for all components to complete, or for the process to be asked to stop
+    tokio::select! {
+        (scheduler_result, server_result, notifier_result) = async {
+            tokio::join!(scheduler_handle, server_handle, notifier_handle)
+        } => {
+            scheduler_result??;
+            server_result??;
+            notifier_result?;
+        }
+        _ = shutdown_signal() => {
+            info!("Shutting down, flushing pending metrics");
+            if let Err(e) = aggregator.flush().await {
+                warn!("Failed to flush metrics on shutdown: {}", e);
+            }
+        }
+    }
 
     Ok(())
 }
 
+/// Resolves on Ctrl+C, or on SIGTERM on unix
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
 async fn seed() -> Result<(), anyhow::Error> {
     let database_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL in the environment");
 
@@ -171,6 +237,140 @@ async fn handle_metrics_command(command: &MetricsCommands) -> Result<(), anyhow:
     Ok(())
 }
 
+async fn handle_agents_command(command: &AgentCommands) -> Result<(), anyhow::Error> {
+    let database_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL in the environment");
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+    
+    // Run migrations to ensure agents table exists
+    Migrator::new(Path::new("migrations"))
+        .await?
+        .run(&pool)
+        .await?;
+
+    match command {
+        AgentCommands::Register { name, location } => {
+            let (agent, token) = rstat_agent::register(&pool, name, location).await?;
+            info!("Registered agent {} at {}", agent.name, agent.location);
+            println!("Agent ID: {}", agent.id);
+            println!("Token: {}", token);
+            println!("The token is not shown again, pass it to the agent with --token or RSTAT_AGENT_TOKEN");
+        }
+        AgentCommands::List => {
+            for agent in rstat_agent::db::list(&pool).await? {
+                let last_seen = agent.last_seen_at.map(|at| at.to_rfc3339()).unwrap_or_else(|| "never".to_string());
+                println!("{}  {}  {}  last seen {}", agent.id, agent.name, agent.location, last_seen);
+            }
+        }
+        AgentCommands::Remove { id } => {
+            let agent_id = uuid::Uuid::parse_str(id)?;
+            if rstat_agent::db::delete(&pool, agent_id).await? {
+                println!("Removed agent {}", agent_id);
+            } else {
+                eprintln!("No agent with ID {}", agent_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_service_command(command: &ServiceCommands) -> Result<(), anyhow::Error> {
+    let database_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL in the environment");
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+
+    // Run migrations to ensure service_pauses table exists
+    Migrator::new(Path::new("migrations"))
+        .await?
+        .run(&pool)
+        .await?;
+
+    match command {
+        ServiceCommands::Pause { id } => {
+            let service_id = uuid::Uuid::parse_str(id)?;
+            match rstat_service::pause(&pool, service_id).await? {
+                Some(_) => println!("Paused service {}", service_id),
+                None => eprintln!("No service with ID {}", service_id),
+            }
+        }
+        ServiceCommands::Resume { id } => {
+            let service_id = uuid::Uuid::parse_str(id)?;
+            match rstat_service::resume(&pool, service_id).await? {
+                Some(status) => println!("Resumed service {}, now {}", service_id, status),
+                None => eprintln!("No service with ID {}", service_id),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_maintenance_command(command: &MaintenanceCommands) -> Result<(), anyhow::Error> {
+    let database_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL in the environment");
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+
+    // Run migrations to ensure maintenance_windows table exists
+    Migrator::new(Path::new("migrations"))
+        .await?
+        .run(&pool)
+        .await?;
+
+    match command {
+        MaintenanceCommands::Add { name, service, tag, starts_at, ends_at, duration_minutes, cron, timezone } => {
+            let starts_at = starts_at.unwrap_or_else(chrono::Utc::now);
+            let recurrence = cron.as_ref().map(|cron| Recurrence {
+                cron: cron.clone(),
+                duration_minutes: duration_minutes.unwrap_or_default(),
+                timezone: timezone.clone(),
+            });
+            // A one-off window can be given a duration instead of an end
+            let ends_at = match (ends_at, &recurrence, duration_minutes) {
+                (None, None, Some(minutes)) => Some(starts_at + chrono::Duration::minutes(*minutes as i64)),
+                _ => *ends_at,
+            };
+
+            let settings = MaintenanceSettings {
+                name: name.clone(),
+                service_id: service.as_deref().map(uuid::Uuid::parse_str).transpose()?,
+                tag: tag.clone(),
+                starts_at,
+                ends_at,
+                recurrence,
+            };
+            let window = rstat_maintenance::create(&pool, &settings).await?;
+            println!("Maintenance window ID: {}", window.id);
+        }
+        MaintenanceCommands::List => {
+            for window in rstat_maintenance::db::list(&pool).await? {
+                let settings = &window.settings;
+                let scope = match (&settings.service_id, &settings.tag) {
+                    (Some(service_id), _) => format!("service {}", service_id),
+                    (None, Some(tag)) => format!("tag {}", tag),
+                    (None, None) => "all services".to_string(),
+                };
+                let until = settings.ends_at.map(|at| at.to_rfc3339()).unwrap_or_else(|| "forever".to_string());
+                let when = match &settings.recurrence {
+                    Some(recurrence) => format!(
+                        "every \"{}\" for {} minutes, from {} until {}",
+                        recurrence.cron, recurrence.duration_minutes, settings.starts_at.to_rfc3339(), until
+                    ),
+                    None => format!("from {} until {}", settings.starts_at.to_rfc3339(), until),
+                };
+                println!("{}  {}  {}  {}", window.id, settings.name, scope, when);
+            }
+        }
+        MaintenanceCommands::Remove { id } => {
+            let window_id = uuid::Uuid::parse_str(id)?;
+            if rstat_maintenance::db::delete(&pool, window_id).await? {
+                println!("Removed maintenance window {}", window_id);
+            } else {
+                eprintln!("No maintenance window with ID {}", window_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 async fn handle_config_command(command: &ConfigCommands) -> Result<(), anyhow::Error> {
     let database_url = env::var("DATABASE_URL").expect("Expected DATABASE_URL in the environment");
     let pool = sqlx::PgPool::connect(&database_url).await?;
@@ -260,10 +460,93 @@ async fn handle_config_command(command: &ConfigCommands) -> Result<(), anyhow::E
     Ok(())
 }
 
-async fn start_notifier(mut result_rx: tokio::sync::mpsc::Receiver<String>) {
-    while let Some(result) = result_rx.recv().await {
-        info!("Notification: {}", result);
+async fn start_notifier(event_rx: mpsc::Receiver<Event>, db: sqlx::PgPool, notifications: NotificationSettings) {
+    let mut dispatcher = Dispatcher::new();
+    dispatcher.register(LogNotifier);
+    for webhook in notifications.webhooks {
+        dispatcher.register(WebhookNotifier::new(webhook));
+    }
+    for chat in notifications.chat {
+        dispatcher.register(ChatNotifier::new(chat, notifications.dashboard_url.clone()));
+    }
+    for pagerduty in notifications.pagerduty {
+        dispatcher.register(PagerDutyNotifier::new(pagerduty, notifications.dashboard_url.clone()));
+    }
+
+    let mailer = match notifications.smtp.as_ref().map(Mailer::new) {
+        Some(Ok(mailer)) => Some(mailer),
+        Some(Err(e)) => {
+            warn!("Invalid SMTP configuration, email notifications are disabled: {}", e);
+            None
+        }
+        None => None,
+    };
+
+    let mut service_notifier = ServiceNotifier::new(db.clone()).with_dashboard_url(notifications.dashboard_url);
+    match mailer {
+        Some(mailer) => {
+            for email in notifications.email.iter().cloned() {
+                dispatcher.register(EmailNotifier::new(email, mailer.clone()));
+            }
+            Digest::new(db, mailer.clone(), notifications.email).start();
+            service_notifier = service_notifier.with_mailer(mailer);
+        }
+        None if !notifications.email.is_empty() => {
+            warn!("Email recipients are configured but no SMTP server is set");
+        }
+        None => {}
+    }
+    dispatcher.register(service_notifier);
+
+    info!("Starting notifier with {} channels", dispatcher.len());
+    dispatcher.run(event_rx).await;
+}
+
+/// Limits of the scheduler, defaults can be overridden from the environment
+fn scheduler_limits() -> Limits {
+    let defaults = Limits::default();
+    let limits = Limits {
+        max_concurrent_checks: env_or("MAX_CONCURRENT_CHECKS", defaults.max_concurrent_checks),
+        max_checks_per_host: env_or("MAX_CHECKS_PER_HOST", defaults.max_checks_per_host),
+        jitter: env_or("SCHEDULE_JITTER", defaults.jitter),
+    };
+    if !(0.0..=MAX_JITTER).contains(&limits.jitter) {
+        warn!("SCHEDULE_JITTER must be between 0 and {}, using {}", MAX_JITTER, limits.jitter.clamp(0.0, MAX_JITTER));
     }
+    limits
+}
+
+/// The value of an environment variable, or `default` when it is not set or can't be parsed
+fn env_or<T: FromStr + Display>(name: &str, default: T) -> T
+where
+    T::Err: Display,
+{
+    match env::var(name) {
+        Ok(value) => value.parse().unwrap_or_else(|e| {
+            warn!("Ignoring {}={}: {}, using {}", name, value, e, default);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Serve Prometheus metrics on their own port, so they don't clash with the JSON `/metrics` routes
+fn start_metrics_exporter() -> Result<(), anyhow::Error> {
+    let port: u16 = env::var("METRICS_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(9464);
+
+    PrometheusBuilder::new()
+        .with_http_listener(([0, 0, 0, 0], port))
+        .set_buckets_for_metric(
+            Matcher::Full(telemetry::CHECK_DURATION.to_string()),
+            telemetry::CHECK_DURATION_BUCKETS,
+        )?
+        .install()?;
+
+    info!("Prometheus metrics available on 0.0.0.0:{}/metrics", port);
+    Ok(())
 }
 
 async fn start_server(app: axum::Router) -> Result<(), anyhow::Error> {
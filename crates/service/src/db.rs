@@ -1,93 +1,236 @@
 use std::time::Duration;
 
 use tracing::{info, warn};
-use chrono::{DateTime, Utc};
-use rstat_core::{Service, Kind};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rstat_core::{
+    CheckOutcome, Kind, NotificationSettings, Schedule, Service, ServiceOptions, ServiceState, ServiceStatus,
+    StatusTransition,
+};
+
+/// ServiceRow mirrors a row of the services table.
+struct ServiceRow {
+    id: uuid::Uuid,
+    name: String,
+    interval: i16,
+    config: Option<serde_json::Value>,
+    next_run: NaiveDateTime,
+    status: String,
+    consecutive_failures: i32,
+    consecutive_successes: i32,
+    status_changed_at: NaiveDateTime,
+    failure_threshold: i32,
+    success_threshold: i32,
+    notifications: serde_json::Value,
+    slo: Option<serde_json::Value>,
+    locations: Vec<String>,
+    tags: Vec<String>,
+    schedule: Option<serde_json::Value>,
+}
+
+impl From<ServiceRow> for Service {
+    fn from(row: ServiceRow) -> Self {
+        let kind = match row.config {
+            Some(config) => {
+                let cfg: Kind = serde_json::from_value(config).unwrap();
+                Some(cfg)
+            }
+            None => {
+                warn!("Service {} has no config", row.id);
+                None
+            }
+        }
+        .unwrap();
+
+        Service {
+            id: row.id,
+            name: row.name,
+            kind,
+            interval: Duration::from_secs(row.interval as u64),
+            next_run: row.next_run.and_utc(),
+            options: ServiceOptions {
+                failure_threshold: row.failure_threshold as u32,
+                success_threshold: row.success_threshold as u32,
+                notifications: serde_json::from_value(row.notifications).unwrap_or_else(|err| {
+                    warn!("Service {} has invalid notification settings: {}", row.id, err);
+                    NotificationSettings::default()
+                }),
+                slo: row.slo.and_then(|slo| {
+                    serde_json::from_value(slo)
+                        .map_err(|err| warn!("Service {} has an invalid SLO: {}", row.id, err))
+                        .ok()
+                }),
+                locations: row.locations,
+                tags: row.tags,
+                schedule: row.schedule.and_then(|schedule| {
+                    serde_json::from_value(schedule)
+                        .map_err(|err| warn!("Service {} has an invalid schedule: {}", row.id, err))
+                        .ok()
+                }),
+            },
+            state: ServiceState {
+                status: row.status.into(),
+                consecutive_failures: row.consecutive_failures as u32,
+                consecutive_successes: row.consecutive_successes as u32,
+                changed_at: row.status_changed_at.and_utc(),
+            },
+        }
+    }
+}
 
 pub async fn all(pool: &sqlx::PgPool) -> Result<Vec<Service>, sqlx::Error> {
-    let rows = sqlx::query!("SELECT id, name, kind, interval, config, next_run FROM services")
+    let rows = sqlx::query_as!(
+        ServiceRow,
+        r#"
+        SELECT id, name, interval, config, next_run, status, consecutive_failures,
+               consecutive_successes, status_changed_at, failure_threshold, success_threshold,
+               notifications, slo, locations, tags, schedule
+        FROM services
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Service::from).collect())
+}
+
+/// When each service checked by the scheduler runs next. Services with locations are
+/// left to the agents there, paused services are not checked.
+pub async fn next_runs(pool: &sqlx::PgPool) -> Result<Vec<(uuid::Uuid, DateTime<Utc>)>, sqlx::Error> {
+    let rows = sqlx::query!("SELECT id, next_run FROM services WHERE cardinality(locations) = 0 AND status <> 'PAUSED'")
         .fetch_all(pool)
         .await?;
 
-    let svc: Vec<Service> = rows
-        .iter()
-        .map(|row| {
-            let kind = match &row.config {
-                Some(config) => {
-                    let cfg: Kind = serde_json::from_value(config.clone()).unwrap();
-                    Some(cfg)
-                }
-                None => {
-                    warn!("Service {} has no config", row.id);
-                    None
-                }
-            }
-            .unwrap();
-
-            Service {
-                id: row.id,
-                name: row.name.clone(),
-                kind,
-                interval: Duration::from_secs(row.interval as u64),
-                next_run: row.next_run.and_utc(),
-            }
-        })
-        .collect();
+    Ok(rows.into_iter().map(|row| (row.id, row.next_run.and_utc())).collect())
+}
+
+/// Claim those of the given services that are due at `now`, pushing their next run to
+/// `lease_until` so no other instance picks them up. Rows claimed by a concurrent
+/// instance are skipped.
+///
+/// Claimed services keep the `next_run` they were due at.
+pub async fn claim(
+    pool: &sqlx::PgPool,
+    ids: &[uuid::Uuid],
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+) -> Result<Vec<Service>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        ServiceRow,
+        r#"
+        WITH due AS (
+            SELECT id, next_run FROM services
+            WHERE id = ANY($1) AND next_run <= $2 AND cardinality(locations) = 0 AND status <> 'PAUSED'
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE services s SET next_run = $3
+        FROM due
+        WHERE s.id = due.id
+        RETURNING s.id, s.name, s.interval, s.config, due.next_run, s.status, s.consecutive_failures,
+                  s.consecutive_successes, s.status_changed_at, s.failure_threshold, s.success_threshold,
+                  s.notifications, s.slo, s.locations, s.tags, s.schedule
+        "#,
+        ids,
+        now.naive_utc(),
+        lease_until.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
 
-    Ok(svc)
+    Ok(rows.into_iter().map(Service::from).collect())
+}
+
+/// Services checked by the agents at a location, leaving out paused ones
+pub async fn for_location(pool: &sqlx::PgPool, location: &str) -> Result<Vec<Service>, sqlx::Error> {
+    let rows = sqlx::query_as!(
+        ServiceRow,
+        r#"
+        SELECT id, name, interval, config, next_run, status, consecutive_failures,
+               consecutive_successes, status_changed_at, failure_threshold, success_threshold,
+               notifications, slo, locations, tags, schedule
+        FROM services
+        WHERE $1 = ANY(locations) AND status <> 'PAUSED'
+        "#,
+        location
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Service::from).collect())
 }
 
 pub async fn get(pool: &sqlx::PgPool, id: String) -> Result<Service, anyhow::Error> {
     let uuid = uuid::Uuid::parse_str(&id)?;
-    let record = sqlx::query!(
-        "SELECT id, name, kind, interval, config, next_run FROM services WHERE id = $1",
+    let row = sqlx::query_as!(
+        ServiceRow,
+        r#"
+        SELECT id, name, interval, config, next_run, status, consecutive_failures,
+               consecutive_successes, status_changed_at, failure_threshold, success_threshold,
+               notifications, slo, locations, tags, schedule
+        FROM services
+        WHERE id = $1
+        "#,
         uuid
     )
     .fetch_one(pool)
     .await?;
 
-    let kind = match record.config {
-        Some(config) => {
-            let cfg: Kind = serde_json::from_value(config).unwrap();
-            Some(cfg)
-        }
-        None => None,
-    }
-    .unwrap();
-
-    let svc = Service {
-        id: uuid,
-        name: record.name,
-        kind,
-        interval: Duration::from_secs(record.interval as u64),
-        next_run: record.next_run.and_utc(),
-    };
-
-    Ok(svc)
+    Ok(row.into())
 }
 
+/// Insert a new service. A `paused` service is stored with its pause in the same
+/// transaction, so the scheduler never sees it before it is paused.
 pub async fn create(
     pool: &sqlx::PgPool,
     name: &str,
     kind: Kind,
     interval: Duration,
+    options: &ServiceOptions,
+    paused: bool,
 ) -> Result<uuid::Uuid, sqlx::Error> {
     let id = uuid::Uuid::new_v4();
     let interval_secs = interval.as_secs();
     let kind_str: String = kind.clone().into();
     let config = serde_json::to_value(kind).unwrap();
+    let notifications = serde_json::to_value(&options.notifications).unwrap();
+    let slo = options.slo.as_ref().map(|slo| serde_json::to_value(slo).unwrap());
+    let schedule = options.schedule.as_ref().map(|schedule| serde_json::to_value(schedule).unwrap());
+    let next_run = first_run(options);
+    let now = Utc::now();
+    let status = if paused { ServiceStatus::Paused } else { ServiceStatus::Unknown };
 
+    let mut tx = pool.begin().await?;
     let svc = sqlx::query!(
-        "INSERT INTO services (id, name, kind, interval, config) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        "INSERT INTO services (id, name, kind, interval, config, failure_threshold, success_threshold, notifications, slo, locations, tags, schedule, next_run, status, status_changed_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15) RETURNING id",
         id,
         name,
         kind_str,
         interval_secs as i64,
-        config
+        config,
+        options.failure_threshold as i32,
+        options.success_threshold as i32,
+        notifications,
+        slo,
+        &options.locations,
+        &options.tags,
+        schedule,
+        next_run.naive_utc(),
+        status.to_string(),
+        now.naive_utc()
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
+    if paused {
+        sqlx::query!(
+            "INSERT INTO service_pauses (service_id, previous_status, paused_at) VALUES ($1, $2, $3)",
+            svc.id,
+            ServiceStatus::Unknown.to_string(),
+            now.naive_utc()
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
 
+    tx.commit().await?;
     Ok(svc.id)
 }
 
@@ -97,14 +240,26 @@ pub async fn bulk_create(pool: &sqlx::PgPool, services: &Vec<Service>) -> Result
         let interval_secs = service.interval.as_secs();
         let kind_str: String = service.kind.clone().into();
         let config = serde_json::to_value(service.kind.clone()).unwrap();
+        let notifications = serde_json::to_value(&service.options.notifications).unwrap();
+        let slo = service.options.slo.as_ref().map(|slo| serde_json::to_value(slo).unwrap());
+        let schedule = service.options.schedule.as_ref().map(|schedule| serde_json::to_value(schedule).unwrap());
+        let next_run = first_run(&service.options);
 
         sqlx::query!(
-            "INSERT INTO services (id, name, kind, interval, config) VALUES ($1, $2, $3, $4, $5)",
+            "INSERT INTO services (id, name, kind, interval, config, failure_threshold, success_threshold, notifications, slo, locations, tags, schedule, next_run) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
             id,
             service.name,
             kind_str,
             interval_secs as i64,
-            config
+            config,
+            service.options.failure_threshold as i32,
+            service.options.success_threshold as i32,
+            notifications,
+            slo,
+            &service.options.locations,
+            &service.options.tags,
+            schedule,
+            next_run.naive_utc()
         )
         .execute(pool)
         .await?;
@@ -114,6 +269,13 @@ pub async fn bulk_create(pool: &sqlx::PgPool, services: &Vec<Service>) -> Result
     Ok(())
 }
 
+/// When a new service is first checked: right away, unless its schedule says otherwise.
+/// Schedules that never match are rejected when the service is created.
+fn first_run(options: &ServiceOptions) -> DateTime<Utc> {
+    let now = Utc::now();
+    options.schedule.as_ref().and_then(|schedule| schedule.first_run(now)).unwrap_or(now)
+}
+
 pub async fn delete(pool: &sqlx::PgPool, id: uuid::Uuid) -> Result<(), anyhow::Error> {
     sqlx::query!("DELETE FROM services WHERE id = $1", id)
         .execute(pool)
@@ -137,4 +299,311 @@ pub async fn update_next_run(
     .await?;
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Store the state of a service after a check. Services paused in the meantime stay paused,
+/// returns whether the state was stored.
+pub async fn update_state(
+    pool: &sqlx::PgPool,
+    service_id: uuid::Uuid,
+    state: &ServiceState,
+) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query!(
+        r#"
+        UPDATE services
+        SET status = $1, consecutive_failures = $2, consecutive_successes = $3, status_changed_at = $4
+        WHERE id = $5 AND status <> 'PAUSED'
+        "#,
+        state.status.to_string(),
+        state.consecutive_failures as i32,
+        state.consecutive_successes as i32,
+        state.changed_at.naive_utc(),
+        service_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(updated.rows_affected() > 0)
+}
+
+/// Apply the outcome of a check performed by an agent at `location` to the state of the
+/// location, and the service to it, see `ServiceState::follow`. Returns the new service
+/// state and the transition it went through, None when the service is paused or does not exist.
+///
+/// The service row is held until both are stored, so reports from other locations apply
+/// one after the other. Locations that have not reported for `stale_after` are left out.
+pub async fn apply_location_outcome(
+    pool: &sqlx::PgPool,
+    service_id: uuid::Uuid,
+    location: &str,
+    outcome: CheckOutcome,
+    options: &ServiceOptions,
+    stale_after: chrono::Duration,
+) -> Result<Option<(ServiceState, Option<StatusTransition>)>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(row) = sqlx::query!(
+        r#"
+        SELECT status, consecutive_failures, consecutive_successes, status_changed_at
+        FROM services WHERE id = $1 FOR UPDATE
+        "#,
+        service_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+    let mut state = ServiceState {
+        status: row.status.into(),
+        consecutive_failures: row.consecutive_failures as u32,
+        consecutive_successes: row.consecutive_successes as u32,
+        changed_at: row.status_changed_at.and_utc(),
+    };
+    if state.status == ServiceStatus::Paused {
+        return Ok(None);
+    }
+
+    let mut reporting = sqlx::query!(
+        r#"
+        SELECT status, consecutive_failures, consecutive_successes, status_changed_at
+        FROM service_location_states WHERE service_id = $1 AND location = $2
+        "#,
+        service_id,
+        location
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .map_or_else(ServiceState::default, |row| ServiceState {
+        status: row.status.into(),
+        consecutive_failures: row.consecutive_failures as u32,
+        consecutive_successes: row.consecutive_successes as u32,
+        changed_at: row.status_changed_at.and_utc(),
+    });
+    reporting.apply(outcome, options);
+
+    let now = Utc::now();
+    sqlx::query!(
+        r#"
+        INSERT INTO service_location_states
+            (service_id, location, status, consecutive_failures, consecutive_successes, status_changed_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (service_id, location) DO UPDATE SET
+            status = EXCLUDED.status,
+            consecutive_failures = EXCLUDED.consecutive_failures,
+            consecutive_successes = EXCLUDED.consecutive_successes,
+            status_changed_at = EXCLUDED.status_changed_at,
+            updated_at = EXCLUDED.updated_at
+        "#,
+        service_id,
+        location,
+        reporting.status.to_string(),
+        reporting.consecutive_failures as i32,
+        reporting.consecutive_successes as i32,
+        reporting.changed_at.naive_utc(),
+        now.naive_utc()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let others: Vec<ServiceStatus> = sqlx::query_scalar!(
+        r#"
+        SELECT status FROM service_location_states
+        WHERE service_id = $1 AND location <> $2 AND location = ANY($3) AND updated_at > $4
+        "#,
+        service_id,
+        location,
+        &options.locations,
+        (now - stale_after).naive_utc()
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(ServiceStatus::from)
+    .collect();
+
+    let transition = state.follow(&reporting, &others);
+    sqlx::query!(
+        r#"
+        UPDATE services
+        SET status = $1, consecutive_failures = $2, consecutive_successes = $3, status_changed_at = $4
+        WHERE id = $5
+        "#,
+        state.status.to_string(),
+        state.consecutive_failures as i32,
+        state.consecutive_successes as i32,
+        state.changed_at.naive_utc(),
+        service_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some((state, transition)))
+}
+
+/// Pause a service, it is not checked until resumed. Returns the service's status, None
+/// when it does not exist.
+///
+/// Pausing a paused service changes nothing.
+pub async fn pause(
+    pool: &sqlx::PgPool,
+    service_id: uuid::Uuid,
+    at: DateTime<Utc>,
+) -> Result<Option<ServiceStatus>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(row) = sqlx::query!("SELECT status FROM services WHERE id = $1 FOR UPDATE", service_id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if ServiceStatus::from(row.status.clone()) == ServiceStatus::Paused {
+        return Ok(Some(ServiceStatus::Paused));
+    }
+
+    // Locations start over once the service is resumed
+    sqlx::query!("DELETE FROM service_location_states WHERE service_id = $1", service_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO service_pauses (service_id, previous_status, paused_at) VALUES ($1, $2, $3)",
+        service_id,
+        row.status,
+        at.naive_utc()
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE services
+        SET status = 'PAUSED', consecutive_failures = 0, consecutive_successes = 0, status_changed_at = $2
+        WHERE id = $1
+        "#,
+        service_id,
+        at.naive_utc()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(ServiceStatus::Paused))
+}
+
+/// Resume a paused service with the status it had when paused, and check it right away
+/// or once its schedule allows. Returns the service's status, None when it does not exist.
+///
+/// Resuming a service that is not paused changes nothing.
+pub async fn resume(
+    pool: &sqlx::PgPool,
+    service_id: uuid::Uuid,
+    at: DateTime<Utc>,
+) -> Result<Option<ServiceStatus>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let Some(row) = sqlx::query!("SELECT status, schedule FROM services WHERE id = $1 FOR UPDATE", service_id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let status = ServiceStatus::from(row.status);
+    if status != ServiceStatus::Paused {
+        return Ok(Some(status));
+    }
+
+    let previous = sqlx::query!(
+        r#"
+        UPDATE service_pauses SET resumed_at = $2
+        WHERE service_id = $1 AND resumed_at IS NULL
+        RETURNING previous_status
+        "#,
+        service_id,
+        at.naive_utc()
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let status = previous.map_or(ServiceStatus::Unknown, |row| row.previous_status.into());
+
+    let schedule: Option<Schedule> = row.schedule.and_then(|schedule| serde_json::from_value(schedule).ok());
+    let next_run = schedule.and_then(|schedule| schedule.first_run(at)).unwrap_or(at);
+    sqlx::query!(
+        r#"
+        UPDATE services
+        SET status = $2, consecutive_failures = 0, consecutive_successes = 0, status_changed_at = $3, next_run = $4
+        WHERE id = $1
+        "#,
+        service_id,
+        status.to_string(),
+        at.naive_utc(),
+        next_run.naive_utc()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(status))
+}
+
+/// The periods a service was paused overlapping [start, end), oldest first. A service still
+/// paused has no end to its last period.
+pub async fn pauses(
+    pool: &sqlx::PgPool,
+    service_id: uuid::Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, Option<DateTime<Utc>>)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT paused_at, resumed_at FROM service_pauses
+        WHERE service_id = $1 AND paused_at < $3 AND (resumed_at IS NULL OR resumed_at > $2)
+        ORDER BY paused_at
+        "#,
+        service_id,
+        start.naive_utc(),
+        end.naive_utc()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.paused_at.and_utc(), row.resumed_at.map(|resumed_at| resumed_at.and_utc())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    use rstat_core::TcpChecker;
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    #[sqlx::test(migrations = "../../migrations")]
+    async fn test_concurrent_claims_do_not_overlap(options: PgPoolOptions, connect: PgConnectOptions) -> sqlx::Result<()> {
+        // Two pools stand for two instances sharing the database
+        let first = options.clone().connect_with(connect.clone()).await?;
+        let second = options.connect_with(connect).await?;
+
+        let mut ids = Vec::new();
+        for i in 0..50 {
+            let kind = Kind::TCP(TcpChecker::default());
+            let options = ServiceOptions::default();
+            ids.push(create(&first, &format!("service-{}", i), kind, Duration::from_secs(30), &options, false).await?);
+        }
+
+        let now = Utc::now() + chrono::Duration::seconds(1);
+        let lease_until = now + chrono::Duration::minutes(5);
+        let (claimed_first, claimed_second) =
+            tokio::join!(claim(&first, &ids, now, lease_until), claim(&second, &ids, now, lease_until));
+        let claimed_first: HashSet<uuid::Uuid> = claimed_first?.into_iter().map(|service| service.id).collect();
+        let claimed_second: HashSet<uuid::Uuid> = claimed_second?.into_iter().map(|service| service.id).collect();
+
+        assert!(claimed_first.is_disjoint(&claimed_second));
+        assert_eq!(claimed_first.len() + claimed_second.len(), ids.len());
+
+        // Claimed services are not due again until their lease runs out
+        assert!(claim(&second, &ids, now, lease_until).await?.is_empty());
+        assert_eq!(claim(&second, &ids, lease_until, lease_until + chrono::Duration::minutes(5)).await?.len(), ids.len());
+        Ok(())
+    }
+}
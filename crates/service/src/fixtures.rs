@@ -7,7 +7,7 @@ use http::{
 use uuid::Uuid;
 use chrono::Utc;
 
-use rstat_core::{Service, Kind, HttpChecker};
+use rstat_core::{Service, Kind, HttpChecker, ServiceOptions, ServiceState};
 
 pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
     let url = "http://localhost:5000/health";
@@ -27,6 +27,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
         body: Some("hello".to_string()),
         timeout: 10,
         max_retries: 10,
+        assertions: vec![],
+        allow_insecure_redirects: false,
     };
 
     let http2 = HttpChecker {
@@ -36,6 +38,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
         body: Some("hello".to_string()),
         timeout: 10,
         max_retries: 10,
+        assertions: vec![],
+        allow_insecure_redirects: false,
     };
     
     let http3 = HttpChecker {
@@ -45,6 +49,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
         body: Some("hello".to_string()),
         timeout: 10,
         max_retries: 10,
+        assertions: vec![],
+        allow_insecure_redirects: false,
     };
     
     let http4 = HttpChecker {
@@ -54,6 +60,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
         body: Some("hello".to_string()),
         timeout: 10,
         max_retries: 10,
+        assertions: vec![],
+        allow_insecure_redirects: false,
     };
     
     let now = Utc::now();
@@ -64,6 +72,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
             kind: Kind::HTTP(http1),
             interval: Duration::new(30, 0), // 30 seconds
             next_run: now,
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -71,6 +81,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
             kind: Kind::HTTP(http2),
             interval: Duration::new(60, 0), // 1 minute
             next_run: now,
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -78,6 +90,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
             kind: Kind::HTTP(http3),
             interval: Duration::new(120, 0), // 2 minutes
             next_run: now,
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
         Service {
             id: Uuid::new_v4(),
@@ -85,6 +99,8 @@ pub fn fixtures() -> Result<Vec<Service>, anyhow::Error> {
             kind: Kind::HTTP(http4),
             interval: Duration::new(10, 0), // 10 seconds
             next_run: now,
+            options: ServiceOptions::default(),
+            state: ServiceState::default(),
         },
     ];
     Ok(services)
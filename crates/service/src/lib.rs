@@ -1,7 +1,7 @@
 pub mod db;
 pub mod fixtures;
 
-use rstat_core::Service;
+use rstat_core::{Service, ServiceOptions, ServiceStatus};
 use sqlx::PgPool;
 
 /// Create a new service in the database
@@ -10,8 +10,37 @@ pub async fn create(
     name: &str,
     kind: rstat_core::Kind,
     interval: std::time::Duration,
+    options: &ServiceOptions,
 ) -> Result<uuid::Uuid, anyhow::Error> {
-    db::create(pool, name, kind, interval).await.map_err(|e| anyhow::anyhow!(e))
+    validate(options)?;
+    db::create(pool, name, kind, interval, options, false).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Create a new service that is not checked until it is resumed, see `db::create`
+pub async fn create_paused(
+    pool: &PgPool,
+    name: &str,
+    kind: rstat_core::Kind,
+    interval: std::time::Duration,
+    options: &ServiceOptions,
+) -> Result<uuid::Uuid, anyhow::Error> {
+    validate(options)?;
+    db::create(pool, name, kind, interval, options, true).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+fn validate(options: &ServiceOptions) -> Result<(), anyhow::Error> {
+    if let Some(schedule) = &options.schedule {
+        schedule.validate()?;
+    }
+    if let Some(slo) = &options.slo {
+        slo.validate()?;
+    }
+    Ok(())
+}
+
+/// Get a service from the database
+pub async fn get(pool: &PgPool, id: uuid::Uuid) -> Result<Service, anyhow::Error> {
+    db::get(pool, id.to_string()).await
 }
 
 /// Get all services from the database
@@ -19,6 +48,31 @@ pub async fn all(pool: &PgPool) -> Result<Vec<Service>, anyhow::Error> {
     db::all(pool).await.map_err(|e| anyhow::anyhow!(e))
 }
 
+/// Claim the given services for a check if they are due, see `db::claim`
+pub async fn claim(
+    pool: &PgPool,
+    ids: &[uuid::Uuid],
+    now: chrono::DateTime<chrono::Utc>,
+    lease_until: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<Service>, anyhow::Error> {
+    db::claim(pool, ids, now, lease_until).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Get the services checked by the agents at a location
+pub async fn for_location(pool: &PgPool, location: &str) -> Result<Vec<Service>, anyhow::Error> {
+    db::for_location(pool, location).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Stop checking a service until it is resumed, see `db::pause`
+pub async fn pause(pool: &PgPool, id: uuid::Uuid) -> Result<Option<ServiceStatus>, anyhow::Error> {
+    db::pause(pool, id, chrono::Utc::now()).await.map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Check a paused service again, see `db::resume`
+pub async fn resume(pool: &PgPool, id: uuid::Uuid) -> Result<Option<ServiceStatus>, anyhow::Error> {
+    db::resume(pool, id, chrono::Utc::now()).await.map_err(|e| anyhow::anyhow!(e))
+}
+
 /// Delete a service from the database
 pub async fn delete(pool: &PgPool, id: uuid::Uuid) -> Result<(), anyhow::Error> {
     db::delete(pool, id).await
@@ -0,0 +1,316 @@
+pub mod models;
+
+pub use models::*;
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use tokio::task;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use rstat_core::{Event, Leader, Service, ServiceRef, SloConfig, SLO_MONITOR_LOCK};
+use rstat_metrics::{MetricAggregate, Resolution};
+
+/// A window burn rates are measured over
+struct Window {
+    name: &'static str,
+    minutes: i64,
+    /// Rollup the window is read from, fine enough for the window's length
+    resolution: Resolution,
+}
+
+const WINDOWS: [Window; 5] = [
+    Window { name: "5m", minutes: 5, resolution: Resolution::FiveMinutes },
+    Window { name: "30m", minutes: 30, resolution: Resolution::FiveMinutes },
+    Window { name: "1h", minutes: 60, resolution: Resolution::FiveMinutes },
+    Window { name: "6h", minutes: 6 * 60, resolution: Resolution::Hourly },
+    Window { name: "3d", minutes: 3 * 24 * 60, resolution: Resolution::Hourly },
+];
+
+/// AlertPolicy fires when both of its windows spend the error budget too fast.
+///
+/// These are the multi-window, multi-burn-rate alerts of the Google SRE workbook: page
+/// when 2% of the budget goes in an hour or 5% in six hours, open a ticket when 10%
+/// goes in three days. The short window makes the alert stop soon after the burn does.
+struct AlertPolicy {
+    severity: &'static str,
+    long_window: &'static str,
+    short_window: &'static str,
+    /// Share of the whole error budget spent within the long window
+    budget_spent: f64,
+}
+
+const POLICIES: [AlertPolicy; 3] = [
+    AlertPolicy { severity: "page", long_window: "1h", short_window: "5m", budget_spent: 0.02 },
+    AlertPolicy { severity: "page", long_window: "6h", short_window: "30m", budget_spent: 0.05 },
+    AlertPolicy { severity: "ticket", long_window: "3d", short_window: "6h", budget_spent: 0.10 },
+];
+
+impl AlertPolicy {
+    /// The burn rate that spends `budget_spent` of the budget within the long window
+    fn threshold(&self, window_days: u32) -> f64 {
+        let long_minutes = WINDOWS.iter().find(|w| w.name == self.long_window).map_or(60, |w| w.minutes);
+        self.budget_spent * (window_days as f64 * 24.0 * 60.0) / long_minutes as f64
+    }
+}
+
+/// Good and total checks of an objective
+fn counts(objective: Objective, config: &SloConfig, aggregate: &MetricAggregate) -> (u64, u64) {
+    match (objective, &config.latency) {
+        (Objective::Latency, Some(latency)) => {
+            // Only successful checks have a latency
+            (aggregate.sketch.count_at_most(latency.threshold_ms), aggregate.sketch.count)
+        }
+        _ => (aggregate.successful_checks as u64, aggregate.total_checks as u64),
+    }
+}
+
+/// How many times faster than sustainable the budget is spent
+fn burn_rate(good: u64, total: u64, budget: f64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    let error_rate = 1.0 - good as f64 / total as f64;
+    error_rate / budget
+}
+
+/// Evaluate one objective from the aggregate of the SLO window and of each burn rate window
+pub fn evaluate_objective(
+    objective: Objective,
+    config: &SloConfig,
+    window: &MetricAggregate,
+    burn_windows: &HashMap<&str, MetricAggregate>,
+) -> ObjectiveReport {
+    let (target, threshold_ms) = match (objective, &config.latency) {
+        (Objective::Latency, Some(latency)) => (latency.target, Some(latency.threshold_ms)),
+        _ => (config.availability, None),
+    };
+    // Share of checks allowed to be bad
+    let budget = (1.0 - target / 100.0).max(f64::EPSILON);
+
+    let (good, total) = counts(objective, config, window);
+    let allowed = budget * total as f64;
+    let consumed = total - good;
+    let error_budget = ErrorBudget {
+        allowed,
+        consumed,
+        remaining_percentage: if allowed > 0.0 { 100.0 * (1.0 - consumed as f64 / allowed) } else { 100.0 },
+    };
+
+    let rates: HashMap<&str, f64> = burn_windows
+        .iter()
+        .map(|(name, aggregate)| {
+            let (good, total) = counts(objective, config, aggregate);
+            (*name, burn_rate(good, total, budget))
+        })
+        .collect();
+
+    let burn_rates = WINDOWS
+        .iter()
+        .map(|w| BurnRate { window: w.name.to_string(), burn_rate: rates.get(w.name).copied().unwrap_or(0.0) })
+        .collect();
+
+    let alerts = POLICIES
+        .iter()
+        .map(|policy| {
+            let threshold = policy.threshold(config.window_days);
+            let burning = |window: &str| rates.get(window).is_some_and(|rate| *rate >= threshold);
+            BurnAlert {
+                severity: policy.severity.to_string(),
+                long_window: policy.long_window.to_string(),
+                short_window: policy.short_window.to_string(),
+                threshold,
+                firing: burning(policy.long_window) && burning(policy.short_window),
+            }
+        })
+        .collect();
+
+    ObjectiveReport {
+        objective,
+        target,
+        threshold_ms,
+        sli: if total == 0 { 100.0 } else { 100.0 * good as f64 / total as f64 },
+        good_checks: good,
+        total_checks: total,
+        error_budget,
+        burn_rates,
+        alerts,
+    }
+}
+
+/// SloEvaluator computes SLO reports from the metrics of a service
+#[derive(Clone)]
+pub struct SloEvaluator {
+    pub pool: PgPool,
+}
+
+impl SloEvaluator {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn evaluate(&self, service_id: Uuid, config: &SloConfig) -> Result<SloReport, anyhow::Error> {
+        let now = Utc::now();
+        let since = now - chrono::Duration::days(config.window_days as i64);
+        let window = rstat_metrics::db::get_aggregate_since(&self.pool, service_id, Resolution::Daily, since).await?;
+
+        let mut burn_windows = HashMap::new();
+        for w in &WINDOWS {
+            let since = now - chrono::Duration::minutes(w.minutes);
+            let aggregate = rstat_metrics::db::get_aggregate_since(&self.pool, service_id, w.resolution, since).await?;
+            burn_windows.insert(w.name, aggregate);
+        }
+
+        let mut objectives = vec![Objective::Availability];
+        if config.latency.is_some() {
+            objectives.push(Objective::Latency);
+        }
+
+        Ok(SloReport {
+            service_id,
+            window_days: config.window_days,
+            objectives: objectives
+                .into_iter()
+                .map(|objective| evaluate_objective(objective, config, &window, &burn_windows))
+                .collect(),
+            evaluated_at: now,
+        })
+    }
+}
+
+/// SloMonitor periodically evaluates the SLOs of every service and emits an
+/// `ErrorBudgetBurn` event when an alerting policy starts firing.
+///
+/// Only one of the rstat instances sharing a database evaluates SLOs at a time.
+pub struct SloMonitor {
+    evaluator: SloEvaluator,
+    event_tx: mpsc::Sender<Event>,
+    leader: Leader,
+    /// Policies currently firing, so each burn is announced once
+    firing: HashSet<(Uuid, Objective, String)>,
+}
+
+impl SloMonitor {
+    pub fn new(pool: PgPool, event_tx: mpsc::Sender<Event>) -> Self {
+        Self {
+            leader: Leader::new(pool.clone(), SLO_MONITOR_LOCK),
+            evaluator: SloEvaluator::new(pool),
+            event_tx,
+            firing: HashSet::new(),
+        }
+    }
+
+    /// Evaluate SLOs every `interval` in a background task
+    pub fn start(mut self, interval: Duration) {
+        task::spawn(async move {
+            info!("Starting SLO monitor with {} second interval", interval.as_secs());
+            loop {
+                if !self.leader.is_leader().await {
+                    debug!("Another instance is evaluating SLOs");
+                } else if let Err(e) = self.check_all().await {
+                    error!("Failed to evaluate SLOs: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    async fn check_all(&mut self) -> Result<(), anyhow::Error> {
+        let services = rstat_service::all(&self.evaluator.pool).await?;
+        for service in services {
+            let Some(config) = &service.options.slo else {
+                continue;
+            };
+            match self.evaluator.evaluate(service.id, config).await {
+                Ok(report) => self.check(&service, &report).await,
+                Err(e) => warn!("Failed to evaluate SLO of service {}: {}", service.name, e),
+            }
+        }
+        Ok(())
+    }
+
+    async fn check(&mut self, service: &Service, report: &SloReport) {
+        for objective in &report.objectives {
+            for alert in &objective.alerts {
+                let key = (service.id, objective.objective, alert.long_window.clone());
+                if !alert.firing {
+                    self.firing.remove(&key);
+                    continue;
+                }
+                if !self.firing.insert(key) {
+                    continue;
+                }
+
+                let burn_rate = objective
+                    .burn_rates
+                    .iter()
+                    .find(|rate| rate.window == alert.long_window)
+                    .map_or(0.0, |rate| rate.burn_rate);
+                let event = Event::ErrorBudgetBurn {
+                    service: ServiceRef::from(service),
+                    objective: objective.objective.to_string(),
+                    severity: alert.severity.clone(),
+                    window: format!("{}/{}", alert.long_window, alert.short_window),
+                    burn_rate,
+                    threshold: alert.threshold,
+                    budget_remaining: objective.error_budget.remaining_percentage,
+                    occurred_at: report.evaluated_at,
+                };
+                if let Err(e) = self.event_tx.send(event).await {
+                    warn!("Failed to send event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstat_core::LatencyObjective;
+
+    fn aggregate(total: u32, successful: u32, latencies: &[u32]) -> MetricAggregate {
+        let mut aggregate = MetricAggregate { total_checks: total, successful_checks: successful, ..Default::default() };
+        latencies.iter().for_each(|latency| aggregate.sketch.insert(*latency));
+        aggregate
+    }
+
+    #[test]
+    fn test_error_budget_and_burn_alerts() {
+        let config = SloConfig {
+            availability: 99.9,
+            window_days: 30,
+            latency: Some(LatencyObjective { target: 95.0, threshold_ms: 300 }),
+        };
+        // 10 failures out of 20000 checks spend half of the 0.1% budget
+        let window = aggregate(20_000, 19_990, &[100; 100]);
+
+        // An hour at 2% errors burns the budget 20x too fast, above the 14.4x page threshold
+        let mut burn_windows: HashMap<&str, MetricAggregate> =
+            WINDOWS.iter().map(|w| (w.name, aggregate(1000, 1000, &[]))).collect();
+        burn_windows.insert("1h", aggregate(100, 98, &[]));
+        burn_windows.insert("5m", aggregate(10, 9, &[]));
+
+        let availability = evaluate_objective(Objective::Availability, &config, &window, &burn_windows);
+        assert!((availability.sli - 99.95).abs() < 1e-9);
+        assert!((availability.error_budget.allowed - 20.0).abs() < 1e-6);
+        assert_eq!(availability.error_budget.consumed, 10);
+        assert!((availability.error_budget.remaining_percentage - 50.0).abs() < 1e-6);
+
+        let page = &availability.alerts[0];
+        assert!((page.threshold - 14.4).abs() < 1e-9);
+        assert!(page.firing);
+        assert!(!availability.alerts[1].firing);
+        assert!((availability.alerts[2].threshold - 1.0).abs() < 1e-9);
+
+        // Latency is judged on successful checks only: 100 of them, all under 300ms
+        let latency = evaluate_objective(Objective::Latency, &config, &window, &burn_windows);
+        assert_eq!((latency.good_checks, latency.total_checks), (100, 100));
+        assert_eq!(latency.threshold_ms, Some(300));
+        assert!(latency.alerts.iter().all(|alert| !alert.firing));
+    }
+}
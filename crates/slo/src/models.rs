@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// SloReport is the state of every objective of a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloReport {
+    pub service_id: Uuid,
+    pub window_days: u32,
+    pub objectives: Vec<ObjectiveReport>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+/// Objective is what an SLO measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// Share of checks that succeed
+    Availability,
+    /// Share of successful checks faster than a threshold
+    Latency,
+}
+
+impl Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Objective::Availability => write!(f, "availability"),
+            Objective::Latency => write!(f, "latency"),
+        }
+    }
+}
+
+/// ObjectiveReport is the SLI, error budget and burn rates of one objective
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectiveReport {
+    pub objective: Objective,
+    /// Target percentage of good checks
+    pub target: f64,
+    /// Latency threshold of a latency objective
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold_ms: Option<u32>,
+    /// Percentage of good checks over the SLO window
+    pub sli: f64,
+    pub good_checks: u64,
+    pub total_checks: u64,
+    pub error_budget: ErrorBudget,
+    pub burn_rates: Vec<BurnRate>,
+    pub alerts: Vec<BurnAlert>,
+}
+
+/// ErrorBudget is how many bad checks the SLO window allows and how many were used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBudget {
+    pub allowed: f64,
+    pub consumed: u64,
+    /// Percentage of the budget left, negative once the SLO is breached
+    pub remaining_percentage: f64,
+}
+
+/// BurnRate is how fast the error budget is spent over a window.
+///
+/// A burn rate of 1 spends exactly the whole budget over the SLO window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurnRate {
+    pub window: String,
+    pub burn_rate: f64,
+}
+
+/// BurnAlert is the state of one multi-window burn rate alerting policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurnAlert {
+    pub severity: String,
+    pub long_window: String,
+    pub short_window: String,
+    pub threshold: f64,
+    /// Both windows burn at least `threshold` times too fast
+    pub firing: bool,
+}
@@ -0,0 +1,3 @@
+-- Record degraded results and TLS certificate details on healthcheck results
+ALTER TABLE healthcheck_results ADD COLUMN degraded BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE healthcheck_results ADD COLUMN certificate JSONB;
@@ -0,0 +1,7 @@
+-- Track the status of each service and the thresholds that drive its transitions
+ALTER TABLE services ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'UNKNOWN';
+ALTER TABLE services ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE services ADD COLUMN consecutive_successes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE services ADD COLUMN status_changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
+ALTER TABLE services ADD COLUMN failure_threshold INTEGER NOT NULL DEFAULT 3 CHECK (failure_threshold > 0);
+ALTER TABLE services ADD COLUMN success_threshold INTEGER NOT NULL DEFAULT 1 CHECK (success_threshold > 0);
@@ -0,0 +1,30 @@
+-- Create incidents table to record outages per service
+CREATE TABLE IF NOT EXISTS incidents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'OPEN',
+    started_at TIMESTAMP NOT NULL,
+    resolved_at TIMESTAMP,
+    duration_seconds BIGINT CHECK (duration_seconds >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (service_id) REFERENCES services(id) ON DELETE CASCADE
+);
+
+-- A service can only have one open incident at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_incidents_open_per_service ON incidents(service_id) WHERE resolved_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_incidents_service_started ON incidents(service_id, started_at DESC);
+
+-- Create incident_events table holding the timeline of each incident
+CREATE TABLE IF NOT EXISTS incident_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    incident_id UUID NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    healthcheck_result_id UUID,
+    message TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (incident_id) REFERENCES incidents(id) ON DELETE CASCADE,
+    FOREIGN KEY (healthcheck_result_id) REFERENCES healthcheck_results(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_incident_events_incident ON incident_events(incident_id, created_at);
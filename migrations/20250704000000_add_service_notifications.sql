@@ -0,0 +1,2 @@
+-- Per-service notification channels
+ALTER TABLE services ADD COLUMN notifications JSONB NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1,3 @@
+-- Record when and by whom an incident was acknowledged in a paging tool
+ALTER TABLE incidents ADD COLUMN acknowledged_at TIMESTAMP;
+ALTER TABLE incidents ADD COLUMN acknowledged_by VARCHAR(255);
@@ -0,0 +1,7 @@
+-- Track tail latency next to the daily average
+ALTER TABLE service_metrics ADD COLUMN min_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (min_latency_ms >= 0);
+ALTER TABLE service_metrics ADD COLUMN max_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (max_latency_ms >= 0);
+ALTER TABLE service_metrics ADD COLUMN p50_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p50_latency_ms >= 0);
+ALTER TABLE service_metrics ADD COLUMN p90_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p90_latency_ms >= 0);
+ALTER TABLE service_metrics ADD COLUMN p95_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p95_latency_ms >= 0);
+ALTER TABLE service_metrics ADD COLUMN p99_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p99_latency_ms >= 0);
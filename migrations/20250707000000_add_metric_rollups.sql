@@ -0,0 +1,43 @@
+-- Hourly and 5-minute rollups of healthcheck results, so short outages stay visible
+CREATE TABLE IF NOT EXISTS service_metrics_hourly (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL,
+    bucket_start TIMESTAMP NOT NULL,
+    uptime_percentage DECIMAL(5,2) NOT NULL CHECK (uptime_percentage >= 0 AND uptime_percentage <= 100),
+    average_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (average_latency_ms >= 0),
+    min_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (min_latency_ms >= 0),
+    max_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (max_latency_ms >= 0),
+    p50_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p50_latency_ms >= 0),
+    p90_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p90_latency_ms >= 0),
+    p95_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p95_latency_ms >= 0),
+    p99_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p99_latency_ms >= 0),
+    total_checks INTEGER NOT NULL DEFAULT 0 CHECK (total_checks >= 0),
+    successful_checks INTEGER NOT NULL DEFAULT 0 CHECK (successful_checks >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (service_id) REFERENCES services(id) ON DELETE CASCADE,
+    UNIQUE(service_id, bucket_start)
+);
+
+CREATE TABLE IF NOT EXISTS service_metrics_5m (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL,
+    bucket_start TIMESTAMP NOT NULL,
+    uptime_percentage DECIMAL(5,2) NOT NULL CHECK (uptime_percentage >= 0 AND uptime_percentage <= 100),
+    average_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (average_latency_ms >= 0),
+    min_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (min_latency_ms >= 0),
+    max_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (max_latency_ms >= 0),
+    p50_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p50_latency_ms >= 0),
+    p90_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p90_latency_ms >= 0),
+    p95_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p95_latency_ms >= 0),
+    p99_latency_ms INTEGER NOT NULL DEFAULT 0 CHECK (p99_latency_ms >= 0),
+    total_checks INTEGER NOT NULL DEFAULT 0 CHECK (total_checks >= 0),
+    successful_checks INTEGER NOT NULL DEFAULT 0 CHECK (successful_checks >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (service_id) REFERENCES services(id) ON DELETE CASCADE,
+    UNIQUE(service_id, bucket_start)
+);
+
+-- The UNIQUE constraints index (service_id, bucket_start); rollups scan raw results by service and time
+CREATE INDEX IF NOT EXISTS idx_healthcheck_results_service_created ON healthcheck_results(service_id, created_at);
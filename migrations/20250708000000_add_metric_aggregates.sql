@@ -0,0 +1,9 @@
+-- Running counters so metrics can be merged incrementally instead of recalculated from raw results
+ALTER TABLE service_metrics ADD COLUMN latency_sum_us BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics ADD COLUMN latency_sketch JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE service_metrics_hourly ADD COLUMN latency_sum_us BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_hourly ADD COLUMN latency_sketch JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE service_metrics_5m ADD COLUMN latency_sum_us BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_5m ADD COLUMN latency_sketch JSONB NOT NULL DEFAULT '{}';
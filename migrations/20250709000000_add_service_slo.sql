@@ -0,0 +1,2 @@
+-- Service level objectives declared for a service
+ALTER TABLE services ADD COLUMN slo JSONB;
@@ -0,0 +1,12 @@
+-- Time each bucket was known to be up or down, or had no results to tell, in milliseconds
+ALTER TABLE service_metrics ADD COLUMN up_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics ADD COLUMN down_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics ADD COLUMN unknown_ms BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE service_metrics_hourly ADD COLUMN up_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_hourly ADD COLUMN down_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_hourly ADD COLUMN unknown_ms BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE service_metrics_5m ADD COLUMN up_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_5m ADD COLUMN down_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_5m ADD COLUMN unknown_ms BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Per-phase timings of HTTP checks (DNS, connect, TLS, time to first byte, download)
+ALTER TABLE healthcheck_results ADD COLUMN timings JSONB;
@@ -0,0 +1,6 @@
+-- Classify failed checks, and count failures by kind in the metrics
+ALTER TABLE healthcheck_results ADD COLUMN error_kind TEXT;
+
+ALTER TABLE service_metrics ADD COLUMN failures JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE service_metrics_hourly ADD COLUMN failures JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE service_metrics_5m ADD COLUMN failures JSONB NOT NULL DEFAULT '{}';
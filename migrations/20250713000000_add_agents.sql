@@ -0,0 +1,16 @@
+-- Remote agents checking services from other locations
+CREATE TABLE agents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    location TEXT NOT NULL,
+    -- SHA-256 of the agent's token, the token itself is only shown once
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP
+);
+
+-- Services with locations are checked by the agents there instead of the scheduler
+ALTER TABLE services ADD COLUMN locations TEXT[] NOT NULL DEFAULT '{}';
+
+-- Where a result was checked from, NULL when checked by the scheduler
+ALTER TABLE healthcheck_results ADD COLUMN location TEXT;
@@ -0,0 +1,20 @@
+-- Tell schedulers when a service is added, removed or rescheduled, so they don't have to poll.
+-- The payload carries the next run, NULL when the scheduler no longer checks the service.
+CREATE FUNCTION notify_service_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('rstat_services', json_build_object('id', OLD.id, 'next_run', NULL)::text);
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_notify('rstat_services', json_build_object(
+        'id', NEW.id,
+        'next_run', CASE WHEN cardinality(NEW.locations) = 0 THEN NEW.next_run END
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER services_notify
+AFTER INSERT OR DELETE OR UPDATE OF next_run, locations ON services
+FOR EACH ROW EXECUTE FUNCTION notify_service_change();
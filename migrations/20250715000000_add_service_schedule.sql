@@ -0,0 +1,2 @@
+-- Cron schedule and active windows of a service
+ALTER TABLE services ADD COLUMN schedule JSONB;
@@ -0,0 +1,30 @@
+-- Tags group services, e.g. for maintenance windows covering several of them
+ALTER TABLE services ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Planned maintenance, during which checks still run but alerts are held back and
+-- downtime does not count against uptime
+CREATE TABLE maintenance_windows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- The window applies to a service, to the services with a tag, or to all services when both are NULL
+    service_id UUID REFERENCES services(id) ON DELETE CASCADE,
+    tag TEXT,
+    starts_at TIMESTAMP NOT NULL,
+    -- End of a one-off window, or when a recurring window stops recurring (NULL for never)
+    ends_at TIMESTAMP,
+    -- Cron expression, duration and time zone of a recurring window
+    recurrence JSONB,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (service_id IS NULL OR tag IS NULL),
+    CHECK (recurrence IS NOT NULL OR ends_at IS NOT NULL)
+);
+
+CREATE INDEX idx_maintenance_windows_service ON maintenance_windows(service_id);
+
+-- Results of checks performed during a maintenance window
+ALTER TABLE healthcheck_results ADD COLUMN maintenance BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Time each bucket was covered by results of checks during maintenance, in milliseconds
+ALTER TABLE service_metrics ADD COLUMN maintenance_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_hourly ADD COLUMN maintenance_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_5m ADD COLUMN maintenance_ms BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,41 @@
+-- When services were paused, to tell paused time apart from downtime in the metrics
+CREATE TABLE service_pauses (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    -- Status of the service when it was paused, restored when it is resumed
+    previous_status VARCHAR(20) NOT NULL,
+    paused_at TIMESTAMP NOT NULL,
+    resumed_at TIMESTAMP
+);
+
+-- A service can only be paused once at a time
+CREATE UNIQUE INDEX idx_service_pauses_open_per_service ON service_pauses(service_id) WHERE resumed_at IS NULL;
+
+CREATE INDEX idx_service_pauses_service_paused ON service_pauses(service_id, paused_at);
+
+-- Time each bucket the service was paused, in milliseconds
+ALTER TABLE service_metrics ADD COLUMN paused_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_hourly ADD COLUMN paused_ms BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE service_metrics_5m ADD COLUMN paused_ms BIGINT NOT NULL DEFAULT 0;
+
+-- Paused services are not checked by the scheduler either, pausing or resuming one notifies it
+CREATE OR REPLACE FUNCTION notify_service_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        PERFORM pg_notify('rstat_services', json_build_object('id', OLD.id, 'next_run', NULL)::text);
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_notify('rstat_services', json_build_object(
+        'id', NEW.id,
+        'next_run', CASE WHEN cardinality(NEW.locations) = 0 AND NEW.status <> 'PAUSED' THEN NEW.next_run END
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER services_notify ON services;
+
+CREATE TRIGGER services_notify
+AFTER INSERT OR DELETE OR UPDATE OF next_run, locations, status ON services
+FOR EACH ROW EXECUTE FUNCTION notify_service_change();
@@ -0,0 +1,12 @@
+-- When a bucket was last rebuilt from the raw results, so aggregates pending since then are not merged on top
+ALTER TABLE service_metrics ADD COLUMN recalculated_at TIMESTAMP;
+ALTER TABLE service_metrics_hourly ADD COLUMN recalculated_at TIMESTAMP;
+ALTER TABLE service_metrics_5m ADD COLUMN recalculated_at TIMESTAMP;
+
+-- Rows stored before latency sums were kept have a sum of 0, derive it from their average latency
+UPDATE service_metrics SET latency_sum_us = average_latency_ms::BIGINT * 1000 * successful_checks
+WHERE latency_sum_us = 0 AND successful_checks > 0;
+UPDATE service_metrics_hourly SET latency_sum_us = average_latency_ms::BIGINT * 1000 * successful_checks
+WHERE latency_sum_us = 0 AND successful_checks > 0;
+UPDATE service_metrics_5m SET latency_sum_us = average_latency_ms::BIGINT * 1000 * successful_checks
+WHERE latency_sum_us = 0 AND successful_checks > 0;
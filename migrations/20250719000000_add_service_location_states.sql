@@ -0,0 +1,11 @@
+-- State of a service as seen from each agent location, so every location keeps its own streaks
+CREATE TABLE service_location_states (
+    service_id UUID NOT NULL REFERENCES services(id) ON DELETE CASCADE,
+    location TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'UNKNOWN',
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    consecutive_successes INTEGER NOT NULL DEFAULT 0,
+    status_changed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (service_id, location)
+);